@@ -0,0 +1,202 @@
+//! code generation for `#[derive(JavaEnum)]`, which maps a fieldless rust enum onto a java enum's constants
+
+use crate::java_wrapper::java_class_name;
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Fields, Lit, Meta, Variant};
+
+/// `NANOSECONDS` for `Nanoseconds`, `HALF_EVEN` for `HalfEven`, `HTTP_PROXY` for `HTTPProxy`
+fn screaming_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut rval = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if i > 0 && ch.is_uppercase() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                rval.push('_');
+            }
+        }
+        rval.extend(ch.to_uppercase());
+    }
+    rval
+}
+
+/// The java constant's name, from `#[java_name = "NAME"]` or the variant's own name
+fn constant_name(variant: &Variant) -> Result<String, syn::Error> {
+    for attr in &variant.attrs {
+        if attr.path.is_ident("java_name") {
+            return match attr.parse_meta()? {
+                Meta::NameValue(nv) => match nv.lit {
+                    Lit::Str(name) => Ok(name.value()),
+                    lit => Err(syn::Error::new_spanned(lit, "expected a string literal")),
+                },
+                meta => Err(syn::Error::new_spanned(
+                    meta,
+                    "expected #[java_name = \"CONSTANT_NAME\"]",
+                )),
+            };
+        }
+    }
+    Ok(screaming_snake_case(&variant.ident.to_string()))
+}
+
+pub fn derive_java_enum(input: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let class_name = java_class_name(input, "JavaEnum")?;
+    let signature = format!("L{};", class_name);
+
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[derive(JavaEnum)] only works on an enum",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "#[derive(JavaEnum)] needs an enum without generic parameters",
+        ));
+    }
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "#[derive(JavaEnum)] needs variants without fields",
+            ));
+        }
+    }
+
+    let ty = &input.ident;
+    let idents: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
+    let indices: Vec<usize> = (0..idents.len()).collect();
+    let names = variants
+        .iter()
+        .map(constant_name)
+        .collect::<Result<Vec<String>, syn::Error>>()?;
+
+    // an enum without variants has no value to convert, but still needs a body for from_index
+    let from_index = if idents.is_empty() {
+        quote! { unreachable!("{} has no variants", index) }
+    } else {
+        quote! {
+            match index {
+                #(#indices => #ty::#idents,)*
+                _ => panic!("no variant {} in {}", index, stringify!(#ty)),
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl jni_boilerplate_helper::java_enum::JavaEnum for #ty {
+            const JAVA_NAMES: &'static [&'static str] = &[#(#names),*];
+
+            fn index(self) -> usize {
+                match self {
+                    #(#ty::#idents => #indices,)*
+                }
+            }
+
+            fn from_index(index: usize) -> Self {
+                #from_index
+            }
+
+            fn constants() -> &'static jni_boilerplate_helper::java_enum::EnumConstants {
+                static CONSTANTS: jni_boilerplate_helper::java_enum::EnumConstants =
+                    jni_boilerplate_helper::java_enum::EnumConstants::new();
+                &CONSTANTS
+            }
+        }
+
+        impl jni_boilerplate_helper::JValueNonScalar for #ty {}
+
+        impl<'a: 'b, 'b> jni_boilerplate_helper::local_frame::LeaveFrame<'a, 'b> for #ty {
+            type Rest = #ty;
+
+            fn leave_frame(self) -> (jni::objects::JObject<'a>, #ty) {
+                (jni::objects::JObject::null(), self)
+            }
+
+            fn enter_frame(_jni_env: &'b jni::JNIEnv<'a>, _object: jni::objects::JObject<'a>, rest: #ty) -> Self {
+                rest
+            }
+        }
+
+        impl jni_boilerplate_helper::JavaClassNameFor for #ty {
+            fn java_class_name() -> &'static str {
+                #class_name
+            }
+        }
+
+        impl jni_boilerplate_helper::JavaSignatureFor for #ty {
+            fn signature_for() -> String {
+                String::from(#signature)
+            }
+        }
+
+        impl jni_boilerplate_helper::JavaSignatureFor for &#ty {
+            fn signature_for() -> String {
+                String::from(#signature)
+            }
+        }
+
+        impl<'a, 'b> jni_boilerplate_helper::ConvertRustToJValue<'a, 'b> for #ty {
+            type T = jni::sys::jobject;
+            fn into_temporary(
+                &self,
+                je: &'b jni::JNIEnv<'a>,
+            ) -> Result<jni::sys::jobject, jni::errors::Error> {
+                Ok(jni_boilerplate_helper::unwrap_jobject(
+                    jni_boilerplate_helper::java_enum::enum_constant(je, *self)?,
+                ))
+            }
+
+            fn temporary_into_jvalue(tmp: &Self::T) -> jni::objects::JValue<'a> {
+                jni::objects::JValue::from(jni_boilerplate_helper::wrap_jobject(*tmp))
+            }
+        }
+
+        impl<'a, 'b> jni_boilerplate_helper::ConvertRustToJValue<'a, 'b> for &#ty {
+            type T = jni::sys::jobject;
+            fn into_temporary(
+                &self,
+                je: &'b jni::JNIEnv<'a>,
+            ) -> Result<jni::sys::jobject, jni::errors::Error> {
+                Ok(jni_boilerplate_helper::unwrap_jobject(
+                    jni_boilerplate_helper::java_enum::enum_constant(je, **self)?,
+                ))
+            }
+
+            fn temporary_into_jvalue(tmp: &Self::T) -> jni::objects::JValue<'a> {
+                jni::objects::JValue::from(jni_boilerplate_helper::wrap_jobject(*tmp))
+            }
+        }
+
+        impl<'a, 'b> jni_boilerplate_helper::ConvertJValueToRust<'a, 'b> for #ty {
+            fn to_rust(
+                je: &'b jni::JNIEnv<'a>,
+                val: jni::objects::JValue<'a>,
+            ) -> Result<Self, jni::errors::Error> {
+                jni_boilerplate_helper::java_enum::enum_from_java(je, val)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::screaming_snake_case;
+
+    #[test]
+    fn constant_names() {
+        assert_eq!("NANOSECONDS", screaming_snake_case("Nanoseconds"));
+        assert_eq!("HALF_EVEN", screaming_snake_case("HalfEven"));
+        assert_eq!("HTTP_PROXY", screaming_snake_case("HTTPProxy"));
+        assert_eq!("UTF8", screaming_snake_case("UTF8"));
+        assert_eq!("DIRECT", screaming_snake_case("DIRECT"));
+    }
+}
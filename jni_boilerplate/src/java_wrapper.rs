@@ -0,0 +1,244 @@
+//! code generation for `#[derive(JavaWrapper)]`, the attribute-driven replacement for `jni_wrapper_cliche_impl!`
+
+use proc_macro2::TokenStream;
+use syn::spanned::Spanned;
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericArgument, Lifetime, LitStr, Meta, NestedMeta,
+    PathArguments, Type,
+};
+
+/// The slash-separated class name from `#[java_class("com.example.Widget")]`.
+/// `derive` names the derive which needs it, for the error.
+pub(crate) fn java_class_name(input: &DeriveInput, derive: &str) -> Result<String, syn::Error> {
+    let mut rval = None;
+    for attr in &input.attrs {
+        if attr.path.is_ident("java_class") {
+            if rval.is_some() {
+                return Err(syn::Error::new_spanned(attr, "duplicate #[java_class]"));
+            }
+            let name: LitStr = attr.parse_args()?;
+            rval = Some(name.value().replace('.', "/"));
+        }
+    }
+    rval.ok_or_else(|| {
+        syn::Error::new(
+            input.ident.span(),
+            format!(
+                "#[derive({})] needs a #[java_class(\"package.path.ClassName\")] attribute",
+                derive
+            ),
+        )
+    })
+}
+
+/// `'a` and `'b` from a field declared as `jni_env: &'b jni::JNIEnv<'a>`
+fn env_lifetimes(field: &Field) -> Result<(Lifetime, Lifetime), syn::Error> {
+    let complaint = || syn::Error::new(field.ty.span(), "expected `jni_env: &'b jni::JNIEnv<'a>`");
+
+    let reference = match &field.ty {
+        Type::Reference(reference) if reference.mutability.is_none() => reference,
+        _ => return Err(complaint()),
+    };
+    let lifetime_b = reference.lifetime.clone().ok_or_else(complaint)?;
+    let last = match &*reference.elem {
+        Type::Path(path) => path.path.segments.last().ok_or_else(complaint)?,
+        _ => return Err(complaint()),
+    };
+    let lifetime_a = match &last.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Lifetime(lifetime)) => lifetime.clone(),
+            _ => return Err(complaint()),
+        },
+        _ => return Err(complaint()),
+    };
+    Ok((lifetime_a, lifetime_b))
+}
+
+/// `#[repr(C)]`, which a `PlainJavaWrapper` needs
+fn is_repr_c(input: &DeriveInput) -> bool {
+    input.attrs.iter().any(|attr| match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.path.is_ident("repr") => list.nested.iter().any(
+            |nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C")),
+        ),
+        _ => false,
+    })
+}
+
+pub fn derive_java_wrapper(input: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let class_name = java_class_name(input, "JavaWrapper")?;
+    let signature = format!("L{};", class_name);
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "#[derive(JavaWrapper)] needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "#[derive(JavaWrapper)] only works on a struct",
+            ))
+        }
+    };
+
+    let find = |name: &str| {
+        fields
+            .iter()
+            .find(|field| field.ident.as_ref().is_some_and(|ident| ident == name))
+            .ok_or_else(|| {
+                syn::Error::new(
+                    input.ident.span(),
+                    format!(
+                        "#[derive(JavaWrapper)] needs a `{}` field (java_this: AutoLocal<'a, 'b>, jni_env: &'b JNIEnv<'a>)",
+                        name
+                    ),
+                )
+            })
+    };
+    find("java_this")?;
+    let (lifetime_a, lifetime_b) = env_lifetimes(find("jni_env")?)?;
+
+    // everything else is rust-side state, which starts out as its Default
+    let extra_fields: Vec<_> = fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .filter(|ident| *ident != "java_this" && *ident != "jni_env")
+        .collect();
+
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    // a struct of nothing but the two references can stand in for its superclass's wrapper
+    let field_names: Vec<String> = fields
+        .iter()
+        .filter_map(|field| field.ident.as_ref().map(|ident| ident.to_string()))
+        .collect();
+    let plain = if is_repr_c(input) && field_names == ["java_this", "jni_env"] {
+        quote! {
+            unsafe impl #impl_generics jni_boilerplate_helper::inheritance::PlainJavaWrapper for #ty #ty_generics #where_clause {}
+        }
+    } else {
+        quote! {}
+    };
+
+    // a struct generic over nothing but its lifetimes can be named with any others, which `Global` needs
+    let lifetimes: Vec<&Lifetime> = input
+        .generics
+        .lifetimes()
+        .map(|param| &param.lifetime)
+        .collect();
+    let wrapper_for = if lifetimes.len() == 2 && input.generics.params.len() == 2 {
+        let params = &input.generics.params;
+        let others: Vec<Lifetime> = lifetimes
+            .iter()
+            .map(|lifetime| {
+                Lifetime::new(&format!("'jni_other_{}", lifetime.ident), lifetime.span())
+            })
+            .collect();
+        quote! {
+            impl<#params, #(#others),*> jni_boilerplate_helper::global::WrapperFor<#lifetime_a, #lifetime_b> for #ty<#(#others),*> #where_clause {
+                type Wrapper = #ty #ty_generics;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        #plain
+
+        #wrapper_for
+
+        impl #impl_generics jni_boilerplate_helper::inheritance::JavaObjectWrapper<#lifetime_a, #lifetime_b> for #ty #ty_generics #where_clause {
+            fn jni_env(&self) -> &#lifetime_b jni::JNIEnv<#lifetime_a> {
+                self.jni_env
+            }
+
+            fn java_object(&self) -> jni::objects::JObject<#lifetime_a> {
+                jni_boilerplate_helper::wrap_jobject(*self.java_this.as_obj())
+            }
+
+            fn into_parts(self) -> (&#lifetime_b jni::JNIEnv<#lifetime_a>, jni::objects::AutoLocal<#lifetime_a, #lifetime_b>) {
+                (self.jni_env, self.java_this)
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::JValueNonScalar for #ty #ty_generics #where_clause {}
+
+        impl #impl_generics jni_boilerplate_helper::JavaClassNameFor for #ty #ty_generics #where_clause {
+            fn java_class_name() -> &'static str {
+                #class_name
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::JavaConstructible<#lifetime_a, #lifetime_b> for #ty #ty_generics #where_clause {
+            fn wrap_jobject(
+                jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a>,
+                java_this: jni::objects::AutoLocal<#lifetime_a, #lifetime_b>,
+            ) -> Self {
+                #ty {
+                    java_this,
+                    jni_env,
+                    #(#extra_fields: Default::default(),)*
+                }
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::JavaSignatureFor for #ty #ty_generics #where_clause {
+            fn signature_for() -> String {
+                String::from(#signature)
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::JavaSignatureFor for &#ty #ty_generics #where_clause {
+            fn signature_for() -> String {
+                String::from(#signature)
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::ConvertRustToJValue<#lifetime_a, #lifetime_b> for &#ty #ty_generics #where_clause {
+            type T = jni::sys::jobject;
+            fn into_temporary(
+                &self,
+                _je: &#lifetime_b jni::JNIEnv<#lifetime_a>,
+            ) -> Result<jni::sys::jobject, jni::errors::Error> {
+                Ok(*self.java_this.as_obj())
+            }
+
+            fn temporary_into_jvalue(tmp: &Self::T) -> jni::objects::JValue<#lifetime_a> {
+                jni::objects::JValue::from(jni_boilerplate_helper::wrap_jobject(*tmp))
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::ConvertRustToJValue<#lifetime_a, #lifetime_b> for #ty #ty_generics #where_clause {
+            type T = jni::sys::jobject;
+            fn into_temporary(
+                &self,
+                _je: &#lifetime_b jni::JNIEnv<#lifetime_a>,
+            ) -> Result<jni::sys::jobject, jni::errors::Error> {
+                Ok(*self.java_this.as_obj())
+            }
+
+            fn temporary_into_jvalue(tmp: &Self::T) -> jni::objects::JValue<#lifetime_a> {
+                jni::objects::JValue::from(jni_boilerplate_helper::wrap_jobject(*tmp))
+            }
+        }
+
+        impl #impl_generics jni_boilerplate_helper::ConvertJValueToRust<#lifetime_a, #lifetime_b> for #ty #ty_generics #where_clause {
+            fn to_rust(
+                jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a>,
+                val: jni::objects::JValue<#lifetime_a>,
+            ) -> Result<Self, jni::errors::Error> {
+                Ok(<Self as jni_boilerplate_helper::JavaConstructible<#lifetime_a, #lifetime_b>>::wrap_jobject(
+                    jni_env,
+                    jni::objects::AutoLocal::new(jni_env, val.l()?),
+                ))
+            }
+        }
+    })
+}
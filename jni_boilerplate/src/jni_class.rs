@@ -0,0 +1,486 @@
+//! code generation for `#[jni_class]`, which expands body-less method declarations in an `impl`
+//! the same way `jni_instance_method!`, `jni_static_method!` and `jni_constructor!` would
+
+use crate::{constructor_tokens, instance_method_tokens, static_method_tokens};
+use jni_boilerplate_helper::overload_names::{overload_names, OverloadNaming};
+use proc_macro2::{Ident, TokenStream};
+use std::collections::{BTreeMap, HashMap};
+use syn::parse::{Parse, ParseBuffer, ParseStream};
+use syn::{
+    Attribute, GenericArgument, GenericParam, Generics, ImplItem, Lifetime, Lit, LitStr, Meta, Pat,
+    PathArguments, ReturnType, Type, Visibility,
+};
+
+/// `#[jni_class("package.path.ClassName")]`, or `#[jni_class]` to use an existing `JavaClassNameFor`,
+/// optionally followed by `overloads = "types"` (or `"arity"`, or the default `"explicit"`)
+pub struct JniClassAttr {
+    class_name: Option<String>,
+    overloads: OverloadNaming,
+}
+
+impl Parse for JniClassAttr {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let class_name = if tokens.peek(LitStr) {
+            let name: LitStr = tokens.parse()?;
+            if !tokens.is_empty() {
+                let _comma: Token![,] = tokens.parse()?;
+            }
+            Some(name.value().replace('.', "/"))
+        } else {
+            None
+        };
+
+        let mut overloads = OverloadNaming::Explicit;
+        if !tokens.is_empty() {
+            let key: Ident = tokens.parse()?;
+            if key != "overloads" {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected a class name or overloads = \"...\"",
+                ));
+            }
+            let _eq: Token![=] = tokens.parse()?;
+            let policy: LitStr = tokens.parse()?;
+            overloads = policy
+                .value()
+                .parse()
+                .map_err(|message: String| syn::Error::new(policy.span(), message))?;
+        }
+        Ok(JniClassAttr {
+            class_name,
+            overloads,
+        })
+    }
+}
+
+enum BindingKind {
+    Instance,
+    Static,
+    Constructor,
+}
+
+/// `[#[java_name = "name"]] [#[constructor] | #[static_method]] [#[implicit_env]] [#[nonvirtual = "package.Class"]] [vis] fn rust_name([&self,] name: arg_type, ...) [-> return_type];`
+struct BindingDecl {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    kind: BindingKind,
+    /// a static method or constructor that uses the current thread's env instead of a `jni_env` parameter
+    implicit_env: bool,
+    /// the (slashed) ancestor class whose implementation an instance method calls, instead of the object's own
+    nonvirtual: Option<String>,
+    rust_name: Ident,
+    java_name: String,
+    parameter_types: Vec<Type>,
+    return_type: ReturnType,
+}
+
+impl Parse for BindingDecl {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let mut attrs = Vec::new();
+        let mut java_name = None;
+        let mut constructor = false;
+        let mut static_method = false;
+        let mut implicit_env = None;
+        let mut nonvirtual = None;
+        for attr in tokens.call(Attribute::parse_outer)? {
+            if attr.path.is_ident("java_name") {
+                match attr.parse_meta()? {
+                    Meta::NameValue(nv) => match nv.lit {
+                        Lit::Str(name) => java_name = Some(name.value()),
+                        lit => {
+                            return Err(syn::Error::new_spanned(lit, "expected a string literal"))
+                        }
+                    },
+                    meta => {
+                        return Err(syn::Error::new_spanned(
+                            meta,
+                            "expected #[java_name = \"javaName\"]",
+                        ))
+                    }
+                }
+            } else if attr.path.is_ident("constructor") {
+                constructor = true;
+            } else if attr.path.is_ident("static_method") {
+                static_method = true;
+            } else if attr.path.is_ident("implicit_env") {
+                implicit_env = Some(attr);
+            } else if attr.path.is_ident("nonvirtual") {
+                match attr.parse_meta()? {
+                    Meta::NameValue(nv) => match nv.lit {
+                        Lit::Str(name) => nonvirtual = Some((attr, name.value().replace('.', "/"))),
+                        lit => {
+                            return Err(syn::Error::new_spanned(lit, "expected a string literal"))
+                        }
+                    },
+                    meta => {
+                        return Err(syn::Error::new_spanned(
+                            meta,
+                            "expected #[nonvirtual = \"package.Class\"]",
+                        ))
+                    }
+                }
+            } else {
+                attrs.push(attr);
+            }
+        }
+
+        let vis: Visibility = tokens.parse()?;
+        let _fn: Token![fn] = tokens.parse()?;
+        let rust_name: Ident = tokens.parse()?;
+
+        let args: ParseBuffer;
+        let parens = parenthesized!(args in tokens);
+        let takes_self = args.peek(Token![&]) && args.peek2(Token![self]);
+        if takes_self {
+            let _and: Token![&] = args.parse()?;
+            let _self: Token![self] = args.parse()?;
+            if !args.is_empty() {
+                let _comma: Token![,] = args.parse()?;
+            }
+        }
+        // rust will not parse an anonymous parameter, so each has a name (or `_`) which we ignore
+        let mut parameter_types = Vec::new();
+        while !args.is_empty() {
+            let _name: Pat = args.parse()?;
+            let _colon: Token![:] = args.parse()?;
+            parameter_types.push(args.parse()?);
+            if !args.is_empty() {
+                let _comma: Token![,] = args.parse()?;
+            }
+        }
+
+        let return_type: ReturnType = tokens.parse()?;
+        let _semi: Token![;] = tokens.parse()?;
+
+        let kind = match (takes_self, constructor, static_method) {
+            (true, false, false) => BindingKind::Instance,
+            (false, true, false) => BindingKind::Constructor,
+            (false, false, _) => BindingKind::Static,
+            (true, _, _) => {
+                return Err(syn::Error::new(
+                    parens.span,
+                    "a #[constructor] or #[static_method] does not take &self",
+                ))
+            }
+            (false, true, true) => {
+                return Err(syn::Error::new(
+                    rust_name.span(),
+                    "#[constructor] and #[static_method] do not mix",
+                ))
+            }
+        };
+        if let (BindingKind::Instance, Some(attr)) = (&kind, &implicit_env) {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "#[implicit_env] is for static methods and constructors; an instance method uses its object's env",
+            ));
+        }
+        let nonvirtual = match (&kind, nonvirtual) {
+            (_, None) => None,
+            (BindingKind::Instance, Some((_, class_name))) => Some(class_name),
+            (_, Some((attr, _))) => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "#[nonvirtual] is for instance methods",
+                ))
+            }
+        };
+        if let BindingKind::Constructor = kind {
+            let returns_self = match &return_type {
+                ReturnType::Default => true,
+                ReturnType::Type(_, ty) => {
+                    matches!(&**ty, Type::Path(path) if path.path.is_ident("Self"))
+                }
+            };
+            if !returns_self {
+                return Err(syn::Error::new_spanned(
+                    &return_type,
+                    "a #[constructor] returns Self",
+                ));
+            }
+        }
+
+        let java_name = java_name.unwrap_or_else(|| rust_name.to_string());
+        Ok(BindingDecl {
+            attrs,
+            vis,
+            kind,
+            implicit_env: implicit_env.is_some(),
+            nonvirtual,
+            rust_name,
+            java_name,
+            parameter_types,
+            return_type,
+        })
+    }
+}
+
+/// true when the next item in `tokens` is a `fn` declaration ending in `;` instead of a body
+fn peek_binding(tokens: ParseStream) -> bool {
+    let fork = tokens.fork();
+    let declaration = || -> Result<bool, syn::Error> {
+        fork.call(Attribute::parse_outer)?;
+        let _vis: Visibility = fork.parse()?;
+        let _fn: Token![fn] = fork.parse()?;
+        let _name: Ident = fork.parse()?;
+        let _args: ParseBuffer;
+        parenthesized!(_args in fork);
+        let _return_type: ReturnType = fork.parse()?;
+        Ok(fork.peek(Token![;]))
+    };
+    declaration().unwrap_or(false)
+}
+
+enum ClassItem {
+    Binding(BindingDecl),
+    Verbatim(Box<ImplItem>),
+}
+
+/// `impl<...> Type [where ...] { items }`
+pub struct JniClassImpl {
+    attrs: Vec<Attribute>,
+    generics: Generics,
+    self_ty: Type,
+    items: Vec<ClassItem>,
+}
+
+impl Parse for JniClassImpl {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let attrs = tokens.call(Attribute::parse_outer)?;
+        let _impl: Token![impl] = tokens.parse()?;
+        let mut generics: Generics = tokens.parse()?;
+        let self_ty: Type = tokens.parse()?;
+        generics.where_clause = tokens.parse()?;
+
+        let body: ParseBuffer;
+        braced!(body in tokens);
+        let mut items = Vec::new();
+        while !body.is_empty() {
+            if peek_binding(&body) {
+                items.push(ClassItem::Binding(body.parse()?));
+            } else {
+                items.push(ClassItem::Verbatim(Box::new(body.parse()?)));
+            }
+        }
+
+        Ok(JniClassImpl {
+            attrs,
+            generics,
+            self_ty,
+            items,
+        })
+    }
+}
+
+/// The lifetimes of the `jni_env` parameter of static methods and constructors:
+/// the first two lifetime parameters of the `impl`, like `jni_static_method!{ 'a, 'b, ... }` would be given.
+fn env_lifetimes(generics: &Generics) -> (Lifetime, Lifetime) {
+    let lifetimes: Vec<&Lifetime> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Lifetime(def) => Some(&def.lifetime),
+            _ => None,
+        })
+        .collect();
+    match lifetimes.as_slice() {
+        [] => {
+            let wildcard = Lifetime::new("'_", proc_macro2::Span::call_site());
+            (wildcard.clone(), wildcard)
+        }
+        [a] => ((*a).clone(), (*a).clone()),
+        [a, b, ..] => ((*a).clone(), (*b).clone()),
+    }
+}
+
+/// The descriptor of the java type a rust parameter type converts to, as far as the syntax tells:
+/// a wrapper `Widget<'a, 'b>` is `LWidget;`.  Only used to name overloads.
+fn parameter_descriptor(ty: &Type) -> String {
+    match ty {
+        Type::Reference(reference) => parameter_descriptor(&reference.elem),
+        Type::Paren(paren) => parameter_descriptor(&paren.elem),
+        Type::Group(group) => parameter_descriptor(&group.elem),
+        Type::Slice(slice) => format!("[{}", parameter_descriptor(&slice.elem)),
+        Type::Array(array) => format!("[{}", parameter_descriptor(&array.elem)),
+        Type::Path(path) => {
+            let last = match path.path.segments.last() {
+                Some(last) => last,
+                None => return String::from("Ljava/lang/Object;"),
+            };
+            let name = last.ident.to_string();
+            let descriptor = match name.as_str() {
+                "bool" => "Z",
+                "i8" => "B",
+                "char" => "C",
+                "i16" => "S",
+                "i32" => "I",
+                "i64" => "J",
+                "f32" => "F",
+                "f64" => "D",
+                "str" | "String" => "Ljava/lang/String;",
+                _ => "",
+            };
+            if !descriptor.is_empty() {
+                return String::from(descriptor);
+            }
+            // a type parameter's erasure, java.lang.Object unless a bound is given
+            if name == "Erased" {
+                if let PathArguments::AngleBracketed(args) = &last.arguments {
+                    let mut bound = args.args.iter().filter_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    });
+                    if let Some(bound) = bound.nth(1) {
+                        return parameter_descriptor(bound);
+                    }
+                }
+                return String::from("Ljava/lang/Object;");
+            }
+            if name == "Vec" {
+                if let PathArguments::AngleBracketed(args) = &last.arguments {
+                    for arg in &args.args {
+                        if let GenericArgument::Type(element) = arg {
+                            return format!("[{}", parameter_descriptor(element));
+                        }
+                    }
+                }
+            }
+            format!("L{};", name)
+        }
+        _ => String::from("Ljava/lang/Object;"),
+    }
+}
+
+/// Renames the overloaded declarations (those sharing a rust name) according to `naming`,
+/// then checks that every rust name in the impl is distinct.
+fn name_overloads(items: &mut [ClassItem], naming: OverloadNaming) -> Result<(), syn::Error> {
+    let mut overloads: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, item) in items.iter().enumerate() {
+        if let ClassItem::Binding(binding) = item {
+            overloads
+                .entry(binding.rust_name.to_string())
+                .or_default()
+                .push(i);
+        }
+    }
+
+    for (base, indices) in overloads {
+        let bindings: Vec<&BindingDecl> = indices
+            .iter()
+            .filter_map(|&i| match &items[i] {
+                ClassItem::Binding(binding) => Some(binding),
+                ClassItem::Verbatim(_) => None,
+            })
+            .collect();
+        let parameters: Vec<Vec<String>> = bindings
+            .iter()
+            .map(|binding| {
+                binding
+                    .parameter_types
+                    .iter()
+                    .map(parameter_descriptor)
+                    .collect()
+            })
+            .collect();
+        let names = overload_names(&base, &parameters, naming)
+            .map_err(|message| syn::Error::new(bindings[0].rust_name.span(), message))?;
+        for (&i, name) in indices.iter().zip(names) {
+            if let ClassItem::Binding(binding) = &mut items[i] {
+                binding.rust_name = Ident::new(&name, binding.rust_name.span());
+            }
+        }
+    }
+
+    let mut seen: HashMap<String, &Ident> = HashMap::new();
+    for item in items.iter() {
+        let name = match item {
+            ClassItem::Binding(binding) => &binding.rust_name,
+            ClassItem::Verbatim(item) => match &**item {
+                ImplItem::Method(method) => &method.sig.ident,
+                ImplItem::Const(constant) => &constant.ident,
+                _ => continue,
+            },
+        };
+        if seen.insert(name.to_string(), name).is_some() {
+            return Err(syn::Error::new(
+                name.span(),
+                format!("more than one item in this impl is named {}", name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn jni_class(attr: &JniClassAttr, class: &mut JniClassImpl) -> Result<TokenStream, syn::Error> {
+    name_overloads(&mut class.items, attr.overloads)?;
+    let (lifetime_a, lifetime_b) = env_lifetimes(&class.generics);
+
+    let class_name = match &attr.class_name {
+        Some(name) => quote! { #name },
+        None => quote! { <Self as jni_boilerplate_helper::JavaClassNameFor>::java_class_name() },
+    };
+
+    let items: Vec<TokenStream> = class
+        .items
+        .iter()
+        .map(|item| match item {
+            ClassItem::Verbatim(item) => quote! { #item },
+            ClassItem::Binding(binding) => match binding.kind {
+                BindingKind::Instance => instance_method_tokens(
+                    &binding.attrs,
+                    &binding.vis,
+                    &binding.rust_name,
+                    &binding.java_name,
+                    &binding.parameter_types,
+                    &binding.return_type,
+                    binding.nonvirtual.as_deref(),
+                ),
+                BindingKind::Static => static_method_tokens(
+                    &binding.attrs,
+                    &binding.vis,
+                    &lifetime_a,
+                    &lifetime_b,
+                    &binding.rust_name,
+                    &binding.java_name,
+                    &binding.parameter_types,
+                    &binding.return_type,
+                    binding.implicit_env,
+                ),
+                BindingKind::Constructor => constructor_tokens(
+                    &binding.attrs,
+                    &binding.vis,
+                    &lifetime_a,
+                    &lifetime_b,
+                    &binding.rust_name,
+                    &class_name,
+                    &binding.parameter_types,
+                    binding.implicit_env,
+                ),
+            },
+        })
+        .collect();
+
+    let attrs = &class.attrs;
+    let self_ty = &class.self_ty;
+    let (impl_generics, _, where_clause) = class.generics.split_for_impl();
+
+    let class_name_impl = match &attr.class_name {
+        Some(name) => quote! {
+            impl #impl_generics jni_boilerplate_helper::JavaClassNameFor for #self_ty #where_clause {
+                fn java_class_name() -> &'static str {
+                    #name
+                }
+            }
+        },
+        None => quote! {},
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        impl #impl_generics #self_ty #where_clause {
+            #(#items)*
+        }
+
+        #class_name_impl
+    })
+}
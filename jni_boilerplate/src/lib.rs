@@ -17,7 +17,16 @@ use proc_macro::{Span, TokenStream};
 use proc_macro2::Ident;
 use syn::parse::{Parse, ParseBuffer, ParseStream};
 use syn::token::Comma;
-use syn::{Expr, FnArg, Lifetime, ReturnType, Type, TypeTuple};
+use syn::{
+    Attribute, DeriveInput, Expr, FnArg, ItemFn, Lifetime, ReturnType, Type, TypeTuple, Visibility,
+};
+
+mod java_enum;
+mod java_wrapper;
+mod jni_class;
+mod native_method;
+mod overload_set;
+mod register_natives;
 
 //
 
@@ -33,7 +42,7 @@ impl Parse for MySignature {
         parenthesized!(arg_types in tokens);
 
         while !arg_types.is_empty() {
-            let arg_type: Type = arg_types.parse()?;
+            let arg_type: Type = parse_parameter_type(&arg_types)?;
             parameter_types.push(arg_type);
 
             if !arg_types.is_empty() {
@@ -45,6 +54,28 @@ impl Parse for MySignature {
     }
 }
 
+/// A parameter type, where `...T` is java's varargs `T...`: a `&[T]`, passed as the `T[]` it is to the JVM.
+/// Like java's, it has to be the last parameter.
+fn parse_parameter_type(tokens: ParseStream) -> Result<Type, syn::Error> {
+    if !tokens.peek(Token![...]) {
+        return tokens.parse();
+    }
+    let dots: Token![...] = tokens.parse()?;
+    let element: Type = tokens.parse()?;
+    // nothing but a trailing comma may follow
+    let rest = tokens.fork();
+    if rest.peek(Token![,]) {
+        let _comma: Token![,] = rest.parse()?;
+    }
+    if !rest.is_empty() {
+        return Err(syn::Error::new_spanned(
+            dots,
+            "only the last parameter can be varargs",
+        ));
+    }
+    Ok(parse_quote! { &[#element] })
+}
+
 //
 
 struct InstanceMacroArguments {
@@ -84,59 +115,162 @@ impl Parse for InstanceMacroArguments {
 /// usage:
 /// <pre>jni_instance_method!{ fn_name[=java_name]([ arg_type1 [,arg_type2...]])[ ->return_type ] }
 /// </pre>
+///
+/// A last parameter of `...arg_type` binds a java varargs method: the rust function takes a `&[arg_type]`,
+/// passed to java as the array it packs the arguments into.  This works for every macro that takes such a list.
 #[proc_macro]
 pub fn jni_instance_method(t_stream: TokenStream) -> TokenStream {
     let macro_args = syn::parse_macro_input!(t_stream as InstanceMacroArguments);
 
-    let rust_name = &macro_args.rust_name;
-    let java_name = &macro_args.java_name;
+    instance_method_tokens(
+        &[],
+        &parse_quote! { pub },
+        &macro_args.rust_name,
+        &macro_args.java_name,
+        &macro_args.signature.parameter_types,
+        &macro_args.return_type,
+        None,
+    )
+    .into()
+}
 
-    let arg_types = &macro_args.signature.parameter_types;
+struct NonvirtualMacroArguments {
+    class_name: String,
+    method: InstanceMacroArguments,
+}
 
+impl Parse for NonvirtualMacroArguments {
+    fn parse(tokens: ParseStream) -> Result<NonvirtualMacroArguments, syn::Error> {
+        let ident: Ident = tokens.parse()?;
+        let class_name = harvest_remainder_java_class_name(tokens, ident.to_string())?;
+        let _comma: Token![,] = tokens.parse()?;
+        let method = tokens.parse()?;
+        Ok(NonvirtualMacroArguments { class_name, method })
+    }
+}
+
+/// Like `jni_instance_method!`, but calls the named ancestor class's implementation of the method,
+/// the way `super.javaName(...)` does in java, even if the object's class overrides it.
+///
+/// usage:
+/// <pre>jni_nonvirtual_method!{ package.AncestorClass, fn_name[=java_name]([ arg_type1 [,arg_type2...]])[ ->return_type ] }
+/// </pre>
+#[proc_macro]
+pub fn jni_nonvirtual_method(t_stream: TokenStream) -> TokenStream {
+    let macro_args = syn::parse_macro_input!(t_stream as NonvirtualMacroArguments);
+
+    instance_method_tokens(
+        &[],
+        &parse_quote! { pub },
+        &macro_args.method.rust_name,
+        &macro_args.method.java_name,
+        &macro_args.method.signature.parameter_types,
+        &macro_args.method.return_type,
+        Some(&macro_args.class_name),
+    )
+    .into()
+}
+
+/// the body of `jni_instance_method!`, also used for the instance methods of a `#[jni_class]`.
+/// With a `nonvirtual` (slashed) class name it calls that class's implementation instead of the object's own.
+fn instance_method_tokens(
+    attrs: &[Attribute],
+    vis: &Visibility,
+    rust_name: &Ident,
+    java_name: &str,
+    arg_types: &[Type],
+    return_type: &ReturnType,
+    nonvirtual: Option<&str>,
+) -> proc_macro2::TokenStream {
     let args_metadata: Vec<AllAboutArg> = arg_types
         .iter()
         .enumerate()
         .map(|(i, t)| AllAboutArg::new((*t).clone(), i))
         .collect();
 
-    let return_type: Type = bare_type_from_return_type(&macro_args.return_type);
+    let return_type: Type = bare_type_from_return_type(return_type);
     //let return_type_prefix = type_prefix_from(return_type.clone());
 
     let arg_sig = formal_parameters_tokens(&args_metadata);
 
-    let jni_env = self_jni_env();
+    let body = instance_call_tokens(
+        &quote! { self },
+        java_name,
+        arg_types,
+        &return_type,
+        nonvirtual,
+    );
+
+    quote! {
+        #(#attrs)*
+        #[allow(non_snake_case)]
+        #vis fn #rust_name(&self, #arg_sig) -> Result<#return_type, jni::errors::Error>
+        {
+            #body
+        }
+    }
+}
+
+/// The statements that call an instance method on `this` (an expression for a wrapper) with the
+/// parameters `arg0`, `arg1` ... and convert the result.
+/// Shared by `jni_instance_method!` and the trait impls of `jni_overload_set!`.
+fn instance_call_tokens(
+    this: &proc_macro2::TokenStream,
+    java_name: &str,
+    arg_types: &[Type],
+    return_type: &Type,
+    nonvirtual: Option<&str>,
+) -> proc_macro2::TokenStream {
+    let args_metadata: Vec<AllAboutArg> = arg_types
+        .iter()
+        .enumerate()
+        .map(|(i, t)| AllAboutArg::new((*t).clone(), i))
+        .collect();
+
+    let jni_env: Expr = parse_quote! { #this.jni_env };
 
     let decl: Vec<proc_macro2::TokenStream> =
         initializations_for_parameter_temporaries(&args_metadata, jni_env);
 
     let jvalue_param_array: Vec<proc_macro2::TokenStream> = value_parameter_array(&args_metadata);
 
-    let body = quote! {
-        #[allow(non_snake_case)]
-        pub fn #rust_name(&self, #arg_sig) -> Result<#return_type, jni::errors::Error>
-        {
+    let capacity = frame_capacity(arg_types);
+
+    let call = match nonvirtual {
+        None => quote! {
+            #this.jni_env.call_method(#this.java_this.as_obj(), #java_name, sig,
+                                     &[#(#jvalue_param_array),*])
+        },
+        Some(class_name) => quote! {
+            jni_boilerplate_helper::nonvirtual::call_nonvirtual_method(#this.jni_env, #this.java_this.as_obj(),
+                                     #class_name, #java_name, &sig, &[#(#jvalue_param_array),*])
+        },
+    };
+
+    quote! {
             use jni_boilerplate_helper::{JavaSignatureFor, ConvertRustToJValue, ConvertMutableRustToJValue,
                                          ConvertJValueToRust, ClearIfErr};
 
     #[cfg(debug_assertions)]
             jni_boilerplate_helper::panic_if_bad_sigs( &[ #(<#arg_types as JavaSignatureFor>::signature_for(),)* <#return_type as JavaSignatureFor>::signature_for() ] );
 
-            #(#decl)*
-
-            let sig = String::from("(") #(+&<#arg_types as JavaSignatureFor>::signature_for())* + ")"+&<#return_type as JavaSignatureFor>::signature_for();
+            let leak_check = jni_boilerplate_helper::leak_check::LeakCheck::start(#this.jni_env, std::any::type_name_of_val(#this), #java_name);
 
-            let results =
-                self.jni_env.call_method(self.java_this.as_obj(), #java_name, sig,
+            // the temporaries and whatever the call needs go away with the frame; only the result leaves it
+            let results = jni_boilerplate_helper::local_frame::with_local_frame(#this.jni_env, #capacity, |_| {
+                #(#decl)*
 
-                                     &[#(#jvalue_param_array),*])
-                .clear_if_err(self.jni_env)?;
+                let sig = String::from("(") #(+&<#arg_types as JavaSignatureFor>::signature_for())* + ")"+&<#return_type as JavaSignatureFor>::signature_for();
 
-            <#return_type as ConvertJValueToRust>::to_rust(self.jni_env, results)
-                    .clear_if_err(self.jni_env)
-        }
-                };
+                #call
+                    .clear_if_err(#this.jni_env)
+            })?;
 
-    body.into()
+            let rval = <#return_type as ConvertJValueToRust>::to_rust(#this.jni_env, results)
+                    .clear_if_err(#this.jni_env);
+            leak_check.finish(results);
+            rval
+    }
 }
 
 fn harvest_remainder_java_class_name(
@@ -161,12 +295,6 @@ fn harvest_remainder_java_class_name(
     Ok(class_name)
 }
 
-fn self_jni_env() -> Expr {
-    let ts: proc_macro::TokenStream = quote! { self.jni_env }.into();
-    let expr: Expr = syn::parse_macro_input::parse::<Expr>(ts).expect("how could parsing fail?");
-    expr
-}
-
 fn bare_jni_env() -> Expr {
     let ts: proc_macro::TokenStream = quote! { jni_env }.into();
     let expr: Expr = syn::parse_macro_input::parse::<Expr>(ts).expect("how could parsing fail?");
@@ -289,20 +417,41 @@ impl Parse for ConstructorMacroArgs {
 pub fn jni_constructor(t_stream: TokenStream) -> TokenStream {
     let macro_args = syn::parse_macro_input!(t_stream as ConstructorMacroArgs);
 
-    let lifetime_a = &macro_args.lifetime_a;
-    let lifetime_b = &macro_args.lifetime_b;
-    let arg_types = &macro_args.signature.parameter_types;
+    let class_name: &str = &macro_args.class_name;
 
+    constructor_tokens(
+        &[],
+        &parse_quote! { pub },
+        &macro_args.lifetime_a,
+        &macro_args.lifetime_b,
+        &macro_args.constructor_name,
+        &quote! { #class_name },
+        &macro_args.signature.parameter_types,
+        false,
+    )
+    .into()
+}
+
+/// the body of `jni_constructor!`, also used for the constructors of a `#[jni_class]`.
+/// `class_name` is an expression for the slash-separated name of the class.
+/// With `implicit_env` there is no `jni_env` parameter; the current thread's env is used instead.
+#[allow(clippy::too_many_arguments)]
+fn constructor_tokens(
+    attrs: &[Attribute],
+    vis: &Visibility,
+    lifetime_a: &Lifetime,
+    lifetime_b: &Lifetime,
+    rust_name: &Ident,
+    class_name: &proc_macro2::TokenStream,
+    arg_types: &[Type],
+    implicit_env: bool,
+) -> proc_macro2::TokenStream {
     let args_metadata: Vec<AllAboutArg> = arg_types
         .iter()
         .enumerate()
         .map(|(i, t)| AllAboutArg::new((*t).clone(), i))
         .collect();
 
-    let class_name: &str = &macro_args.class_name;
-
-    let rust_name = &macro_args.constructor_name;
-
     let arg_sig = formal_parameters_tokens(&args_metadata);
 
     let decl: Vec<proc_macro2::TokenStream> =
@@ -310,11 +459,17 @@ pub fn jni_constructor(t_stream: TokenStream) -> TokenStream {
 
     let jvalue_param_array: Vec<proc_macro2::TokenStream> = value_parameter_array(&args_metadata);
 
-    let body = quote! {
+    let (env_param, env_init) = env_tokens(lifetime_a, lifetime_b, implicit_env);
+
+    let capacity = frame_capacity(arg_types);
+
+    quote! {
+        #(#attrs)*
         #[allow(non_snake_case)]
-        pub fn #rust_name(jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a>, #arg_sig)
+        #vis fn #rust_name(#env_param #arg_sig)
         -> Result<Self, jni::errors::Error>
         {
+            #env_init
                 use jni_boilerplate_helper::{JavaSignatureFor, ConvertRustToJValue, ConvertMutableRustToJValue,
                                              ConvertJValueToRust, JClassWrapper, JavaConstructible, ClearIfErr};
 
@@ -322,25 +477,28 @@ pub fn jni_constructor(t_stream: TokenStream) -> TokenStream {
             jni_boilerplate_helper::panic_if_bad_sigs( &[ #(<#arg_types as JavaSignatureFor>::signature_for(),)* ] );
 
             //struct AssertReturnJC<'a> where Self:JavaConstructible<'a> { phantom: &'a PhantomData<u8>};
-                let cls = jni_env.find_class(#class_name)
-                    .clear_if_err(jni_env)?;
-                let cls = JClassWrapper {
-                    jni_env,
-                    cls,
-                };
+                let leak_check = jni_boilerplate_helper::leak_check::LeakCheck::start(jni_env, std::any::type_name::<Self>(), "<init>");
 
-                #(#decl)*
+                let rval = jni_boilerplate_helper::local_frame::with_local_frame(jni_env, #capacity, |_| {
+                    let cls = jni_env.find_class(#class_name)
+                        .clear_if_err(jni_env)?;
+                    let cls = JClassWrapper {
+                        jni_env,
+                        cls,
+                    };
+
+                    #(#decl)*
 
-                let sig = String::from("(")#(+&<#arg_types as JavaSignatureFor>::signature_for())* + ")V";
+                    let sig = String::from("(")#(+&<#arg_types as JavaSignatureFor>::signature_for())* + ")V";
 
-                let rval = jni_env.new_object(cls.cls, sig, &[#(#jvalue_param_array),*])
-                    .clear_if_err(jni_env)?;
+                    jni_env.new_object(cls.cls, sig, &[#(#jvalue_param_array),*])
+                        .clear_if_err(jni_env)
+                })?;
 
+                leak_check.finish(jni::objects::JValue::from(rval));
                 Ok(<Self as JavaConstructible>::wrap_jobject(jni_env, jni::objects::AutoLocal::new(&jni_env, rval)))
         }
-    };
-
-    body.into()
+    }
 }
 
 //
@@ -415,20 +573,43 @@ fn simple_identifier(name: &str) -> Ident {
 /// impl<'a:'b, 'b> DogWrapper<'a, 'b> {
 ///     jni_static_method! { 'a, 'b, functionName(&str, i32) -> DogWrapper<'a, 'b> }
 ///     jni_static_method! { 'a, 'b, rust_name=java_name(&str, i32) -> DogWrapper<'a, 'b> }
+///     // Dog.pack(String... names), called as DogWrapper::pack(jni_env, &["rex", "fido"])
+///     jni_static_method! { 'a, 'b, pack(...&str) -> DogWrapper<'a, 'b> }
 /// }
 /// ```
 #[proc_macro]
 pub fn jni_static_method(t_stream: TokenStream) -> TokenStream {
     let macro_args = syn::parse_macro_input!(t_stream as StaticMethodArgs);
 
-    let rust_name = &macro_args.rust_name;
-    let java_name: &str = &macro_args.java_name;
-
-    let return_type: Type = bare_type_from_return_type(&macro_args.return_type);
+    static_method_tokens(
+        &[],
+        &parse_quote! { pub },
+        &macro_args.lifetime_a,
+        &macro_args.lifetime_b,
+        &macro_args.rust_name,
+        &macro_args.java_name,
+        &macro_args.signature.parameter_types,
+        &macro_args.return_type,
+        false,
+    )
+    .into()
+}
 
-    let arg_types = &macro_args.signature.parameter_types;
-    let lifetime_a = &macro_args.lifetime_a;
-    let lifetime_b = &macro_args.lifetime_b;
+/// the body of `jni_static_method!`, also used for the static methods of a `#[jni_class]`.
+/// With `implicit_env` there is no `jni_env` parameter; the current thread's env is used instead.
+#[allow(clippy::too_many_arguments)]
+fn static_method_tokens(
+    attrs: &[Attribute],
+    vis: &Visibility,
+    lifetime_a: &Lifetime,
+    lifetime_b: &Lifetime,
+    rust_name: &Ident,
+    java_name: &str,
+    arg_types: &[Type],
+    return_type: &ReturnType,
+    implicit_env: bool,
+) -> proc_macro2::TokenStream {
+    let return_type: Type = bare_type_from_return_type(return_type);
 
     let args_metadata: Vec<AllAboutArg> = arg_types
         .iter()
@@ -443,34 +624,74 @@ pub fn jni_static_method(t_stream: TokenStream) -> TokenStream {
 
     let jvalue_param_array: Vec<proc_macro2::TokenStream> = value_parameter_array(&args_metadata);
 
-    let body = quote! {
+    let (env_param, env_init) = env_tokens(lifetime_a, lifetime_b, implicit_env);
+
+    let capacity = frame_capacity(arg_types);
+
+    quote! {
+        #(#attrs)*
         #[allow(non_snake_case)]
-        pub fn #rust_name(jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a>, #arg_sig) ->Result<#return_type, jni::errors::Error>
+        #vis fn #rust_name(#env_param #arg_sig) ->Result<#return_type, jni::errors::Error>
         {
+            #env_init
             use jni_boilerplate_helper::{JavaSignatureFor, ConvertRustToJValue, ConvertMutableRustToJValue,
                                          ConvertJValueToRust,JClassWrapper,JavaClassNameFor,ClearIfErr};
 
     #[cfg(debug_assertions)]
             jni_boilerplate_helper::panic_if_bad_sigs( &[ #(<#arg_types as JavaSignatureFor>::signature_for(),)* <#return_type as JavaSignatureFor>::signature_for() ] );
 
-            let cls = jni_env.find_class(&<Self>::java_class_name())
-            .clear_if_err(jni_env)?;
-            let cls = JClassWrapper {
-                jni_env: &jni_env,
-                cls,
-            };
+            let leak_check = jni_boilerplate_helper::leak_check::LeakCheck::start(jni_env, std::any::type_name::<Self>(), #java_name);
 
-            #(#decl)*
-            let sig = String::from("(")+#(&<#arg_types as JavaSignatureFor>::signature_for() +)*  ")"+&<#return_type as JavaSignatureFor>::signature_for();
+            let results = jni_boilerplate_helper::local_frame::with_local_frame(jni_env, #capacity, |_| {
+                let cls = jni_env.find_class(&<Self>::java_class_name())
+                .clear_if_err(jni_env)?;
+                let cls = JClassWrapper {
+                    jni_env: &jni_env,
+                    cls,
+                };
+
+                #(#decl)*
+                let sig = String::from("(")+#(&<#arg_types as JavaSignatureFor>::signature_for() +)*  ")"+&<#return_type as JavaSignatureFor>::signature_for();
 
-            let results = jni_env.call_static_method(cls.cls, #java_name, sig, &[#(#jvalue_param_array),*])
-            .clear_if_err(jni_env)?;
+                jni_env.call_static_method(cls.cls, #java_name, sig, &[#(#jvalue_param_array),*])
+                .clear_if_err(jni_env)
+            })?;
 
-            <#return_type as ConvertJValueToRust>::to_rust(jni_env, results)
+            let rval = <#return_type as ConvertJValueToRust>::to_rust(jni_env, results)
+                .clear_if_err(jni_env);
+            leak_check.finish(results);
+            rval
         }
-        };
+    }
+}
 
-    body.into()
+/// the `jni_env` parameter of a static method or constructor, or, for an `implicit_env` one,
+/// the statement that borrows the current thread's env instead
+fn env_tokens(
+    lifetime_a: &Lifetime,
+    lifetime_b: &Lifetime,
+    implicit_env: bool,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    if implicit_env {
+        (
+            quote! {},
+            quote! {
+                let jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a> =
+                    jni_boilerplate_helper::thread_env::current_env()?;
+            },
+        )
+    } else {
+        (
+            quote! { jni_env: &#lifetime_b jni::JNIEnv<#lifetime_a>, },
+            quote! {},
+        )
+    }
+}
+
+/// room in a generated call's local frame for each argument's temporary, and for the class and result
+fn frame_capacity(arg_types: &[Type]) -> proc_macro2::TokenStream {
+    let capacity = arg_types.len() as i32 + 4;
+    quote! { #capacity }
 }
 
 fn formal_parameters_tokens(
@@ -690,13 +911,14 @@ pub fn jni_field(t_stream: TokenStream) -> TokenStream {
                 .clear_if_err(self.jni_env)?)
     }
 
+        #[allow(non_snake_case)]
     pub fn #setter(&self, new_val: #rust_type) -> Result<(), jni::errors::Error>
     {
     use jni_boilerplate_helper::{ConvertRustToJValue,JavaSignatureFor, ClearIfErr};
     let tmp = <#rust_type as ConvertRustToJValue>::into_temporary(&new_val, self.jni_env).clear_if_err(self.jni_env)?;
     self.jni_env.set_field(self.java_this.as_obj(), #java_name,
     #java_type,
-    <&#rust_type as ConvertRustToJValue>::temporary_into_jvalue(&tmp)).clear_if_err(self.jni_env)
+    <#rust_type as ConvertRustToJValue>::temporary_into_jvalue(&tmp)).clear_if_err(self.jni_env)
     }
     };
 
@@ -705,6 +927,389 @@ pub fn jni_field(t_stream: TokenStream) -> TokenStream {
     body.into()
 }
 
+/// Exports a rust function as the implementation of a java `native` method.
+/// The function itself is left alone; next to it we generate the
+/// `extern "system" fn Java_package_Class_method(...)` the JVM will look for,
+/// which converts the raw JNI arguments with `ConvertJValueToRust`, calls the function,
+/// and converts the result back with `ConvertRustToJValue`.
+///
+/// The first parameter may be a `&JNIEnv`.  For an instance method the next parameter receives `this`
+/// (usually a `jni_wrapper_cliche_impl!` type); a `static` method has no `this` parameter.
+/// The function may return a plain value or a `Result<_, jni::errors::Error>`.
+/// An `Err` becomes a `java.lang.RuntimeException` unless a java exception is already pending.
+/// A panic does not unwind into the JVM; it becomes a `java.lang.RuntimeException`
+/// (see `jni_boilerplate_helper::native_method::set_panic_exception_class()`) and java gets a default value.
+///
+/// usage:
+/// <pre>#[jni_native_method([static] package.path.ClassName)]
+/// fn javaMethodName(jni_env: &amp;JNIEnv, this: Wrapper, arg1: arg_type1 ...) -&gt; return_type { ... }
+/// </pre>
+///
+/// example:
+/// ```
+/// use jni::JNIEnv;
+/// use jni_boilerplate::jni_native_method;
+/// use jni_boilerplate_helper::jni_wrapper_cliche_impl;
+/// jni_wrapper_cliche_impl!{ Widget, "com/purplefrog/rust_callables/Widget" }
+///
+/// #[jni_native_method(com.purplefrog.rust_callables.Widget)]
+/// fn nativeDescribe(_jni_env: &JNIEnv, _this: Widget, count: i32, label: String) -> String {
+///     format!("{} x{}", label, count)
+/// }
+///
+/// #[jni_native_method(static com.purplefrog.rust_callables.Widget)]
+/// fn nativeSum(values: Vec<i32>) -> Result<i64, jni::errors::Error> {
+///     Ok(values.iter().map(|&v| v as i64).sum())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn jni_native_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = syn::parse_macro_input!(attr as native_method::NativeMethodAttr);
+    let item = syn::parse_macro_input!(item as ItemFn);
+
+    match native_method::native_method_export(&attr, &item) {
+        Ok(export) => quote! {
+            #[allow(non_snake_case)]
+            #item
+            #export
+        }
+        .into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Binds rust functions to java `native` methods by calling `RegisterNatives` from a generated `JNI_OnLoad`,
+/// so nothing depends on the `Java_...` symbol names (which get awkward with overloads and inner classes).
+/// The JNI signature of each method is built from the `JavaSignatureFor` descriptors of the listed types.
+///
+/// Each rust function is called with the `&JNIEnv`, then `this` as a `JObject` (a `static` method gets its `JClass`),
+/// then the converted arguments.  It returns the listed type, or a `Result` of it;
+/// an `Err` becomes a `java.lang.RuntimeException` unless a java exception is already pending.
+/// Panics are contained the same way as for `#[jni_native_method]`.
+/// Without `= rust_function` the rust function has the same name as the java method.
+///
+/// The optional `init` hook runs after registration with the `&JNIEnv`, and may fail with a `jni::errors::Error`.
+/// The optional `shutdown` hook is called with the `&JavaVM` from a generated `JNI_OnUnload`.
+/// The `JavaVM` is kept for later use; see `jni_boilerplate_helper::register_natives::java_vm()`.
+///
+/// There can only be one `JNI_OnLoad` in a library, so list every class in one `jni_register_natives!`.
+///
+/// usage:
+/// <pre>jni_register_natives!{ [init = init_fn;] [shutdown = shutdown_fn;]
+///     package.path.ClassName {
+///         [static] fn javaName([ arg_type1 [,arg_type2...]])[ -&gt;return_type ][ = rust_function];
+///         ...
+///     }
+///     ...
+/// }
+/// </pre>
+///
+/// example:
+/// ```
+/// use jni::objects::{JClass, JObject};
+/// use jni::{JNIEnv, JavaVM};
+/// use jni_boilerplate::jni_register_natives;
+///
+/// fn describe_int(_jni_env: &JNIEnv, _this: JObject, count: i32) -> String {
+///     format!("x{}", count)
+/// }
+///
+/// fn describe_string(_jni_env: &JNIEnv, _this: JObject, label: String) -> String {
+///     label
+/// }
+///
+/// fn sum(_jni_env: &JNIEnv, _class: JClass, values: Vec<i32>) -> Result<i64, jni::errors::Error> {
+///     Ok(values.iter().map(|&v| v as i64).sum())
+/// }
+///
+/// fn init(_jni_env: &JNIEnv) -> Result<(), jni::errors::Error> {
+///     Ok(())
+/// }
+///
+/// fn shutdown(_vm: &JavaVM) {}
+///
+/// jni_register_natives! {
+///     init = init;
+///     shutdown = shutdown;
+///     com.purplefrog.rust_callables.Widget {
+///         fn describe(i32) -> String = describe_int;
+///         fn describe(String) -> String = describe_string;
+///     }
+///     com.purplefrog.rust_callables.Widget$Helper {
+///         static fn sum(Vec<i32>) -> Result<i64, jni::errors::Error>;
+///     }
+/// }
+/// ```
+#[proc_macro]
+pub fn jni_register_natives(t_stream: TokenStream) -> TokenStream {
+    let macro_args = syn::parse_macro_input!(t_stream as register_natives::RegisterNativesArgs);
+
+    register_natives::register_natives(&macro_args).into()
+}
+
+/// Implements the conversion traits for a hand-written wrapper struct, the way `jni_wrapper_cliche_impl!` does
+/// for the struct it declares: `JavaClassNameFor`, `JavaSignatureFor` (for the struct and references to it),
+/// `JavaConstructible`, `ConvertRustToJValue` (for both), `ConvertJValueToRust`, `JValueNonScalar`
+/// and `inheritance::JavaObjectWrapper`.
+///
+/// The struct needs a `java_this: AutoLocal<'a, 'b>` field and a `jni_env: &'b JNIEnv<'a>` field;
+/// the lifetimes may have other names.  Any other fields are filled in with `Default::default()`
+/// whenever a wrapper is made from a java object.  Generic parameters are carried through to the impls,
+/// and the visibility, docs and other derives of the struct are up to you.
+/// A `#[repr(C)]` struct with only those two fields, in that order, is also an `inheritance::PlainJavaWrapper`,
+/// so `jni_extends!` can relate it to the wrappers of its superclass and interfaces.
+/// A struct whose only generic parameters are its two lifetimes also gets `global::WrapperFor`,
+/// so it can be held as a `global::Global`.
+///
+/// The class name may be dotted; it is converted to the `/` form JNI wants.
+/// Use `$` for inner classes.
+///
+/// example:
+/// ```
+/// use jni::objects::AutoLocal;
+/// use jni::JNIEnv;
+/// use jni_boilerplate::{jni_instance_method, JavaWrapper};
+/// use jni_boilerplate_helper::{ConvertJValueToRust, JavaSignatureFor};
+/// use std::marker::PhantomData;
+///
+/// /// a `java.lang.StringBuilder`, plus a count of what we appended
+/// #[derive(JavaWrapper)]
+/// #[java_class("java.lang.StringBuilder")]
+/// pub(crate) struct Builder<'a: 'b, 'b> {
+///     java_this: AutoLocal<'a, 'b>,
+///     jni_env: &'b JNIEnv<'a>,
+///     appended: usize,
+/// }
+///
+/// impl<'a, 'b> Builder<'a, 'b> {
+///     jni_instance_method! { length() -> i32 }
+/// }
+///
+/// /// a `java.util.List` whose elements we expect to convert to `T`
+/// #[derive(JavaWrapper)]
+/// #[java_class("java.util.List")]
+/// struct TypedList<'a: 'b, 'b, T> {
+///     java_this: AutoLocal<'a, 'b>,
+///     jni_env: &'b JNIEnv<'a>,
+///     phantom: PhantomData<T>,
+/// }
+///
+/// assert_eq!("Ljava/lang/StringBuilder;", Builder::signature_for());
+/// assert_eq!("Ljava/util/List;", TypedList::<String>::signature_for());
+/// ```
+#[proc_macro_derive(JavaWrapper, attributes(java_class))]
+pub fn derive_java_wrapper(t_stream: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(t_stream as DeriveInput);
+
+    match java_wrapper::derive_java_wrapper(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Maps a fieldless rust enum onto a java enum, so its variants can be passed to and returned from java methods.
+///
+/// A variant goes to java as the enum constant of the same name, which is read from the class once and kept as a global reference.
+/// The constant's name is the variant's in SCREAMING_SNAKE_CASE (`HalfEven` is `HALF_EVEN`), unless it has a
+/// `#[java_name = "NAME"]` attribute.  A returned constant is matched by its `name()`; one the rust enum lacks is
+/// an `IllegalArgumentException`, and a `null` a `NullPointerException`.
+///
+/// The class name may be dotted; it is converted to the `/` form JNI wants.
+///
+/// example:
+/// ```
+/// use jni_boilerplate::JavaEnum;
+/// use jni_boilerplate_helper::java_enum::JavaEnum as _;
+/// use jni_boilerplate_helper::JavaSignatureFor;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, JavaEnum)]
+/// #[java_class("java.math.RoundingMode")]
+/// enum RoundingMode {
+///     Up,
+///     Down,
+///     HalfEven,
+///     #[java_name = "UNNECESSARY"]
+///     Exact,
+/// }
+///
+/// assert_eq!("Ljava/math/RoundingMode;", RoundingMode::signature_for());
+/// assert_eq!("HALF_EVEN", RoundingMode::HalfEven.java_name());
+/// assert_eq!("UNNECESSARY", RoundingMode::Exact.java_name());
+/// ```
+#[proc_macro_derive(JavaEnum, attributes(java_class, java_name))]
+pub fn derive_java_enum(t_stream: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(t_stream as DeriveInput);
+
+    match java_enum::derive_java_enum(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Declares a batch of java bindings in one `impl`.  Each body-less `fn` declaration is expanded the way
+/// `jni_instance_method!`, `jni_static_method!` or `jni_constructor!` would expand it,
+/// and ordinary items are left alone.
+///
+/// * `fn name(&self, arg: arg_type, ...) -> return_type;` is an instance method
+/// * `#[static_method] fn name(arg: arg_type, ...) -> return_type;` is a static method (a declaration without `&self` is static anyway)
+/// * `#[constructor] fn name(arg: arg_type, ...) -> Self;` is a constructor
+/// * `#[java_name = "javaName"]` calls a differently named java method, which is how overloads get distinct rust names
+/// * `#[implicit_env]` on a static method or constructor drops its `jni_env` parameter and uses
+///   `thread_env::current_env()`, attaching the thread if need be, so it can be called from any thread
+///   once a `JavaVM` has been registered, except one that java or other native code attached
+/// * `#[nonvirtual = "package.AncestorClass"]` on an instance method calls that class's implementation,
+///   like `super.javaName()` in java, instead of the object's own override (see `jni_nonvirtual_method!`)
+///
+/// Declarations can instead share the java method's name, and `overloads = "types"` renames them
+/// after their parameter types: `echo(&self, value: i32)` becomes `echo_int`, `echo(&self, value: &str)` becomes
+/// `echo_string` and a `Widget<'a, 'b>` parameter adds `_widget`.  `overloads = "arity"` uses the parameter count
+/// (`echo_1`) instead.  The overload without parameters keeps the bare name, and a declaration
+/// whose name is not shared keeps it, so `#[java_name]` with a name of your own overrides the policy.
+/// By default (`"explicit"`) overloads have to be named by hand.
+/// Either way, two items with the same rust name are a compile error.
+///
+/// Rust insists on parameter names, but only the types matter.
+/// A varargs parameter is declared as the slice it is passed as, so `String.format(String, Object...)`
+/// is `fn format(pattern: &str, args: &[Erased<String>]) -> String;`.
+/// Like the individual macros, every generated function returns a `Result<_, jni::errors::Error>`,
+/// and static methods and constructors take a `&JNIEnv` first.
+/// The `jni_env` lifetimes are the first two lifetime parameters of the `impl`.
+///
+/// Given a class name (dotted or slashed) this also implements `JavaClassNameFor`;
+/// use a bare `#[jni_class]` (or `#[jni_class(overloads = "types")]`) when the type already has one,
+/// e.g. from `#[derive(JavaWrapper)]`.
+///
+/// example:
+/// ```
+/// use jni_boilerplate::jni_class;
+/// use jni_boilerplate_helper::jni_wrapper_cliche_impl;
+///
+/// jni_wrapper_cliche_impl! { Widget, "com/purplefrog/rust_callables/Widget" }
+///
+/// #[jni_class]
+/// impl<'a: 'b, 'b> Widget<'a, 'b> {
+///     #[constructor]
+///     pub fn new() -> Self;
+///     #[constructor]
+///     pub fn new_one(label: &str) -> Self;
+///
+///     pub fn count(&self) -> i32;
+///     pub fn add(&self, text: &str);
+///     #[java_name = "echo"]
+///     pub fn echo_int(&self, value: i32) -> i32;
+///     #[java_name = "echo"]
+///     pub fn echo_str(&self, value: &str) -> String;
+///
+///     #[static_method]
+///     pub fn sum(values: &[i32]) -> i32;
+///
+///     pub fn count_twice(&self) -> Result<i32, jni::errors::Error> {
+///         Ok(self.count()? * 2)
+///     }
+/// }
+///
+/// jni_wrapper_cliche_impl! { Gadget, "com/purplefrog/rust_callables/Gadget" }
+///
+/// #[jni_class(overloads = "types")]
+/// impl<'a: 'b, 'b> Gadget<'a, 'b> {
+///     #[constructor]
+///     pub fn new() -> Self;
+///     #[constructor]
+///     pub fn new(label: &str) -> Self;
+///
+///     pub fn echo(&self, value: i32) -> i32;
+///     pub fn echo(&self, value: &str) -> String;
+///     pub fn echo(&self, value: char) -> char;
+/// }
+///
+/// fn use_gadget(jni_env: &jni::JNIEnv) -> Result<(), jni::errors::Error> {
+///     let gadget = Gadget::new_string(jni_env, "g")?;
+///     let _: i32 = gadget.echo_int(1)?;
+///     let _: String = gadget.echo_string("two")?;
+///     let _: char = gadget.echo_char('3')?;
+///     let _ = Gadget::new(jni_env)?;
+///     Ok(())
+/// }
+/// ```
+///
+/// Overloads that the policy can not tell apart do not compile:
+/// ```compile_fail
+/// use jni_boilerplate::jni_class;
+/// use jni_boilerplate_helper::jni_wrapper_cliche_impl;
+///
+/// jni_wrapper_cliche_impl! { Gadget, "com/purplefrog/rust_callables/Gadget" }
+///
+/// #[jni_class(overloads = "arity")]
+/// impl<'a: 'b, 'b> Gadget<'a, 'b> {
+///     pub fn echo(&self, value: i32) -> i32;
+///     pub fn echo(&self, value: &str) -> String;
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn jni_class(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = syn::parse_macro_input!(attr as jni_class::JniClassAttr);
+    let mut item = syn::parse_macro_input!(item as jni_class::JniClassImpl);
+
+    match jni_class::jni_class(&attr, &mut item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Binds a whole java overload set to one generic rust method, so the call site picks the overload
+/// by the type of its argument: `widget.echo(3)`, `widget.echo("three")`, `widget.echo((3, 4))`.
+///
+/// usage (at module level, not inside an `impl`):
+/// <pre>jni_overload_set! { Wrapper&lt;'a, 'b&gt;, [pub] rust_name[=javaName]: TraitName {
+///     ([ arg_type1 [,arg_type2...]])[ -&gt;return_type ];
+///     ...
+/// } }
+/// </pre>
+///
+/// This declares `TraitName`, implements it for each overload's arguments (the bare type when there is
+/// one, a tuple when there are several, `()` when there are none), and adds
+/// `fn rust_name<Args: TraitName>(&self, args: Args) -> Result<Args::Output, jni::errors::Error>` to the wrapper.
+/// Each impl calls its own overload, like `jni_instance_method!` would, so two overloads whose
+/// rust argument types are the same do not compile.  When overloads differ only in the width of an integer
+/// (`i32` and `i64`), a literal argument needs a suffix: `builder.append(1i32)`.
+///
+/// example:
+/// ```
+/// use jni_boilerplate::jni_overload_set;
+/// use jni_boilerplate_helper::jni_wrapper_cliche_impl;
+///
+/// jni_wrapper_cliche_impl! { Widget, "com/purplefrog/rust_callables/Widget" }
+///
+/// jni_overload_set! { Widget<'a, 'b>, pub echo: WidgetEcho {
+///     (i32) -> i32;
+///     (&str) -> String;
+///     (i32, i32) -> i64;
+///     () -> String;
+/// } }
+///
+/// jni_overload_set! { Widget<'a, 'b>, pub to_text=toString: WidgetToText {
+///     () -> String;
+/// } }
+///
+/// fn use_widget(widget: &Widget) -> Result<(), jni::errors::Error> {
+///     let _: i32 = widget.echo(3)?;
+///     let _: String = widget.echo("three")?;
+///     let _: i64 = widget.echo((3, 4))?;
+///     let _: String = widget.echo(())?;
+///     let _: String = widget.to_text(())?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro]
+pub fn jni_overload_set(t_stream: TokenStream) -> TokenStream {
+    let set = syn::parse_macro_input!(t_stream as overload_set::OverloadSet);
+
+    match overload_set::overload_set(&set) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
 /*
   pub fn get_xform(&self) -> Result<AffineTransform<'a, 'b>, jni::errors::Error> {
        ConvertJValueToRust::to_rust(self.jni_env,
@@ -726,8 +1331,8 @@ mod test {
 
         let b: Type = parse_quote! { &mut [i32] };
 
-        assert_eq!(false, is_mut_ref(&a));
-        assert_eq!(true, is_mut_ref(&b));
+        assert!(!is_mut_ref(&a));
+        assert!(is_mut_ref(&b));
 
         Ok(())
     }
@@ -0,0 +1,311 @@
+//! code generation for `#[jni_native_method]`, the java-calls-rust direction
+
+use crate::{harvest_remainder_java_class_name, simple_identifier};
+use jni_boilerplate_helper::jni_symbol::jni_symbol_name;
+use proc_macro2::{Ident, TokenStream};
+use syn::parse::{Parse, ParseStream};
+use syn::visit_mut::VisitMut;
+use syn::{FnArg, GenericArgument, ItemFn, Lifetime, PathArguments, ReturnType, Type};
+
+pub struct NativeMethodAttr {
+    pub is_static: bool,
+    pub class_name: String,
+}
+
+impl Parse for NativeMethodAttr {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let is_static = if tokens.peek(Token![static]) {
+            let _static: Token![static] = tokens.parse()?;
+            true
+        } else {
+            false
+        };
+        let ident: Ident = tokens.parse()?;
+        let class_name = harvest_remainder_java_class_name(tokens, ident.to_string())?;
+        Ok(NativeMethodAttr {
+            is_static,
+            class_name,
+        })
+    }
+}
+
+/// What the rust function is given for the `this` (or, for a static method, `jclass`) argument of the native method.
+pub enum NativeReceiver {
+    /// a static method whose rust function does not take the class
+    Nothing,
+    /// `this`, converted with `ConvertJValueToRust`
+    Converted(Box<Type>),
+    /// `this` as a `JObject`, or the class as a `JClass` for a static method
+    Raw { is_static: bool },
+}
+
+/// How the parameters of the user's rust function line up with the parameters of the java native method.
+pub struct NativeParameters {
+    pub takes_env: bool,
+    pub receiver: NativeReceiver,
+    pub java_types: Vec<Type>,
+}
+
+impl NativeParameters {
+    pub fn new(item: &ItemFn, is_static: bool) -> Result<NativeParameters, syn::Error> {
+        let mut types = Vec::new();
+        for input in &item.sig.inputs {
+            match input {
+                FnArg::Receiver(r) => {
+                    return Err(syn::Error::new_spanned(
+                        r,
+                        "native methods are exported as free functions and can not take self",
+                    ))
+                }
+                FnArg::Typed(pat_type) => types.push((*pat_type.ty).clone()),
+            }
+        }
+
+        let takes_env = types.first().map(is_jni_env_reference).unwrap_or(false);
+        if takes_env {
+            types.remove(0);
+        }
+
+        let receiver = if is_static {
+            NativeReceiver::Nothing
+        } else if types.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &item.sig,
+                "an instance native method needs a parameter for `this` (use `static` for static methods)",
+            ));
+        } else {
+            NativeReceiver::Converted(Box::new(types.remove(0)))
+        };
+
+        Ok(NativeParameters {
+            takes_env,
+            receiver,
+            java_types: types,
+        })
+    }
+}
+
+/// true for `&JNIEnv`, `&jni::JNIEnv<'a>` and the like
+pub fn is_jni_env_reference(ty: &Type) -> bool {
+    if let Type::Reference(reference) = ty {
+        if let Type::Path(path) = &*reference.elem {
+            return path
+                .path
+                .segments
+                .last()
+                .map(|seg| seg.ident == "JNIEnv")
+                .unwrap_or(false);
+        }
+    }
+    false
+}
+
+/// If `ty` is `Result<T, ...>`, returns `T`.
+pub fn result_ok_type(ty: &Type) -> Option<Type> {
+    if let Type::Path(path) = ty {
+        let last = path.path.segments.last()?;
+        if last.ident != "Result" {
+            return None;
+        }
+        if let PathArguments::AngleBracketed(args) = &last.arguments {
+            if let Some(GenericArgument::Type(ok)) = args.args.first() {
+                return Some(ok.clone());
+            }
+        }
+    }
+    None
+}
+
+pub fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+struct ReplaceLifetimes(Lifetime);
+
+impl VisitMut for ReplaceLifetimes {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        *lifetime = self.0.clone();
+    }
+
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        reference.lifetime = Some(self.0.clone());
+        self.visit_type_mut(&mut reference.elem);
+    }
+}
+
+/// The user's rust types may mention lifetimes that only exist in the user's function,
+/// so we rewrite them before using the type somewhere else.
+pub fn with_lifetimes(ty: &Type, lifetime: &str) -> Type {
+    let mut rval = ty.clone();
+    ReplaceLifetimes(Lifetime::new(lifetime, proc_macro2::Span::call_site()))
+        .visit_type_mut(&mut rval);
+    rval
+}
+
+/// Generates the `extern "system" fn Java_...` which converts the raw JNI arguments,
+/// calls the user's function, and converts its result back.
+pub fn native_method_export(
+    attr: &NativeMethodAttr,
+    item: &ItemFn,
+) -> Result<TokenStream, syn::Error> {
+    let params = NativeParameters::new(item, attr.is_static)?;
+
+    let rust_name = &item.sig.ident;
+    let export_name = simple_identifier(&jni_symbol_name(
+        &attr.class_name,
+        &rust_name.to_string(),
+        None,
+    ));
+
+    Ok(native_entry_point(
+        &export_name,
+        true,
+        &params,
+        &quote! { #rust_name },
+        &item.sig.output,
+    ))
+}
+
+/// Splits the rust return type into the type java sees and whether the rust function returns a `Result`.
+pub fn native_value_type(output: &ReturnType) -> (Type, bool) {
+    match output {
+        ReturnType::Default => (syn::parse_quote! { () }, false),
+        ReturnType::Type(_, ty) => match result_ok_type(ty) {
+            Some(ok) => (ok, true),
+            None => ((**ty).clone(), false),
+        },
+    }
+}
+
+/// The `extern "system" fn` java calls: it converts the raw JNI arguments, calls `callee`,
+/// and converts the result back.  When `exported` it is `#[no_mangle]` so the JVM can find it by name;
+/// otherwise it is only reachable through a function pointer handed to `RegisterNatives`.
+/// A panic becomes a java exception instead of unwinding into the JVM.
+pub fn native_entry_point(
+    fn_name: &Ident,
+    exported: bool,
+    params: &NativeParameters,
+    callee: &TokenStream,
+    output: &ReturnType,
+) -> TokenStream {
+    let (value_type, fallible) = native_value_type(output);
+    let value_elided = with_lifetimes(&value_type, "'_");
+    let value_static = with_lifetimes(&value_type, "'static");
+
+    let raw_params: Vec<TokenStream> = params
+        .java_types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let p_i = simple_identifier(&format!("arg{}", i));
+            let ty = with_lifetimes(ty, "'static");
+            quote! { #p_i: <#ty as jni_boilerplate_helper::native_method::JniNativeType>::Raw }
+        })
+        .collect();
+
+    let conversions: Vec<TokenStream> = params
+        .java_types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let p_i = simple_identifier(&format!("arg{}", i));
+            let elided = with_lifetimes(ty, "'_");
+            let ty_static = with_lifetimes(ty, "'static");
+            quote! {
+                let #p_i = <#elided as ConvertJValueToRust>::to_rust(jni_env, <#ty_static as JniNativeType>::raw_into_jvalue(#p_i))?;
+            }
+        })
+        .collect();
+
+    let mut call_args: Vec<TokenStream> = Vec::new();
+    if params.takes_env {
+        call_args.push(quote! { jni_env });
+    }
+    let (this_param, this_conversion) = match &params.receiver {
+        NativeReceiver::Nothing => (quote! { _java_class: jni::sys::jclass }, quote! {}),
+        NativeReceiver::Converted(ty) => {
+            call_args.push(quote! { this });
+            let elided = with_lifetimes(ty, "'_");
+            (
+                quote! { java_this: jni::sys::jobject },
+                quote! {
+                    let this = <#elided as ConvertJValueToRust>::to_rust(jni_env, jni::objects::JValue::Object(jni_boilerplate_helper::wrap_jobject(java_this)))?;
+                },
+            )
+        }
+        NativeReceiver::Raw { is_static: false } => {
+            call_args.push(quote! { this });
+            (
+                quote! { java_this: jni::sys::jobject },
+                quote! {
+                    let this = jni_boilerplate_helper::wrap_jobject(java_this);
+                },
+            )
+        }
+        NativeReceiver::Raw { is_static: true } => {
+            call_args.push(quote! { class });
+            (
+                quote! { java_class: jni::sys::jclass },
+                quote! {
+                    let class = jni::objects::JClass::from(jni_boilerplate_helper::wrap_jobject(java_class));
+                },
+            )
+        }
+    };
+    for i in 0..params.java_types.len() {
+        let p_i = simple_identifier(&format!("arg{}", i));
+        call_args.push(quote! { #p_i });
+    }
+
+    let call = if fallible {
+        quote! { #callee(#(#call_args),*)? }
+    } else {
+        quote! { #callee(#(#call_args),*) }
+    };
+
+    let convert_result = if is_unit(&value_type) {
+        quote! {
+            #call;
+            Ok(())
+        }
+    } else {
+        quote! {
+            let rval: #value_elided = #call;
+            let tmp = <#value_elided as ConvertRustToJValue>::into_temporary(&rval, jni_env)?;
+            <#value_static as JniNativeType>::jvalue_into_raw(jni_env, <#value_elided as ConvertRustToJValue>::temporary_into_jvalue(&tmp))
+        }
+    };
+
+    let linkage = if exported {
+        quote! {
+            #[no_mangle]
+            pub
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        #[allow(non_snake_case, clippy::not_unsafe_ptr_arg_deref)]
+        #linkage extern "system" fn #fn_name(jni_env: jni::JNIEnv, #this_param, #(#raw_params),*) -> <#value_static as jni_boilerplate_helper::native_method::JniNativeType>::Raw
+        {
+            use jni_boilerplate_helper::native_method::{JniNativeType, catch_panic, throw_for_native_error};
+            use jni_boilerplate_helper::{ConvertJValueToRust, ConvertRustToJValue};
+
+            let jni_env = &jni_env;
+            let outcome = catch_panic(jni_env, || -> Result<<#value_static as JniNativeType>::Raw, jni::errors::Error> {
+                #this_conversion
+                #(#conversions)*
+                #convert_result
+            });
+            match outcome {
+                Some(Ok(raw)) => raw,
+                Some(Err(e)) => {
+                    throw_for_native_error(jni_env, &e);
+                    <#value_static as JniNativeType>::default_raw()
+                }
+                None => <#value_static as JniNativeType>::default_raw(),
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! code generation for `jni_overload_set!`, which binds every overload of a java instance method to
+//! one generic rust method, dispatched through a trait implemented for each overload's argument types
+
+use crate::{bare_type_from_return_type, instance_call_tokens, parse_function_names, MySignature};
+use proc_macro2::{Ident, TokenStream};
+use syn::parse::{Parse, ParseBuffer, ParseStream};
+use syn::{GenericArgument, Lifetime, PathArguments, ReturnType, Type, Visibility};
+
+/// `Wrapper<'a, 'b>, [vis] rust_name[=javaName]: TraitName { (arg_types) [-> return_type]; ... }`
+pub struct OverloadSet {
+    wrapper: Type,
+    vis: Visibility,
+    rust_name: Ident,
+    java_name: String,
+    trait_name: Ident,
+    overloads: Vec<(Vec<Type>, ReturnType)>,
+}
+
+impl Parse for OverloadSet {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let wrapper: Type = tokens.parse()?;
+        let _comma: Token![,] = tokens.parse()?;
+        let vis: Visibility = tokens.parse()?;
+        let (rust_name, java_name) = parse_function_names(tokens)?;
+        let _colon: Token![:] = tokens.parse()?;
+        let trait_name: Ident = tokens.parse()?;
+
+        let body: ParseBuffer;
+        let braces = braced!(body in tokens);
+        let mut overloads = Vec::new();
+        while !body.is_empty() {
+            let signature: MySignature = body.parse()?;
+            let return_type: ReturnType = body.parse()?;
+            let _semi: Token![;] = body.parse()?;
+            overloads.push((signature.parameter_types, return_type));
+        }
+        if overloads.is_empty() {
+            return Err(syn::Error::new(
+                braces.span,
+                "an overload set needs at least one (argument types) -> return_type;",
+            ));
+        }
+
+        Ok(OverloadSet {
+            wrapper,
+            vis,
+            rust_name,
+            java_name,
+            trait_name,
+            overloads,
+        })
+    }
+}
+
+/// the lifetime arguments of the wrapper type, which the trait is generic over
+fn wrapper_lifetimes(wrapper: &Type) -> Result<Vec<Lifetime>, syn::Error> {
+    let lifetimes: Vec<Lifetime> = match wrapper {
+        Type::Path(path) => match path.path.segments.last().map(|last| &last.arguments) {
+            Some(PathArguments::AngleBracketed(args)) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Lifetime(lifetime) => Some(lifetime.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    if lifetimes.is_empty() || lifetimes.iter().any(|l| l.ident == "_") {
+        return Err(syn::Error::new_spanned(
+            wrapper,
+            "name the wrapper's lifetimes, as in Widget<'a, 'b>",
+        ));
+    }
+    Ok(lifetimes)
+}
+
+pub fn overload_set(set: &OverloadSet) -> Result<TokenStream, syn::Error> {
+    let lifetimes = wrapper_lifetimes(&set.wrapper)?;
+    // the wrappers are declared `<'a: 'b, 'b>`, and a reference to one needs that bound to be well formed
+    let lifetime_params = match lifetimes.as_slice() {
+        [a, b] if a != b => quote! { #a: #b, #b },
+        _ => quote! { #(#lifetimes),* },
+    };
+
+    let OverloadSet {
+        wrapper,
+        vis,
+        rust_name,
+        java_name,
+        trait_name,
+        overloads,
+    } = set;
+
+    let trait_doc = format!(
+        "The argument types of `{}`, one impl per overload of java's `{}`: \
+         a single type for a one-argument overload, a tuple otherwise.",
+        rust_name, java_name
+    );
+
+    let impls = overloads.iter().map(|(arg_types, return_type)| {
+        let return_type = bare_type_from_return_type(return_type);
+        let params: Vec<Ident> = (0..arg_types.len())
+            .map(|i| Ident::new(&format!("arg{}", i), proc_macro2::Span::call_site()))
+            .collect();
+        let (implementor, unpack) = match arg_types.as_slice() {
+            [] => (quote! { () }, quote! {}),
+            [single] => (quote! { #single }, quote! { let arg0 = self; }),
+            _ => (
+                quote! { (#(#arg_types,)*) },
+                quote! { let (#(#params,)*) = self; },
+            ),
+        };
+        let body = instance_call_tokens(&quote! { this }, java_name, arg_types, &return_type, None);
+
+        quote! {
+            impl<#lifetime_params> #trait_name<#(#lifetimes),*> for #implementor {
+                type Output = #return_type;
+
+                #[allow(non_snake_case)]
+                fn call_java(self, this: &#wrapper) -> Result<#return_type, jni::errors::Error> {
+                    #unpack
+                    #body
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        #[doc = #trait_doc]
+        #vis trait #trait_name<#lifetime_params> {
+            type Output;
+
+            fn call_java(self, this: &#wrapper) -> Result<Self::Output, jni::errors::Error>;
+        }
+
+        #(#impls)*
+
+        impl<#lifetime_params> #wrapper {
+            #[allow(non_snake_case)]
+            #vis fn #rust_name<Args: #trait_name<#(#lifetimes),*>>(&self, args: Args) -> Result<Args::Output, jni::errors::Error> {
+                args.call_java(self)
+            }
+        }
+    })
+}
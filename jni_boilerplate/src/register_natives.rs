@@ -0,0 +1,211 @@
+//! code generation for `jni_register_natives!`, which binds native methods with `RegisterNatives` from `JNI_OnLoad`
+
+use crate::harvest_remainder_java_class_name;
+use crate::native_method::{
+    native_entry_point, native_value_type, with_lifetimes, NativeParameters, NativeReceiver,
+};
+use crate::{simple_identifier, MySignature};
+use proc_macro2::{Ident, TokenStream};
+use syn::parse::{Parse, ParseBuffer, ParseStream};
+use syn::{Path, ReturnType};
+
+/// `[static] fn javaName(arg_type, ...) [-> return_type] [= rust_function];`
+pub struct NativeBindingDecl {
+    is_static: bool,
+    java_name: Ident,
+    signature: MySignature,
+    return_type: ReturnType,
+    rust_function: Path,
+}
+
+impl Parse for NativeBindingDecl {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let is_static = if tokens.peek(Token![static]) {
+            let _static: Token![static] = tokens.parse()?;
+            true
+        } else {
+            false
+        };
+        let _fn: Token![fn] = tokens.parse()?;
+        let java_name: Ident = tokens.parse()?;
+        let signature: MySignature = tokens.parse()?;
+        let return_type: ReturnType = tokens.parse()?;
+        let rust_function: Path = if tokens.peek(Token![=]) {
+            let _eq: Token![=] = tokens.parse()?;
+            tokens.parse()?
+        } else {
+            java_name.clone().into()
+        };
+        let _semi: Token![;] = tokens.parse()?;
+
+        Ok(NativeBindingDecl {
+            is_static,
+            java_name,
+            signature,
+            return_type,
+            rust_function,
+        })
+    }
+}
+
+/// `package.path.ClassName { binding; ... }`
+pub struct NativeClassDecl {
+    class_name: String,
+    bindings: Vec<NativeBindingDecl>,
+}
+
+impl Parse for NativeClassDecl {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let ident: Ident = tokens.parse()?;
+        let class_name = harvest_remainder_java_class_name(tokens, ident.to_string())?;
+
+        let body: ParseBuffer;
+        braced!(body in tokens);
+        let mut bindings = Vec::new();
+        while !body.is_empty() {
+            bindings.push(body.parse()?);
+        }
+
+        Ok(NativeClassDecl {
+            class_name,
+            bindings,
+        })
+    }
+}
+
+pub struct RegisterNativesArgs {
+    init: Option<Path>,
+    shutdown: Option<Path>,
+    classes: Vec<NativeClassDecl>,
+}
+
+impl Parse for RegisterNativesArgs {
+    fn parse(tokens: ParseStream) -> Result<Self, syn::Error> {
+        let mut init = None;
+        let mut shutdown = None;
+        while tokens.peek(syn::Ident) && tokens.peek2(Token![=]) {
+            let hook: Ident = tokens.parse()?;
+            let _eq: Token![=] = tokens.parse()?;
+            let function: Path = tokens.parse()?;
+            let _semi: Token![;] = tokens.parse()?;
+            if hook == "init" {
+                init = Some(function);
+            } else if hook == "shutdown" {
+                shutdown = Some(function);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    hook,
+                    "expected `init = function;` or `shutdown = function;`",
+                ));
+            }
+        }
+
+        let mut classes = Vec::new();
+        while !tokens.is_empty() {
+            classes.push(tokens.parse()?);
+        }
+
+        Ok(RegisterNativesArgs {
+            init,
+            shutdown,
+            classes,
+        })
+    }
+}
+
+/// Generates a private entry point for every binding, and a `JNI_OnLoad` that registers them
+/// (plus a `JNI_OnUnload` if there is a shutdown hook).
+pub fn register_natives(args: &RegisterNativesArgs) -> TokenStream {
+    let mut entry_points: Vec<TokenStream> = Vec::new();
+    let mut registrations: Vec<TokenStream> = Vec::new();
+
+    for class in &args.classes {
+        let class_name = &class.class_name;
+        let mut methods: Vec<TokenStream> = Vec::new();
+
+        for binding in &class.bindings {
+            let entry_name = simple_identifier(&format!(
+                "native_entry_{}_{}",
+                entry_points.len(),
+                binding.java_name
+            ));
+            let params = NativeParameters {
+                takes_env: true,
+                receiver: NativeReceiver::Raw {
+                    is_static: binding.is_static,
+                },
+                java_types: binding.signature.parameter_types.clone(),
+            };
+            let rust_function = &binding.rust_function;
+            entry_points.push(native_entry_point(
+                &entry_name,
+                false,
+                &params,
+                &quote! { #rust_function },
+                &binding.return_type,
+            ));
+
+            let arg_types: Vec<_> = params
+                .java_types
+                .iter()
+                .map(|ty| with_lifetimes(ty, "'static"))
+                .collect();
+            let return_type = with_lifetimes(&native_value_type(&binding.return_type).0, "'static");
+            let java_name = binding.java_name.to_string();
+            methods.push(quote! {
+                {
+                    #[cfg(debug_assertions)]
+                    jni_boilerplate_helper::panic_if_bad_sigs( &[ #(<#arg_types as JavaSignatureFor>::signature_for(),)* <#return_type as JavaSignatureFor>::signature_for() ] );
+
+                    jni::NativeMethod {
+                        name: #java_name.into(),
+                        sig: (String::from("(") #(+&<#arg_types as JavaSignatureFor>::signature_for())* + ")"+&<#return_type as JavaSignatureFor>::signature_for()).into(),
+                        fn_ptr: #entry_name as *mut std::os::raw::c_void,
+                    }
+                }
+            });
+        }
+
+        registrations.push(quote! {
+            jni_env.register_native_methods(#class_name, &[#(#methods),*])?;
+        });
+    }
+
+    let init = match &args.init {
+        Some(init) => quote! { #init(jni_env)?; },
+        None => quote! {},
+    };
+
+    let on_unload = match &args.shutdown {
+        Some(shutdown) => quote! {
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            pub extern "system" fn JNI_OnUnload(vm: *mut jni::sys::JavaVM, _reserved: *mut std::os::raw::c_void) {
+                unsafe { jni_boilerplate_helper::register_natives::on_unload(vm, #shutdown) }
+            }
+        },
+        None => quote! {},
+    };
+
+    quote! {
+        const _: () = {
+            #(#entry_points)*
+
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            pub extern "system" fn JNI_OnLoad(vm: *mut jni::sys::JavaVM, _reserved: *mut std::os::raw::c_void) -> jni::sys::jint {
+                unsafe {
+                    jni_boilerplate_helper::register_natives::on_load(vm, |jni_env| {
+                        use jni_boilerplate_helper::JavaSignatureFor;
+
+                        #(#registrations)*
+                        #init
+                        Ok(())
+                    })
+                }
+            }
+
+            #on_unload
+        };
+    }
+}
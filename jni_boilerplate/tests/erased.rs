@@ -0,0 +1,114 @@
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni_boilerplate::{jni_class, jni_instance_method};
+use jni_boilerplate_helper::erased::Erased;
+use jni_boilerplate_helper::{
+    jni_wrapper_cliche_impl, jni_wrapper_cliche_impl_T, test_jvm, ConvertJValueToRust,
+    ConvertRustToJValue, JavaSignatureFor,
+};
+use std::marker::PhantomData;
+
+jni_wrapper_cliche_impl_T! { ArrayList, "java/util/ArrayList" }
+jni_wrapper_cliche_impl! { Comparable, "java/lang/Comparable" }
+jni_wrapper_cliche_impl! { JavaEnum, "java/lang/Enum" }
+jni_wrapper_cliche_impl! { TimeUnit, "java/util/concurrent/TimeUnit" }
+
+#[jni_class]
+impl<'a: 'b, 'b, T> ArrayList<'a, 'b, T>
+where
+    T: ConvertJValueToRust<'a, 'b> + ConvertRustToJValue<'a, 'b> + JavaSignatureFor,
+{
+    #[constructor]
+    fn new() -> Self;
+
+    // add(E) and get(int) are add(Object) and get(int) -> Object to the JVM
+    fn add(&self, element: Erased<T>) -> bool;
+    fn get(&self, index: i32) -> Erased<T>;
+    fn size(&self) -> i32;
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> TimeUnit<'a, 'b> {
+    #[java_name = "valueOf"]
+    fn value_of(name: &str) -> TimeUnit<'a, 'b>;
+}
+
+impl<'a: 'b, 'b> TimeUnit<'a, 'b> {
+    // Enum<E extends Enum<E>>.compareTo(E) is compareTo(Enum) to the JVM
+    jni_instance_method! { compare_to=compareTo(Erased<&TimeUnit<'a, 'b>, JavaEnum<'a, 'b>>) -> i32 }
+}
+
+impl<'a: 'b, 'b> Comparable<'a, 'b> {
+    // Comparable<T>.compareTo(T) is compareTo(Object)
+    jni_instance_method! { compare_to=compareTo(Erased<i32>) -> i32 }
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn generic_methods() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let words: ArrayList<String> = ArrayList::new(&jni_env)?;
+    assert!(words.add(String::from("apple").into())?);
+    assert!(words.add(Erased::new(String::from("banana")))?);
+    assert_eq!(2, words.size()?);
+    assert_eq!("banana", words.get(1)?.into_inner());
+    assert_eq!(5, words.get(0)?.len());
+
+    let numbers: ArrayList<i32> = ArrayList::new(&jni_env)?;
+    numbers.add(7.into())?;
+    numbers.add((-3).into())?;
+    assert_eq!(4, *numbers.get(0)? + *numbers.get(1)?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn bounded() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let seconds = TimeUnit::value_of(&jni_env, "SECONDS")?;
+    let minutes = TimeUnit::value_of(&jni_env, "MINUTES")?;
+    assert!(seconds.compare_to(Erased::new(&minutes))? < 0);
+
+    let numbers: ArrayList<i32> = ArrayList::new(&jni_env)?;
+    numbers.add(5.into())?;
+    let five = ArrayList::<Comparable>::wrap(&jni_env, numbers)
+        .get(0)?
+        .into_inner();
+    assert!(five.compare_to(Erased::new(9))? < 0);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn mismatched() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let numbers: ArrayList<i32> = ArrayList::new(&jni_env)?;
+    numbers.add(5.into())?;
+    // an Integer is not a String: a ClassCastException, cleared and reported like any other
+    let as_strings = ArrayList::<String>::wrap(&jni_env, numbers);
+    assert!(as_strings.get(0).is_err());
+    assert!(!jni_env.exception_check()?);
+    Ok(())
+}
+
+impl<'a: 'b, 'b, T> ArrayList<'a, 'b, T>
+where
+    T: ConvertJValueToRust<'a, 'b>,
+{
+    /// the same list, for elements of another type
+    fn wrap<U: ConvertJValueToRust<'a, 'b>>(
+        jni_env: &'b jni::JNIEnv<'a>,
+        list: ArrayList<'a, 'b, U>,
+    ) -> ArrayList<'a, 'b, T> {
+        ArrayList {
+            java_this: list.java_this,
+            jni_env,
+            phantom: PhantomData,
+        }
+    }
+}
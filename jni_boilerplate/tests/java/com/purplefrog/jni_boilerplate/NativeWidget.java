@@ -0,0 +1,36 @@
+package com.purplefrog.jni_boilerplate;
+
+/**
+ * A class whose native methods tests/register_natives.rs binds with jni_register_natives!.
+ * The test defines it at run time from the compiled class next to this file.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d tests/java tests/java/com/purplefrog/jni_boilerplate/NativeWidget.java</pre>
+ * from the jni_boilerplate directory.
+ */
+public final class NativeWidget
+{
+    private final String label;
+
+    public NativeWidget(String label)
+    {
+        this.label = label;
+    }
+
+    public String getLabel()
+    {
+        return label;
+    }
+
+    public native String describe(int count);
+
+    public native String describe(String suffix);
+
+    public static native long sum(int[] values);
+
+    /** calls the natives from java */
+    public String describeBoth(int count)
+    {
+        return describe(count) + ", " + describe("!") + ", " + sum(new int[] {count, count});
+    }
+}
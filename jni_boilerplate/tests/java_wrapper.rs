@@ -0,0 +1,136 @@
+//! Runs the impls `#[derive(JavaWrapper)]` generates against real java objects.
+
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni::objects::AutoLocal;
+use jni::JNIEnv;
+use jni_boilerplate::{jni_class, JavaWrapper};
+use jni_boilerplate_helper::erased::Erased;
+use jni_boilerplate_helper::inheritance::upcast_ref;
+use jni_boilerplate_helper::{
+    jni_extends, test_jvm, ConvertJValueToRust, ConvertRustToJValue, JavaConstructible,
+    JavaSignatureFor,
+};
+use std::marker::PhantomData;
+
+/// a `java.lang.StringBuilder`, with rust-side state of its own
+#[derive(JavaWrapper)]
+#[java_class("java.lang.StringBuilder")]
+struct Tally<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+    appended: usize,
+    notes: Vec<String>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> Tally<'a, 'b> {
+    #[constructor]
+    fn new() -> Self;
+    #[java_name = "append"]
+    fn append_str(&self, text: &str) -> Tally<'a, 'b>;
+    #[java_name = "toString"]
+    fn contents(&self) -> String;
+
+    fn push(&mut self, text: &str) -> Result<(), Error> {
+        self.append_str(text)?;
+        self.appended += 1;
+        self.notes.push(String::from(text));
+        Ok(())
+    }
+}
+
+/// a `java.util.ArrayList` of `T`
+#[derive(JavaWrapper)]
+#[java_class("java.util.ArrayList")]
+struct TypedList<'a: 'b, 'b, T> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+    phantom: PhantomData<T>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b, T> TypedList<'a, 'b, T>
+where
+    T: ConvertJValueToRust<'a, 'b> + ConvertRustToJValue<'a, 'b> + JavaSignatureFor,
+{
+    #[constructor]
+    fn new() -> Self;
+    fn add(&self, element: Erased<T>) -> bool;
+    fn get(&self, index: i32) -> Erased<T>;
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.StringBuilder")]
+#[repr(C)]
+struct PlainBuilder<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> PlainBuilder<'a, 'b> {
+    #[constructor]
+    fn with_text(text: &str) -> Self;
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.CharSequence")]
+#[repr(C)]
+struct CharSequence<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> CharSequence<'a, 'b> {
+    fn length(&self) -> i32;
+}
+
+jni_extends! { PlainBuilder implements CharSequence }
+
+#[test]
+#[ignore = "needs a JVM"]
+fn extra_fields_start_as_default() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let mut tally = Tally::new(&jni_env)?;
+    assert_eq!(0, tally.appended);
+    assert!(tally.notes.is_empty());
+    tally.push("one")?;
+    tally.push("two")?;
+    assert_eq!(2, tally.appended);
+    assert_eq!(vec!["one", "two"], tally.notes);
+    assert_eq!("onetwo", tally.contents()?);
+
+    // a wrapper returned from java is a fresh one, with its own state
+    let returned = tally.append_str("three")?;
+    assert_eq!(0, returned.appended);
+    assert_eq!("onetwothree", returned.contents()?);
+
+    // and so is one made straight from a reference
+    let object = jni_env.new_object("java/lang/StringBuilder", "()V", &[])?;
+    let wrapped = Tally::wrap_jobject(&jni_env, AutoLocal::new(&jni_env, object));
+    assert!(wrapped.notes.is_empty());
+    assert_eq!("", wrapped.contents()?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn generic_and_plain_wrappers() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let words: TypedList<String> = TypedList::new(&jni_env)?;
+    assert!(words.add(Erased::new(String::from("apple")))?);
+    assert_eq!("apple", words.get(0)?.into_inner());
+    assert_eq!("Ljava/util/ArrayList;", TypedList::<i32>::signature_for());
+
+    // a #[repr(C)] wrapper of nothing but the two references can be viewed as its interface's
+    let builder = PlainBuilder::with_text(&jni_env, "seven")?;
+    let sequence: &CharSequence = upcast_ref(&builder);
+    assert_eq!(5, sequence.length()?);
+    Ok(())
+}
@@ -0,0 +1,478 @@
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni::objects::AutoLocal;
+use jni::JNIEnv;
+use jni_boilerplate::{jni_class, jni_nonvirtual_method, jni_static_method, JavaEnum, JavaWrapper};
+use jni_boilerplate_helper::erased::Erased;
+use jni_boilerplate_helper::global::{Global, ToGlobal};
+use jni_boilerplate_helper::inheritance::JavaObjectWrapper;
+use jni_boilerplate_helper::java_any::JavaAny;
+use jni_boilerplate_helper::test_jvm;
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.StringBuilder")]
+struct StringBuilder<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+    appended: usize,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> StringBuilder<'a, 'b> {
+    #[constructor]
+    fn new() -> Self;
+    #[constructor]
+    fn with_text(text: &str) -> Self;
+    #[constructor]
+    #[implicit_env]
+    fn with_text_here(text: &str) -> Self;
+
+    fn length(&self) -> i32;
+    #[java_name = "append"]
+    fn append_str(&self, text: &str) -> StringBuilder<'a, 'b>;
+    #[java_name = "append"]
+    fn append_int(&self, value: i32) -> StringBuilder<'a, 'b>;
+    #[java_name = "toString"]
+    fn contents(&self) -> String;
+
+    fn push(&mut self, text: &str) -> Result<(), Error> {
+        self.append_str(text)?;
+        self.appended += 1;
+        Ok(())
+    }
+}
+
+/// the same class, with its overloads named by the macro
+#[derive(JavaWrapper)]
+#[java_class("java.lang.StringBuilder")]
+struct Builder<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class(overloads = "types")]
+impl<'a: 'b, 'b> Builder<'a, 'b> {
+    #[constructor]
+    fn new() -> Self;
+    #[constructor]
+    fn new(text: &str) -> Self;
+
+    fn append(&self, text: &str) -> Builder<'a, 'b>;
+    fn append(&self, value: i32) -> Builder<'a, 'b>;
+    fn append(&self, value: char) -> Builder<'a, 'b>;
+    fn append(&self, value: bool) -> Builder<'a, 'b>;
+    #[java_name = "append"]
+    fn append_number(&self, value: i64) -> Builder<'a, 'b>;
+    #[java_name = "toString"]
+    fn contents(&self) -> String;
+
+    #[java_name = "indexOf"]
+    fn index_of(&self, text: &str) -> i32;
+    #[java_name = "indexOf"]
+    fn index_of(&self, text: &str, from: i32) -> i32;
+}
+
+struct Integer;
+
+#[jni_class("java.lang.Integer")]
+impl Integer {
+    #[static_method]
+    fn parseInt(text: &str) -> i32;
+    #[java_name = "toString"]
+    fn to_string_radix(value: i32, radix: i32) -> String;
+    #[implicit_env]
+    #[java_name = "parseInt"]
+    fn parse_int_here(text: &str) -> i32;
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn string_builder() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let mut builder = StringBuilder::with_text(&jni_env, "x=")?;
+    builder.append_int(42)?;
+    builder.push("!")?;
+    assert_eq!("x=42!", builder.contents()?);
+    assert_eq!(5, builder.length()?);
+    assert_eq!(1, builder.appended);
+
+    // the wrapper made from a returned object starts with fresh rust-side fields
+    let same = builder.append_str("?")?;
+    assert_eq!(0, same.appended);
+    assert_eq!("x=42!?", same.contents()?);
+
+    assert_eq!(0, StringBuilder::new(&jni_env)?.length()?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn overload_naming() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let builder = Builder::new_string(&jni_env, "x")?;
+    builder.append_int(1)?;
+    builder.append_char('=')?;
+    builder.append_boolean(true)?;
+    builder.append_string(";")?;
+    builder.append_number(1 << 40)?;
+    assert_eq!("x1=true;1099511627776", builder.contents()?);
+    assert_eq!("", Builder::new(&jni_env)?.contents()?);
+
+    assert_eq!(1, builder.index_of_string("1")?);
+    assert_eq!(8, builder.index_of_string_int("1", 2)?);
+    Ok(())
+}
+
+struct Arrays;
+
+#[jni_class("java.util.Arrays")]
+impl Arrays {
+    fn fill(values: &mut [i32], value: i32);
+    #[java_name = "toString"]
+    fn to_string_ints(values: &[i32]) -> String;
+}
+
+struct Objects;
+
+#[jni_class("java.util.Objects")]
+impl Objects {
+    // a varargs parameter is the array it is passed as
+    fn hash(values: &[Erased<i32>]) -> i32;
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.nio.file.Path")]
+struct JavaPath<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> JavaPath<'a, 'b> {
+    fn of(first: &str, more: &[&str]) -> JavaPath<'a, 'b>;
+    #[java_name = "toString"]
+    fn to_text(&self) -> String;
+    #[java_name = "getNameCount"]
+    fn name_count(&self) -> i32;
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn varargs() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let words = [
+        Erased::new(String::from("one")),
+        Erased::new(String::from("two")),
+    ];
+    assert_eq!("one, two", JavaString::format(&jni_env, "%s, %s", &words)?);
+    assert_eq!("none", JavaString::format(&jni_env, "none", &[])?);
+
+    // Arrays.hashCode() of the boxed values
+    let values = [1, 2, 3].map(Erased::new);
+    assert_eq!(
+        ((31 + 1) * 31 + 2) * 31 + 3,
+        Objects::hash(&jni_env, &values)?
+    );
+
+    let path = JavaPath::of(&jni_env, "usr", &["lib", "jvm"])?;
+    // the separator between the names is the platform's
+    assert_eq!(3, path.name_count()?);
+    assert!(path.to_text()?.starts_with("usr"));
+    Ok(())
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.util.ArrayList")]
+struct ArrayList<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> ArrayList<'a, 'b> {
+    #[constructor]
+    fn new() -> Self;
+    fn add(&self, value: JavaAny<'a, 'b>) -> bool;
+    fn get(&self, index: i32) -> JavaAny<'a, 'b>;
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn java_any() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let list = ArrayList::new(&jni_env)?;
+    list.add(JavaAny::from(3))?;
+    list.add(JavaAny::from("three"))?;
+    list.add(JavaAny::from(StringBuilder::with_text(&jni_env, "built")?))?;
+    list.add(JavaAny::Null)?;
+
+    assert!(matches!(list.get(0)?, JavaAny::Int(3)));
+    assert_eq!(Some("three"), list.get(1)?.as_string());
+    let Ok(builder) = list.get(2)?.downcast::<StringBuilder>(&jni_env) else {
+        panic!("not a StringBuilder");
+    };
+    assert_eq!("built", builder.contents()?);
+    assert!(list.get(3)?.is_null());
+
+    let args = [JavaAny::from(1), JavaAny::from("two"), JavaAny::from(3.5)];
+    assert_eq!(
+        "1 two 3.5",
+        JavaString::format_any(&jni_env, "%d %s %s", &args)?
+    );
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, JavaEnum)]
+#[java_class("java.util.concurrent.TimeUnit")]
+enum TimeUnit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+    Minutes,
+    Hours,
+    Days,
+}
+
+#[jni_class]
+impl TimeUnit {
+    fn of(unit: ChronoUnit) -> TimeUnit;
+    fn values() -> Vec<TimeUnit>;
+}
+
+/// only some of the constants, in an order of our own
+#[derive(Clone, Copy, Debug, PartialEq, JavaEnum)]
+#[java_class("java.time.temporal.ChronoUnit")]
+enum ChronoUnit {
+    Days,
+    Hours,
+    #[java_name = "SECONDS"]
+    Secs,
+}
+
+#[jni_class]
+impl ChronoUnit {
+    #[java_name = "valueOf"]
+    fn value_of(name: &str) -> ChronoUnit;
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn java_enum() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    assert_eq!(TimeUnit::Hours, TimeUnit::of(&jni_env, ChronoUnit::Hours)?);
+    assert_eq!(TimeUnit::Seconds, TimeUnit::of(&jni_env, ChronoUnit::Secs)?);
+    assert_eq!(
+        vec![
+            TimeUnit::Nanoseconds,
+            TimeUnit::Microseconds,
+            TimeUnit::Milliseconds,
+            TimeUnit::Seconds,
+            TimeUnit::Minutes,
+            TimeUnit::Hours,
+            TimeUnit::Days,
+        ],
+        TimeUnit::values(&jni_env)?
+    );
+
+    assert_eq!(ChronoUnit::Days, ChronoUnit::value_of(&jni_env, "DAYS")?);
+    // a constant without a variant
+    assert!(ChronoUnit::value_of(&jni_env, "WEEKS").is_err());
+    // the conversion's IllegalArgumentException is not left pending
+    assert!(!jni_env.exception_check()?);
+    assert_eq!(ChronoUnit::Hours, ChronoUnit::value_of(&jni_env, "HOURS")?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn static_methods() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    assert_eq!(1234, Integer::parseInt(&jni_env, "1234")?);
+    assert_eq!("ff", Integer::to_string_radix(&jni_env, 255, 16)?);
+    assert!(Integer::parseInt(&jni_env, "twelve").is_err());
+    Ok(())
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.CharSequence")]
+#[repr(C)]
+struct CharSequence<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> CharSequence<'a, 'b> {
+    fn length(&self) -> i32;
+    #[java_name = "charAt"]
+    fn char_at(&self, index: i32) -> char;
+}
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.String")]
+#[repr(C)]
+struct JavaString<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> JavaString<'a, 'b> {
+    #[constructor]
+    fn new(text: &str) -> Self;
+    #[java_name = "toUpperCase"]
+    fn to_upper_case(&self) -> String;
+    #[java_name = "hashCode"]
+    fn hash_code(&self) -> i32;
+    #[nonvirtual = "java.lang.Object"]
+    #[java_name = "toString"]
+    fn object_to_string(&self) -> String;
+}
+
+impl<'a: 'b, 'b> JavaString<'a, 'b> {
+    jni_nonvirtual_method! { java.lang.Object, identity_hash_code=hashCode() -> i32 }
+    jni_static_method! { format(&str, ...Erased<String>) -> String }
+    jni_static_method! { 'a, 'b, format_any=format(&str, ...JavaAny<'a, 'b>) -> String }
+}
+
+jni_boilerplate_helper::jni_extends! { JavaString extends CharSequence }
+
+#[test]
+#[ignore = "needs a JVM"]
+fn inheritance() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let text = JavaString::new(&jni_env, "abc")?;
+    assert_eq!("ABC", text.to_upper_case()?);
+    // declared on CharSequence, reached through Deref
+    assert_eq!(3, text.length()?);
+    assert_eq!('b', text.char_at(1)?);
+
+    let sequence: CharSequence = text.upcast();
+    assert_eq!(3, sequence.length()?);
+    let Ok(text) = sequence.downcast::<JavaString>() else {
+        panic!("a String is a CharSequence");
+    };
+    assert_eq!("ABC", text.to_upper_case()?);
+
+    // a failed cast hands the wrapper back, and throws nothing
+    let Err(text) = text.downcast::<StringBuilder>() else {
+        panic!("a String is not a StringBuilder");
+    };
+    assert_eq!("ABC", text.to_upper_case()?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn nonvirtual() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    // String overrides hashCode() and toString(); these call Object's instead
+    let text = JavaString::new(&jni_env, "abc")?;
+    let identity = jni_env
+        .call_static_method(
+            "java/lang/System",
+            "identityHashCode",
+            "(Ljava/lang/Object;)I",
+            &[text.java_this.as_obj().into()],
+        )?
+        .i()?;
+    assert_eq!(identity, text.identity_hash_code()?);
+    assert_eq!(
+        "abc".chars().fold(0, |h, c| 31 * h + c as i32),
+        text.hash_code()?
+    );
+    assert_eq!(
+        format!("java.lang.String@{:x}", text.hash_code()?),
+        text.object_to_string()?
+    );
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn global() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+    let builder: Global<StringBuilder<'static, 'static>> =
+        StringBuilder::with_text(&jni_env, "made here")?.to_global()?;
+
+    let contents = std::thread::spawn(move || {
+        let jni_env = builder.attach()?;
+        let builder = builder.with_local(&jni_env)?;
+        builder.append_str(", used there")?;
+        builder.contents()
+    })
+    .join()
+    .unwrap()?;
+    assert_eq!("made here, used there", contents);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn implicit_env() -> Result<(), Error> {
+    test_jvm::java_vm();
+
+    let (number, contents) = std::thread::spawn(|| {
+        let number = Integer::parse_int_here("42")?;
+        let builder = StringBuilder::with_text_here("from a thread java never saw")?;
+        Ok::<_, Error>((number, builder.contents()?))
+    })
+    .join()
+    .unwrap()?;
+    assert_eq!(42, number);
+    assert_eq!("from a thread java never saw", contents);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn local_frames() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    // each call's class, array and string references go with its frame
+    let mut values = [0; 3];
+    for i in 0..10_000 {
+        Arrays::fill(&jni_env, &mut values, i)?;
+        assert_eq!(
+            format!("[{0}, {0}, {0}]", i),
+            Arrays::to_string_ints(&jni_env, &values)?
+        );
+    }
+
+    // while a returned wrapper's reference outlives the call's frame
+    let builder = StringBuilder::with_text(&jni_env, "still ")?.append_str("here")?;
+    assert_eq!("still here", builder.contents()?);
+    Ok(())
+}
+
+#[cfg(feature = "leak_check")]
+#[test]
+#[ignore = "needs a JVM"]
+fn leak_free() -> Result<(), Error> {
+    use jni_boilerplate_helper::leak_check::leaked_local_refs;
+
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    // a wrapper's reference is deleted when it is dropped, and a converted String's right away
+    let leaked = leaked_local_refs(&jni_env, || {
+        let mut values = [1, 2, 3];
+        Arrays::fill(&jni_env, &mut values, 4)?;
+        assert_eq!("[4, 4, 4]", Arrays::to_string_ints(&jni_env, &values)?);
+        let builder = StringBuilder::with_text(&jni_env, "counted")?.append_str(" twice")?;
+        assert_eq!("counted twice", builder.contents()?);
+        Ok(())
+    })?;
+    assert_eq!(0, leaked);
+    Ok(())
+}
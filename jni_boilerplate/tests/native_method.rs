@@ -0,0 +1,90 @@
+//! Calls the `Java_...` entry points `#[jni_native_method]` exports directly, as the JVM would.
+
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni::objects::{JObject, JValue};
+use jni::JNIEnv;
+use jni_boilerplate::{jni_class, jni_native_method};
+use jni_boilerplate_helper::{
+    jni_wrapper_cliche_impl, test_jvm, unwrap_jobject, wrap_jobject, ConvertJValueToRust,
+};
+
+jni_wrapper_cliche_impl! { StringBuilder, "java/lang/StringBuilder" }
+
+#[jni_class]
+impl<'a: 'b, 'b> StringBuilder<'a, 'b> {
+    #[constructor]
+    fn with_text(text: &str) -> Self;
+    fn length(&self) -> i32;
+}
+
+#[jni_native_method(com.purplefrog.rust_callables.Widget)]
+fn nativeLabel(this: StringBuilder, prefix: String) -> Result<String, Error> {
+    Ok(format!("{}{}", prefix, this.length()?))
+}
+
+#[jni_native_method(static com.purplefrog.rust_callables.Widget)]
+fn nativeSum(values: Vec<i32>, scale: i64) -> Result<i64, Error> {
+    if scale < 0 {
+        // an Err with nothing pending becomes a RuntimeException
+        return Err(Error::from("negative scale"));
+    }
+    Ok(values.iter().map(|&v| v as i64).sum::<i64>() * scale)
+}
+
+/// the `JNIEnv` the JVM would pass, by value
+fn env_by_value<'a>(jni_env: &JNIEnv<'a>) -> Result<JNIEnv<'a>, Error> {
+    unsafe { JNIEnv::from_raw(jni_env.get_native_interface()) }
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn exported_entry_points() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let builder = StringBuilder::with_text(&jni_env, "seven")?;
+    // java's references to the arguments belong to the native method, which deletes them
+    let this = unwrap_jobject(jni_env.new_local_ref::<JObject>(builder.java_this.as_obj())?);
+    let prefix = unwrap_jobject(JObject::from(jni_env.new_string("length ")?));
+    let label = Java_com_purplefrog_rust_1callables_Widget_nativeLabel(
+        env_by_value(&jni_env)?,
+        this,
+        prefix,
+    );
+    let label = String::to_rust(&jni_env, JValue::from(wrap_jobject(label)))?;
+    assert_eq!("length 5", label);
+
+    let values = || -> Result<_, Error> {
+        let values = jni_env.new_int_array(3)?;
+        jni_env.set_int_array_region(values, 0, &[1, 2, 3])?;
+        Ok(unwrap_jobject(JObject::from(values)))
+    };
+    let class = unwrap_jobject(JObject::from(jni_env.find_class("java/lang/Object")?));
+    let sum = Java_com_purplefrog_rust_1callables_Widget_nativeSum(
+        env_by_value(&jni_env)?,
+        class,
+        values()?,
+        10,
+    );
+    assert_eq!(60, sum);
+    assert!(!jni_env.exception_check()?);
+
+    // the Err comes back to java as a pending exception, and a default value
+    let sum = Java_com_purplefrog_rust_1callables_Widget_nativeSum(
+        env_by_value(&jni_env)?,
+        class,
+        values()?,
+        -1,
+    );
+    assert_eq!(0, sum);
+    assert!(jni_env.exception_check()?);
+    let throwable = jni_env.exception_occurred()?;
+    jni_env.exception_clear()?;
+    assert!(jni_env.is_instance_of(*throwable, "java/lang/RuntimeException")?);
+    let message = jni_env.call_method(*throwable, "getMessage", "()Ljava/lang/String;", &[])?;
+    let message = String::to_rust(&jni_env, message)?;
+    assert!(message.contains("negative scale"), "{}", message);
+    Ok(())
+}
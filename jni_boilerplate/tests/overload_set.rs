@@ -0,0 +1,80 @@
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni::objects::AutoLocal;
+use jni::JNIEnv;
+use jni_boilerplate::{jni_class, jni_overload_set, JavaWrapper};
+use jni_boilerplate_helper::test_jvm;
+
+#[derive(JavaWrapper)]
+#[java_class("java.lang.StringBuilder")]
+struct StringBuilder<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+#[jni_class]
+impl<'a: 'b, 'b> StringBuilder<'a, 'b> {
+    #[constructor]
+    fn new() -> Self;
+    #[java_name = "toString"]
+    fn contents(&self) -> String;
+}
+
+jni_overload_set! { StringBuilder<'a, 'b>, append: Append {
+    (&str) -> StringBuilder<'a, 'b>;
+    (i32) -> StringBuilder<'a, 'b>;
+    (i64) -> StringBuilder<'a, 'b>;
+    (char) -> StringBuilder<'a, 'b>;
+    (bool) -> StringBuilder<'a, 'b>;
+    (&[char], i32, i32) -> StringBuilder<'a, 'b>;
+} }
+
+jni_overload_set! { StringBuilder<'a, 'b>, index_of=indexOf: IndexOf {
+    (&str) -> i32;
+    (&str, i32) -> i32;
+} }
+
+jni_overload_set! { StringBuilder<'a, 'b>, pub clear=setLength: Clear {
+    (i32);
+} }
+
+/// generic code names the overload set through its trait
+fn append_all<'a: 'b, 'b, T: Append<'a, 'b>>(
+    builder: &StringBuilder<'a, 'b>,
+    values: Vec<T>,
+) -> Result<(), Error> {
+    for value in values {
+        builder.append(value)?;
+    }
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn dispatch() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+    let builder = StringBuilder::new(&jni_env)?;
+    builder.append("x=")?;
+    // with both i32 and i64 overloads, an unsuffixed literal is ambiguous
+    builder.append(1i32)?;
+    builder.append(1i64 << 40)?;
+    builder.append(',')?;
+    builder.append(true)?;
+    builder.append((&['a', 'b', 'c', 'd'][..], 1, 2))?;
+    assert_eq!("x=11099511627776,truebc", builder.contents()?);
+
+    let returned = builder.append("!")?;
+    assert_eq!(builder.contents()?, returned.contents()?);
+
+    assert_eq!(2, builder.index_of("1")?);
+    assert_eq!(3, builder.index_of(("1", 3))?);
+    assert_eq!(-1, builder.index_of("?")?);
+
+    builder.clear(0)?;
+    append_all(&builder, vec![1, 2, 3])?;
+    assert_eq!("123", builder.contents()?);
+    Ok(())
+}
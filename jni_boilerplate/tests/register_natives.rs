@@ -0,0 +1,117 @@
+//! Runs the `JNI_OnLoad` that `jni_register_natives!` generates against a class defined at run time,
+//! then calls the natives it registered from java.
+
+// written against the jni 0.17 dev-dependency, which is only the helper's jni without the jni_0_18/jni_0_20 features
+#![cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JClass, JObject, JValue};
+use jni::sys::{jint, JNI_VERSION_1_6};
+use jni::{JNIEnv, JavaVM};
+use jni_boilerplate::jni_register_natives;
+use jni_boilerplate_helper::{test_jvm, ConvertJValueToRust};
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+fn describe_int(jni_env: &JNIEnv, this: JObject, count: i32) -> Result<String, Error> {
+    let label = jni_env.call_method(this, "getLabel", "()Ljava/lang/String;", &[])?;
+    Ok(format!("{} x{}", String::to_rust(jni_env, label)?, count))
+}
+
+fn describe_string(_jni_env: &JNIEnv, _this: JObject, suffix: String) -> String {
+    format!("described{}", suffix)
+}
+
+fn sum(_jni_env: &JNIEnv, _class: JClass, values: Vec<i32>) -> i64 {
+    values.iter().map(|&v| v as i64).sum()
+}
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+fn init(jni_env: &JNIEnv) -> Result<(), Error> {
+    // the natives are registered by the time the hook runs
+    jni_env.find_class("com/purplefrog/jni_boilerplate/NativeWidget")?;
+    INITIALIZED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+fn shutdown(_vm: &JavaVM) {
+    SHUT_DOWN.store(true, Ordering::SeqCst);
+}
+
+jni_register_natives! {
+    init = init;
+    shutdown = shutdown;
+    com.purplefrog.jni_boilerplate.NativeWidget {
+        fn describe(i32) -> Result<String, Error> = describe_int;
+        fn describe(String) -> String = describe_string;
+        static fn sum(Vec<i32>) -> i64;
+    }
+}
+
+// the generated functions are only reachable by their symbols, as the JVM reaches them
+extern "system" {
+    fn JNI_OnLoad(vm: *mut jni::sys::JavaVM, reserved: *mut c_void) -> jint;
+    fn JNI_OnUnload(vm: *mut jni::sys::JavaVM, reserved: *mut c_void);
+}
+
+/// defines the class in the system class loader, where `RegisterNatives` will look for it
+fn define_widget_class(jni_env: &JNIEnv) -> Result<(), Error> {
+    let loader = jni_env
+        .call_static_method(
+            "java/lang/ClassLoader",
+            "getSystemClassLoader",
+            "()Ljava/lang/ClassLoader;",
+            &[],
+        )?
+        .l()?;
+    let loader = AutoLocal::new(jni_env, loader);
+    let class = jni_env.define_class(
+        "com/purplefrog/jni_boilerplate/NativeWidget",
+        loader.as_obj(),
+        include_bytes!("java/com/purplefrog/jni_boilerplate/NativeWidget.class"),
+    )?;
+    jni_env.delete_local_ref(*class)?;
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn registered_natives() -> Result<(), Error> {
+    let vm = test_jvm::java_vm();
+    let jni_env = vm.attach_current_thread()?;
+    define_widget_class(&jni_env)?;
+
+    let version = unsafe { JNI_OnLoad(vm.get_java_vm_pointer(), std::ptr::null_mut()) };
+    assert_eq!(JNI_VERSION_1_6, version);
+    assert!(INITIALIZED.load(Ordering::SeqCst));
+
+    let label = jni_env.new_string("widget")?;
+    let widget = jni_env.new_object(
+        "com/purplefrog/jni_boilerplate/NativeWidget",
+        "(Ljava/lang/String;)V",
+        &[JValue::from(JObject::from(label))],
+    )?;
+
+    // each overload was registered under its own descriptor
+    let by_count = jni_env.call_method(
+        widget,
+        "describe",
+        "(I)Ljava/lang/String;",
+        &[JValue::Int(3)],
+    )?;
+    assert_eq!("widget x3", String::to_rust(&jni_env, by_count)?);
+
+    let both = jni_env.call_method(
+        widget,
+        "describeBoth",
+        "(I)Ljava/lang/String;",
+        &[JValue::Int(4)],
+    )?;
+    assert_eq!("widget x4, described!, 8", String::to_rust(&jni_env, both)?);
+
+    unsafe { JNI_OnUnload(vm.get_java_vm_pointer(), std::ptr::null_mut()) };
+    assert!(SHUT_DOWN.load(Ordering::SeqCst));
+    Ok(())
+}
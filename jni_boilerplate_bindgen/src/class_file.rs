@@ -0,0 +1,424 @@
+//! Just enough of the class file format (chapter 4 of the JVM specification) to generate bindings:
+//! the constant pool, the class's name and super types, and its field and method tables
+//! with the `ConstantValue` of `static final` fields.
+
+use crate::Error;
+
+/// access flags of classes, fields and methods
+pub mod access {
+    pub const PUBLIC: u16 = 0x0001;
+    pub const PRIVATE: u16 = 0x0002;
+    pub const PROTECTED: u16 = 0x0004;
+    pub const STATIC: u16 = 0x0008;
+    pub const FINAL: u16 = 0x0010;
+    pub const BRIDGE: u16 = 0x0040;
+    pub const VARARGS: u16 = 0x0080;
+    pub const INTERFACE: u16 = 0x0200;
+    pub const ABSTRACT: u16 = 0x0400;
+    pub const SYNTHETIC: u16 = 0x1000;
+    pub const ENUM: u16 = 0x4000;
+}
+
+/// the value of a `static final` field that the compiler folded into the class file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    /// also `boolean`, `byte`, `char` and `short` constants
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub access_flags: u16,
+    pub name: String,
+    /// e.g. `I` or `Ljava/lang/String;`
+    pub descriptor: String,
+    pub constant_value: Option<Constant>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Method {
+    pub access_flags: u16,
+    /// `<init>` for constructors
+    pub name: String,
+    /// e.g. `(ILjava/lang/String;)V`
+    pub descriptor: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassFile {
+    pub access_flags: u16,
+    /// slash-separated, e.g. `com/example/Widget$Inner`
+    pub name: String,
+    /// `None` only for `java/lang/Object`
+    pub super_name: Option<String>,
+    pub interfaces: Vec<String>,
+    pub fields: Vec<Field>,
+    pub methods: Vec<Method>,
+}
+
+impl ClassFile {
+    pub fn parse(bytes: &[u8]) -> Result<ClassFile, Error> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.u4()? != 0xCAFE_BABE {
+            return Err(Error::Format(String::from("not a class file (bad magic)")));
+        }
+        let _minor_version = reader.u2()?;
+        let _major_version = reader.u2()?;
+        let pool = ConstantPool::parse(&mut reader)?;
+
+        let access_flags = reader.u2()?;
+        let name = pool.class_name(reader.u2()?)?;
+        let super_name = match reader.u2()? {
+            0 => None,
+            index => Some(pool.class_name(index)?),
+        };
+        let interfaces = (0..reader.u2()?)
+            .map(|_| pool.class_name(reader.u2()?))
+            .collect::<Result<_, _>>()?;
+
+        let mut fields = Vec::new();
+        for _ in 0..reader.u2()? {
+            let access_flags = reader.u2()?;
+            let name = pool.utf8(reader.u2()?)?;
+            let descriptor = pool.utf8(reader.u2()?)?;
+            let mut constant_value = None;
+            for _ in 0..reader.u2()? {
+                let attribute_name = pool.utf8(reader.u2()?)?;
+                let length = reader.u4()? as usize;
+                let body = reader.bytes(length)?;
+                if attribute_name == "ConstantValue" && body.len() == 2 {
+                    constant_value = pool.constant(u16::from_be_bytes([body[0], body[1]]))?;
+                }
+            }
+            fields.push(Field {
+                access_flags,
+                name,
+                descriptor,
+                constant_value,
+            });
+        }
+
+        let mut methods = Vec::new();
+        for _ in 0..reader.u2()? {
+            let access_flags = reader.u2()?;
+            let name = pool.utf8(reader.u2()?)?;
+            let descriptor = pool.utf8(reader.u2()?)?;
+            reader.skip_attributes()?;
+            methods.push(Method {
+                access_flags,
+                name,
+                descriptor,
+            });
+        }
+
+        Ok(ClassFile {
+            access_flags,
+            name,
+            super_name,
+            interfaces,
+            fields,
+            methods,
+        })
+    }
+
+    /// `com.example.Widget$Inner`
+    pub fn dotted_name(&self) -> String {
+        self.name.replace('/', ".")
+    }
+}
+
+//
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let end = self.position + count;
+        if end > self.bytes.len() {
+            return Err(Error::Format(String::from("truncated class file")));
+        }
+        let rval = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(rval)
+    }
+
+    fn u1(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u2(&mut self) -> Result<u16, Error> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u4(&mut self) -> Result<u32, Error> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u8(&mut self) -> Result<u64, Error> {
+        Ok((u64::from(self.u4()?) << 32) | u64::from(self.u4()?))
+    }
+
+    fn skip_attributes(&mut self) -> Result<(), Error> {
+        for _ in 0..self.u2()? {
+            let _name = self.u2()?;
+            let length = self.u4()? as usize;
+            self.bytes(length)?;
+        }
+        Ok(())
+    }
+}
+
+//
+
+enum Entry {
+    /// the slot after a `Long` or `Double`, and slot 0
+    Unusable,
+    /// `None` when the modified UTF-8 holds an unpaired surrogate, which a rust `String` can not
+    Utf8(Option<String>),
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    Class(u16),
+    String(u16),
+    /// the kinds we have no use for
+    Other,
+}
+
+struct ConstantPool {
+    entries: Vec<Entry>,
+}
+
+impl ConstantPool {
+    fn parse(reader: &mut Reader) -> Result<ConstantPool, Error> {
+        let count = reader.u2()? as usize;
+        let mut entries = vec![Entry::Unusable];
+        while entries.len() < count {
+            let tag = reader.u1()?;
+            let entry = match tag {
+                1 => {
+                    let length = reader.u2()? as usize;
+                    Entry::Utf8(decode_modified_utf8(reader.bytes(length)?))
+                }
+                3 => Entry::Int(reader.u4()? as i32),
+                4 => Entry::Float(f32::from_bits(reader.u4()?)),
+                5 => Entry::Long(reader.u8()? as i64),
+                6 => Entry::Double(f64::from_bits(reader.u8()?)),
+                7 => Entry::Class(reader.u2()?),
+                8 => Entry::String(reader.u2()?),
+                // MethodType, Module, Package
+                16 | 19 | 20 => {
+                    reader.bytes(2)?;
+                    Entry::Other
+                }
+                // MethodHandle
+                15 => {
+                    reader.bytes(3)?;
+                    Entry::Other
+                }
+                // Fieldref, Methodref, InterfaceMethodref, NameAndType, Dynamic, InvokeDynamic
+                9 | 10 | 11 | 12 | 17 | 18 => {
+                    reader.bytes(4)?;
+                    Entry::Other
+                }
+                _ => {
+                    return Err(Error::Format(format!(
+                        "unknown constant pool tag {} at entry {}",
+                        tag,
+                        entries.len()
+                    )))
+                }
+            };
+            let wide = matches!(entry, Entry::Long(_) | Entry::Double(_));
+            entries.push(entry);
+            if wide {
+                entries.push(Entry::Unusable);
+            }
+        }
+        Ok(ConstantPool { entries })
+    }
+
+    fn entry(&self, index: u16) -> Result<&Entry, Error> {
+        self.entries
+            .get(index as usize)
+            .ok_or_else(|| Error::Format(format!("constant pool index {} out of range", index)))
+    }
+
+    fn utf8(&self, index: u16) -> Result<String, Error> {
+        match self.entry(index)? {
+            Entry::Utf8(Some(text)) => Ok(text.clone()),
+            Entry::Utf8(None) => Err(Error::Format(format!(
+                "constant pool entry {} is not valid UTF-16",
+                index
+            ))),
+            _ => Err(Error::Format(format!(
+                "constant pool entry {} is not a Utf8",
+                index
+            ))),
+        }
+    }
+
+    fn class_name(&self, index: u16) -> Result<String, Error> {
+        match self.entry(index)? {
+            Entry::Class(name) => self.utf8(*name),
+            _ => Err(Error::Format(format!(
+                "constant pool entry {} is not a Class",
+                index
+            ))),
+        }
+    }
+
+    /// a string constant that rust can not represent is treated as no constant at all
+    fn constant(&self, index: u16) -> Result<Option<Constant>, Error> {
+        Ok(match self.entry(index)? {
+            Entry::Int(v) => Some(Constant::Int(*v)),
+            Entry::Long(v) => Some(Constant::Long(*v)),
+            Entry::Float(v) => Some(Constant::Float(*v)),
+            Entry::Double(v) => Some(Constant::Double(*v)),
+            Entry::String(text) => match self.entry(*text)? {
+                Entry::Utf8(text) => text.clone().map(Constant::String),
+                _ => None,
+            },
+            _ => {
+                return Err(Error::Format(format!(
+                    "constant pool entry {} is not a constant value",
+                    index
+                )))
+            }
+        })
+    }
+}
+
+/// The class file's variant of UTF-8: NUL is two bytes, and characters outside the basic multilingual plane
+/// are a surrogate pair of three bytes each.  We decode to UTF-16 and let rust check the pairs.
+fn decode_modified_utf8(bytes: &[u8]) -> Option<String> {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let continuation = |k: usize| -> Option<u16> {
+            match bytes.get(i + k) {
+                Some(c) if c & 0xC0 == 0x80 => Some(u16::from(c & 0x3F)),
+                _ => None,
+            }
+        };
+        if b & 0x80 == 0 {
+            units.push(u16::from(b));
+            i += 1;
+        } else if b & 0xE0 == 0xC0 {
+            units.push((u16::from(b & 0x1F) << 6) | continuation(1)?);
+            i += 2;
+        } else if b & 0xF0 == 0xE0 {
+            units.push((u16::from(b & 0x0F) << 12) | (continuation(1)? << 6) | continuation(2)?);
+            i += 3;
+        } else {
+            return None;
+        }
+    }
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{access, decode_modified_utf8, ClassFile, Constant};
+
+    const GADGET: &[u8] =
+        include_bytes!("../tests/fixtures/com/purplefrog/bindgen_fixture/Gadget.class");
+
+    #[test]
+    fn modified_utf8() {
+        assert_eq!(Some(String::from("plain")), decode_modified_utf8(b"plain"));
+        assert_eq!(
+            Some(String::from("\0")),
+            decode_modified_utf8(&[0xC0, 0x80])
+        );
+        assert_eq!(Some(String::from("é")), decode_modified_utf8(&[0xC3, 0xA9]));
+        // U+1D11E as a surrogate pair
+        assert_eq!(
+            Some(String::from("\u{1d11e}")),
+            decode_modified_utf8(&[0xED, 0xA0, 0xB4, 0xED, 0xB4, 0x9E])
+        );
+        // a lone surrogate
+        assert_eq!(None, decode_modified_utf8(&[0xED, 0xA0, 0xB4]));
+        assert_eq!(None, decode_modified_utf8(&[0xC3]));
+    }
+
+    #[test]
+    fn header() {
+        let class = ClassFile::parse(GADGET).unwrap();
+        assert_eq!("com/purplefrog/bindgen_fixture/Gadget", class.name);
+        assert_eq!("com.purplefrog.bindgen_fixture.Gadget", class.dotted_name());
+        assert_eq!(Some("java/lang/Object"), class.super_name.as_deref());
+        assert!(class.interfaces.is_empty());
+        assert_ne!(0, class.access_flags & access::PUBLIC);
+
+        assert!(ClassFile::parse(b"not a class").is_err());
+        assert!(ClassFile::parse(&GADGET[..100]).is_err());
+    }
+
+    #[test]
+    fn constants() {
+        let class = ClassFile::parse(GADGET).unwrap();
+        let constant = |name: &str| {
+            class
+                .fields
+                .iter()
+                .find(|field| field.name == name)
+                .unwrap()
+                .constant_value
+                .clone()
+        };
+        assert_eq!(Some(Constant::Int(10)), constant("MAX_COUNT"));
+        assert_eq!(Some(Constant::Long(1 << 40)), constant("BIG"));
+        assert_eq!(Some(Constant::Int(-3)), constant("SMALL"));
+        assert_eq!(Some(Constant::Double(0.5)), constant("RATIO"));
+        assert_eq!(Some(Constant::Int(1)), constant("ENABLED"));
+        assert_eq!(Some(Constant::Int('g' as i32)), constant("LETTER"));
+        assert_eq!(
+            Some(Constant::String(String::from("hi \"there\"\né\0"))),
+            constant("GREETING")
+        );
+        match constant("NOT_A_NUMBER") {
+            Some(Constant::Float(v)) => assert!(v.is_nan()),
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(None, constant("count"));
+        assert_eq!(None, constant("instances"));
+    }
+
+    #[test]
+    fn members() {
+        let class = ClassFile::parse(GADGET).unwrap();
+        let describe: Vec<_> = class
+            .methods
+            .iter()
+            .filter(|method| method.name == "describe")
+            .map(|method| method.descriptor.as_str())
+            .collect();
+        assert_eq!(
+            vec![
+                "(I)Ljava/lang/String;",
+                "(Ljava/lang/String;)Ljava/lang/String;"
+            ],
+            describe
+        );
+        let hidden = class.methods.iter().find(|m| m.name == "hidden").unwrap();
+        assert_ne!(0, hidden.access_flags & access::PRIVATE);
+        let sum = class.methods.iter().find(|m| m.name == "sum").unwrap();
+        assert_eq!("([I)I", sum.descriptor);
+        assert_ne!(0, sum.access_flags & access::STATIC);
+        assert_eq!(
+            2,
+            class.methods.iter().filter(|m| m.name == "<init>").count()
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Field and method descriptors, such as `[I` and `(ILjava/lang/String;)V`.
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JavaType {
+    Boolean,
+    Byte,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    /// only as a return type
+    Void,
+    /// slash-separated class name
+    Object(String),
+    Array(Box<JavaType>),
+}
+
+fn parse_one(descriptor: &str) -> Result<(JavaType, &str), Error> {
+    let bad = || Error::Format(format!("bad descriptor {:?}", descriptor));
+    let mut chars = descriptor.chars();
+    let rval = match chars.next().ok_or_else(bad)? {
+        'Z' => JavaType::Boolean,
+        'B' => JavaType::Byte,
+        'C' => JavaType::Char,
+        'S' => JavaType::Short,
+        'I' => JavaType::Int,
+        'J' => JavaType::Long,
+        'F' => JavaType::Float,
+        'D' => JavaType::Double,
+        'V' => JavaType::Void,
+        'L' => {
+            let end = descriptor.find(';').ok_or_else(bad)?;
+            return Ok((
+                JavaType::Object(String::from(&descriptor[1..end])),
+                &descriptor[end + 1..],
+            ));
+        }
+        '[' => {
+            let (element, rest) = parse_one(chars.as_str())?;
+            return Ok((JavaType::Array(Box::new(element)), rest));
+        }
+        _ => return Err(bad()),
+    };
+    Ok((rval, chars.as_str()))
+}
+
+pub fn parse_field_descriptor(descriptor: &str) -> Result<JavaType, Error> {
+    match parse_one(descriptor)? {
+        (rval, "") => Ok(rval),
+        _ => Err(Error::Format(format!("bad descriptor {:?}", descriptor))),
+    }
+}
+
+/// `(argument types, return type)`
+pub fn parse_method_descriptor(descriptor: &str) -> Result<(Vec<JavaType>, JavaType), Error> {
+    let bad = || Error::Format(format!("bad method descriptor {:?}", descriptor));
+    let mut rest = descriptor.strip_prefix('(').ok_or_else(bad)?;
+    let mut arguments = Vec::new();
+    while !rest.starts_with(')') {
+        let (argument, remainder) = parse_one(rest)?;
+        arguments.push(argument);
+        rest = remainder;
+    }
+    let return_type = parse_field_descriptor(&rest[1..])?;
+    Ok((arguments, return_type))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_field_descriptor, parse_method_descriptor, JavaType};
+
+    #[test]
+    fn fields() {
+        assert_eq!(JavaType::Int, parse_field_descriptor("I").unwrap());
+        assert_eq!(
+            JavaType::Object(String::from("java/lang/String")),
+            parse_field_descriptor("Ljava/lang/String;").unwrap()
+        );
+        assert_eq!(
+            JavaType::Array(Box::new(JavaType::Array(Box::new(JavaType::Double)))),
+            parse_field_descriptor("[[D").unwrap()
+        );
+        assert!(parse_field_descriptor("II").is_err());
+        assert!(parse_field_descriptor("Ljava/lang/String").is_err());
+        assert!(parse_field_descriptor("").is_err());
+    }
+
+    #[test]
+    fn methods() {
+        let (arguments, return_type) = parse_method_descriptor("(I[Ljava/lang/String;Z)V").unwrap();
+        assert_eq!(
+            vec![
+                JavaType::Int,
+                JavaType::Array(Box::new(JavaType::Object(String::from("java/lang/String")))),
+                JavaType::Boolean
+            ],
+            arguments
+        );
+        assert_eq!(JavaType::Void, return_type);
+        assert_eq!(
+            (vec![], JavaType::Long),
+            parse_method_descriptor("()J").unwrap()
+        );
+        assert!(parse_method_descriptor("I)V").is_err());
+        assert!(parse_method_descriptor("(I").is_err());
+    }
+}
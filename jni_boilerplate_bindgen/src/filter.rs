@@ -0,0 +1,132 @@
+//! Which classes and members get bindings.
+
+/// Patterns are dotted class names (`com.example.Widget`, `com.example.Widget$Inner`) in which `*`
+/// matches any run of characters, so `com.example.*` covers a package and its subpackages.
+/// A pattern with a `#` selects members instead: `com.example.Widget#secret`, or `*#finalize`.
+///
+/// A class is emitted if no class pattern was included or one of them matches, and no excluded class pattern matches.
+/// A member of an emitted class is left out if an excluded member pattern matches it,
+/// or if member patterns were included for its class and none of them matches it.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    pub fn new() -> Filter {
+        Filter::default()
+    }
+
+    pub fn include(mut self, pattern: &str) -> Filter {
+        self.include.push(String::from(pattern));
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Filter {
+        self.exclude.push(String::from(pattern));
+        self
+    }
+
+    /// `class_name` is dotted
+    pub fn allows_class(&self, class_name: &str) -> bool {
+        let mut includes = self.include.iter().filter(|p| !p.contains('#')).peekable();
+        let included = includes.peek().is_none() || includes.any(|p| glob_match(p, class_name));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|p| !p.contains('#') && glob_match(p, class_name))
+    }
+
+    /// `class_name` is dotted
+    pub fn allows_member(&self, class_name: &str, member: &str) -> bool {
+        let qualified = format!("{}#{}", class_name, member);
+        let member_patterns = |patterns: &[String]| -> Vec<(String, String)> {
+            patterns
+                .iter()
+                .filter_map(|p| {
+                    let (class, member) = p.split_once('#')?;
+                    Some((String::from(class), String::from(member)))
+                })
+                .collect()
+        };
+
+        let includes: Vec<_> = member_patterns(&self.include)
+            .into_iter()
+            .filter(|(class, _)| glob_match(class, class_name))
+            .collect();
+        let included = includes.is_empty()
+            || includes
+                .iter()
+                .any(|(class, member)| glob_match(&format!("{}#{}", class, member), &qualified));
+        included
+            && !member_patterns(&self.exclude)
+                .iter()
+                .any(|(class, member)| glob_match(&format!("{}#{}", class, member), &qualified))
+    }
+}
+
+/// `*` matches any run of characters, including none; everything else matches itself
+fn glob_match(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            text.starts_with(prefix)
+                && (prefix.len()..=text.len())
+                    .filter(|&i| text.is_char_boundary(i))
+                    .any(|i| glob_match(rest, &text[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{glob_match, Filter};
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("com.example.Widget", "com.example.Widget"));
+        assert!(!glob_match(
+            "com.example.Widget",
+            "com.example.Widget$Inner"
+        ));
+        assert!(glob_match("com.example.*", "com.example.Widget"));
+        assert!(glob_match("com.example.*", "com.example.sub.Widget"));
+        assert!(!glob_match("com.example.*", "com.other.Widget"));
+        assert!(glob_match("*$Inner", "com.example.Widget$Inner"));
+        assert!(glob_match("com.*.Widget*", "com.example.Widget$Inner"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("a*b", "ba"));
+    }
+
+    #[test]
+    fn classes() {
+        let everything = Filter::new();
+        assert!(everything.allows_class("com.example.Widget"));
+
+        let filter = Filter::new()
+            .include("com.example.*")
+            .exclude("com.example.internal.*")
+            .exclude("*#finalize");
+        assert!(filter.allows_class("com.example.Widget"));
+        assert!(!filter.allows_class("com.example.internal.Secret"));
+        assert!(!filter.allows_class("org.other.Thing"));
+    }
+
+    #[test]
+    fn members() {
+        let filter = Filter::new()
+            .exclude("*#finalize")
+            .exclude("com.example.Widget#secret*")
+            .include("com.example.Gadget#get*");
+        assert!(filter.allows_member("com.example.Widget", "count"));
+        assert!(!filter.allows_member("com.example.Widget", "finalize"));
+        assert!(!filter.allows_member("com.example.Widget", "secretCount"));
+        assert!(filter.allows_member("com.example.Other", "secretCount"));
+        // member includes only restrict their own class
+        assert!(filter.allows_member("com.example.Gadget", "getCount"));
+        assert!(!filter.allows_member("com.example.Gadget", "setCount"));
+        assert!(filter.allows_class("com.example.Widget"));
+    }
+}
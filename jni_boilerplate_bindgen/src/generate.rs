@@ -0,0 +1,632 @@
+//! Turns parsed class files into rust source: one module per java package, and for each class a
+//! `#[derive(JavaWrapper)]` struct with a `#[jni_class]` impl declaring its constants, constructors,
+//! methods and fields.
+
+use crate::class_file::{access, ClassFile, Constant, Field, Method};
+use crate::descriptor::{parse_field_descriptor, parse_method_descriptor, JavaType};
+use crate::{Error, Filter};
+use jni_boilerplate_helper::overload_names::{
+    overload_names, parameter_descriptors, OverloadNaming,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+/// How methods get their rust names.
+/// Overloads are named by `overloads` (by default from their parameter types: `describe_int`,
+/// `describe_string`), except those given a name in `renames`.
+#[derive(Debug, Clone)]
+pub struct Naming {
+    pub overloads: OverloadNaming,
+    /// keyed by the dotted class name, the method name (`new` for constructors) and the parameter
+    /// descriptors: `com.example.Widget#describe(Ljava/lang/String;)`
+    pub renames: HashMap<String, String>,
+}
+
+impl Default for Naming {
+    fn default() -> Naming {
+        Naming {
+            overloads: OverloadNaming::ParameterTypes,
+            renames: HashMap::new(),
+        }
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn is_keyword(name: &str) -> bool {
+    RUST_KEYWORDS.contains(&name)
+}
+
+/// a java identifier as a rust one: `$` is not allowed, and keywords get a trailing `_`
+fn rust_identifier(name: &str) -> String {
+    let rval = name.replace('$', "_");
+    if is_keyword(&rval) {
+        rval + "_"
+    } else {
+        rval
+    }
+}
+
+/// where the wrapper for a class lives
+struct Binding<'c> {
+    class: &'c ClassFile,
+    /// the rust module path, one module per java package component
+    package: Vec<String>,
+    /// `Outer$Inner` becomes `Outer_Inner`
+    struct_name: String,
+}
+
+impl<'c> Binding<'c> {
+    fn new(class: &'c ClassFile) -> Binding<'c> {
+        let mut components: Vec<&str> = class.name.split('/').collect();
+        let simple_name = components.pop().unwrap_or_default();
+        Binding {
+            class,
+            package: components.into_iter().map(rust_identifier).collect(),
+            struct_name: rust_identifier(simple_name),
+        }
+    }
+}
+
+/// Classes that can not be named from java source (anonymous and local classes) are left out,
+/// as are classes that are not public.
+fn wanted(class: &ClassFile, filter: &Filter) -> bool {
+    let anonymous_or_local = class
+        .name
+        .rsplit('$')
+        .next()
+        .is_some_and(|last| last.starts_with(|c: char| c.is_ascii_digit()));
+    class.access_flags & access::PUBLIC != 0
+        && class.access_flags & access::SYNTHETIC == 0
+        && !anonymous_or_local
+        && filter.allows_class(&class.dotted_name())
+}
+
+/// Fails if two members of a class would get the same rust name.
+pub fn generate(classes: &[ClassFile], filter: &Filter, naming: &Naming) -> Result<String, Error> {
+    let mut bindings: Vec<Binding> = classes
+        .iter()
+        .filter(|class| wanted(class, filter))
+        .map(Binding::new)
+        .collect();
+    bindings.sort_by(|a, b| a.class.name.cmp(&b.class.name));
+    bindings.dedup_by(|a, b| a.class.name == b.class.name);
+
+    let known: HashMap<&str, &Binding> = bindings
+        .iter()
+        .map(|binding| (binding.class.name.as_str(), binding))
+        .collect();
+
+    let mut packages: BTreeMap<&[String], Vec<&Binding>> = BTreeMap::new();
+    for binding in &bindings {
+        packages
+            .entry(binding.package.as_slice())
+            .or_default()
+            .push(binding);
+    }
+
+    let mut out = Source::default();
+    out.line("// generated by jni_boilerplate_bindgen from compiled java classes; do not edit");
+    let mut open: &[String] = &[];
+    for (package, classes) in packages {
+        let common = open
+            .iter()
+            .zip(package.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        for _ in common..open.len() {
+            out.close();
+        }
+        for module in &package[common..] {
+            out.blank();
+            out.open(&format!("pub mod {} {{", module));
+        }
+        open = package;
+
+        for binding in classes {
+            out.blank();
+            class_tokens(&mut out, binding, &known, filter, naming)?;
+        }
+    }
+    for _ in 0..open.len() {
+        out.close();
+    }
+    Ok(out.text)
+}
+
+/// the wrapper struct and its `#[jni_class]` impl
+fn class_tokens(
+    out: &mut Source,
+    binding: &Binding,
+    known: &HashMap<&str, &Binding>,
+    filter: &Filter,
+    naming: &Naming,
+) -> Result<(), Error> {
+    let class = binding.class;
+    let dotted = class.dotted_name();
+    let name = &binding.struct_name;
+
+    out.line(&format!("/// `{}`", dotted));
+    out.line("#[derive(jni_boilerplate::JavaWrapper)]");
+    out.line(&format!("#[java_class({:?})]", dotted));
+    if name.contains('_') {
+        out.line("#[allow(non_camel_case_types)]");
+    }
+    out.line("#[repr(C)]");
+    out.open(&format!("pub struct {}<'a: 'b, 'b> {{", name));
+    out.line("#[allow(dead_code)]");
+    out.line("java_this: jni::objects::AutoLocal<'a, 'b>,");
+    out.line("#[allow(dead_code)]");
+    out.line("jni_env: &'b jni::JNIEnv<'a>,");
+    out.close();
+
+    let types = TypeMapper {
+        known,
+        package: &binding.package,
+    };
+    hierarchy_tokens(out, binding, &types);
+    let mut members = Members {
+        class: dotted.clone(),
+        lines: Vec::new(),
+        used: HashSet::new(),
+    };
+
+    for field in &class.fields {
+        if field.access_flags & access::PUBLIC == 0
+            || field.access_flags & access::SYNTHETIC != 0
+            || !filter.allows_member(&dotted, &field.name)
+        {
+            continue;
+        }
+        field_tokens(&mut members, field, &types)?;
+    }
+
+    let instantiable = class.access_flags & (access::ABSTRACT | access::INTERFACE) == 0;
+    let methods: Vec<&Method> = class
+        .methods
+        .iter()
+        .filter(|method| {
+            method.access_flags & access::PUBLIC != 0
+                && method.access_flags & (access::SYNTHETIC | access::BRIDGE) == 0
+                && method.name != "<clinit>"
+                && (method.name != "<init>" || instantiable)
+                && filter.allows_member(&dotted, &method.name)
+        })
+        .collect();
+    for (method, rust_name) in methods.iter().zip(method_names(&dotted, &methods, naming)?) {
+        method_tokens(&mut members, method, rust_name, &types)?;
+    }
+
+    if members.lines.is_empty() {
+        return Ok(());
+    }
+    out.blank();
+    out.line("#[jni_boilerplate::jni_class]");
+    out.open(&format!("impl<'a: 'b, 'b> {}<'a, 'b> {{", name));
+    for line in &members.lines {
+        out.line(line);
+    }
+    out.close();
+    Ok(())
+}
+
+/// `jni_extends!` for the superclasses and interfaces that have bindings too.
+/// The chain of superclasses stops at the first one without a binding.
+fn hierarchy_tokens(out: &mut Source, binding: &Binding, types: &TypeMapper) {
+    let mut superclasses: Vec<String> = Vec::new();
+    let mut classes = vec![binding.class];
+    let mut next = binding.class.super_name.as_deref();
+    while let Some(superclass) = next.and_then(|name| types.known.get(name)) {
+        superclasses.extend(types.wrapper_path(&superclass.class.name));
+        classes.push(superclass.class);
+        next = superclass.class.super_name.as_deref();
+    }
+
+    // and the interfaces those interfaces extend
+    let mut interfaces: Vec<String> = Vec::new();
+    let mut pending: Vec<&str> = classes
+        .iter()
+        .flat_map(|class| class.interfaces.iter().map(String::as_str))
+        .collect();
+    let mut seen = HashSet::new();
+    while !pending.is_empty() {
+        let interface = pending.remove(0);
+        if !seen.insert(interface) {
+            continue;
+        }
+        if let Some(known) = types.known.get(interface) {
+            interfaces.extend(types.wrapper_path(interface));
+            pending.extend(known.class.interfaces.iter().map(String::as_str));
+        }
+    }
+
+    let mut relations = String::new();
+    if !superclasses.is_empty() {
+        relations = format!(" extends {}", superclasses.join(", "));
+    }
+    if !interfaces.is_empty() {
+        relations = format!("{} implements {}", relations, interfaces.join(", "));
+    }
+    if !relations.is_empty() {
+        out.blank();
+        out.line(&format!(
+            "jni_boilerplate_helper::jni_extends! {{ {}{} }}",
+            binding.struct_name, relations
+        ));
+    }
+}
+
+/// `new` for constructors
+fn java_name(method: &Method) -> &str {
+    if method.name == "<init>" {
+        "new"
+    } else {
+        &method.name
+    }
+}
+
+/// The rust name of each method.  Every public overload takes part in the naming, even the ones
+/// that get skipped, so that adding a binding for a parameter type does not rename the others.
+fn method_names(dotted: &str, methods: &[&Method], naming: &Naming) -> Result<Vec<String>, Error> {
+    let mut rval = vec![String::new(); methods.len()];
+    let mut overloads: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, method) in methods.iter().enumerate() {
+        let parameters = parameter_descriptors(&method.descriptor).concat();
+        let key = format!("{}#{}({})", dotted, java_name(method), parameters);
+        match naming.renames.get(&key) {
+            Some(rust_name) => rval[i] = rust_name.clone(),
+            None => overloads.entry(java_name(method)).or_default().push(i),
+        }
+    }
+
+    for (java_name, indices) in overloads {
+        let parameters: Vec<Vec<&str>> = indices
+            .iter()
+            .map(|&i| parameter_descriptors(&methods[i].descriptor))
+            .collect();
+        let names = overload_names(java_name, &parameters, naming.overloads)
+            .map_err(|message| Error::Naming(format!("{}: {}", dotted, message)))?;
+        for (i, name) in indices.into_iter().zip(names) {
+            rval[i] = rust_identifier(&name);
+        }
+    }
+    Ok(rval)
+}
+
+/// the lines of a `#[jni_class]` impl, and the rust names they have used up
+struct Members {
+    /// dotted, for messages
+    class: String,
+    lines: Vec<String>,
+    used: HashSet<String>,
+}
+
+impl Members {
+    /// two members with the same rust name would not compile
+    fn claim(&mut self, name: String) -> Result<String, Error> {
+        if self.used.insert(name.clone()) {
+            Ok(name)
+        } else {
+            Err(Error::Naming(format!(
+                "{}: more than one member would be named {}",
+                self.class, name
+            )))
+        }
+    }
+}
+
+fn field_tokens(members: &mut Members, field: &Field, types: &TypeMapper) -> Result<(), Error> {
+    let ty = match parse_field_descriptor(&field.descriptor) {
+        Ok(ty) => ty,
+        Err(_) => {
+            members.lines.push(format!(
+                "// skipped {}: bad descriptor {}",
+                field.name, field.descriptor
+            ));
+            return Ok(());
+        }
+    };
+
+    if field.access_flags & access::STATIC != 0 {
+        match (
+            &field.constant_value,
+            field.access_flags & access::FINAL != 0,
+        ) {
+            (Some(value), true) => match constant_tokens(&ty, value) {
+                Some((rust_type, literal)) => {
+                    let name = members.claim(rust_identifier(&field.name))?;
+                    members
+                        .lines
+                        .push(format!("pub const {}: {} = {};", name, rust_type, literal));
+                }
+                None => members.lines.push(format!(
+                    "// skipped {}: no rust constant for this value",
+                    field.name
+                )),
+            },
+            _ => members.lines.push(format!(
+                "// skipped {}: static fields other than constants are not supported",
+                field.name
+            )),
+        }
+        return Ok(());
+    }
+
+    if is_keyword(&field.name) || field.name.contains('$') {
+        members.lines.push(format!(
+            "// skipped {}: the name does not work as a rust identifier",
+            field.name
+        ));
+        return Ok(());
+    }
+    // the getter returns, and the setter takes, the same type, so it has to be an owned one
+    match types.owned(&ty) {
+        Some(rust_type) if !matches!(ty, JavaType::Array(_)) => {
+            members.claim(format!("get_{}", field.name))?;
+            members.claim(format!("set_{}", field.name))?;
+            members.lines.push(format!(
+                "jni_boilerplate::jni_field! {{ {}: {} }}",
+                field.name, rust_type
+            ));
+        }
+        _ => members
+            .lines
+            .push(format!("// skipped {}: {}", field.name, types.why_not(&ty))),
+    }
+    Ok(())
+}
+
+fn method_tokens(
+    members: &mut Members,
+    method: &Method,
+    rust_name: String,
+    types: &TypeMapper,
+) -> Result<(), Error> {
+    let constructor = method.name == "<init>";
+    let is_static = method.access_flags & access::STATIC != 0;
+    let description = format!("{}{}", java_name(method), method.descriptor);
+
+    let (arguments, return_type) = match parse_method_descriptor(&method.descriptor) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            members
+                .lines
+                .push(format!("// skipped {}: bad descriptor", description));
+            return Ok(());
+        }
+    };
+
+    let mut parameters: Vec<String> = Vec::new();
+    if !is_static && !constructor {
+        parameters.push(String::from("&self"));
+    }
+    for (i, argument) in arguments.iter().enumerate() {
+        match types.argument(argument) {
+            Some(rust_type) => parameters.push(format!("arg{}: {}", i, rust_type)),
+            None => {
+                members.lines.push(format!(
+                    "// skipped {}: {}",
+                    description,
+                    types.why_not(argument)
+                ));
+                return Ok(());
+            }
+        }
+    }
+    let returns = if constructor {
+        String::from(" -> Self")
+    } else {
+        match return_type {
+            JavaType::Void => String::new(),
+            ref ty => match types.owned(ty) {
+                Some(rust_type) => format!(" -> {}", rust_type),
+                None => {
+                    members.lines.push(format!(
+                        "// skipped {}: {}",
+                        description,
+                        types.why_not(ty)
+                    ));
+                    return Ok(());
+                }
+            },
+        }
+    };
+
+    let rust_name = members.claim(rust_name)?;
+    if !members.lines.is_empty() {
+        members.lines.push(String::new());
+    }
+    if constructor {
+        members.lines.push(String::from("#[constructor]"));
+    } else {
+        if rust_name != method.name {
+            members
+                .lines
+                .push(format!("#[java_name = {:?}]", method.name));
+        }
+        if is_static {
+            members.lines.push(String::from("#[static_method]"));
+        }
+    }
+    members.lines.push(format!(
+        "pub fn {}({}){};",
+        rust_name,
+        parameters.join(", "),
+        returns
+    ));
+    Ok(())
+}
+
+/// `(rust type, rust literal)` for a constant
+fn constant_tokens(ty: &JavaType, value: &Constant) -> Option<(&'static str, String)> {
+    Some(match (ty, value) {
+        (JavaType::Boolean, Constant::Int(v)) => ("bool", (*v != 0).to_string()),
+        (JavaType::Byte, Constant::Int(v)) => ("i8", (*v as i8).to_string()),
+        (JavaType::Short, Constant::Int(v)) => ("i16", (*v as i16).to_string()),
+        (JavaType::Char, Constant::Int(v)) => {
+            ("char", format!("{:?}", std::char::from_u32(*v as u32)?))
+        }
+        (JavaType::Int, Constant::Int(v)) => ("i32", v.to_string()),
+        (JavaType::Long, Constant::Long(v)) => ("i64", v.to_string()),
+        (JavaType::Float, Constant::Float(v)) => {
+            ("f32", float_literal("f32", *v as f64, format!("{:?}", v)))
+        }
+        (JavaType::Double, Constant::Double(v)) => {
+            ("f64", float_literal("f64", *v, format!("{:?}", v)))
+        }
+        (JavaType::Object(class), Constant::String(v)) if class == "java/lang/String" => {
+            ("&'static str", format!("{:?}", v))
+        }
+        _ => return None,
+    })
+}
+
+fn float_literal(ty: &str, value: f64, literal: String) -> String {
+    if value.is_nan() {
+        format!("{}::NAN", ty)
+    } else if value == f64::INFINITY {
+        format!("{}::INFINITY", ty)
+    } else if value == f64::NEG_INFINITY {
+        format!("{}::NEG_INFINITY", ty)
+    } else {
+        literal
+    }
+}
+
+/// the rust types the conversion traits support for each java type
+struct TypeMapper<'m, 'c> {
+    known: &'m HashMap<&'m str, &'m Binding<'c>>,
+    /// the module the generated code is in
+    package: &'m [String],
+}
+
+impl<'m, 'c> TypeMapper<'m, 'c> {
+    /// the path to another class's wrapper, relative to this module
+    fn wrapper(&self, class: &str) -> Option<String> {
+        Some(self.wrapper_path(class)? + "<'a, 'b>")
+    }
+
+    /// the same, without the lifetimes
+    fn wrapper_path(&self, class: &str) -> Option<String> {
+        let binding = self.known.get(class)?;
+        let common = self
+            .package
+            .iter()
+            .zip(binding.package.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut rval = String::new();
+        for _ in common..self.package.len() {
+            rval.push_str("super::");
+        }
+        for module in &binding.package[common..] {
+            rval.push_str(module);
+            rval.push_str("::");
+        }
+        rval.push_str(&binding.struct_name);
+        Some(rval)
+    }
+
+    fn primitive(ty: &JavaType) -> Option<&'static str> {
+        Some(match ty {
+            JavaType::Boolean => "bool",
+            JavaType::Byte => "i8",
+            JavaType::Char => "char",
+            JavaType::Short => "i16",
+            JavaType::Int => "i32",
+            JavaType::Long => "i64",
+            JavaType::Float => "f32",
+            JavaType::Double => "f64",
+            _ => return None,
+        })
+    }
+
+    /// for arguments: borrowed strings, slices and wrappers
+    fn argument(&self, ty: &JavaType) -> Option<String> {
+        if let Some(primitive) = Self::primitive(ty) {
+            return Some(String::from(primitive));
+        }
+        match ty {
+            JavaType::Object(class) if class == "java/lang/String" => Some(String::from("&str")),
+            JavaType::Object(class) => Some(format!("&{}", self.wrapper(class)?)),
+            JavaType::Array(element) => match &**element {
+                JavaType::Object(class) if class == "java/lang/String" => {
+                    Some(String::from("&[&str]"))
+                }
+                element => Some(format!("&[{}]", Self::primitive(element)?)),
+            },
+            _ => None,
+        }
+    }
+
+    /// for return values and fields
+    fn owned(&self, ty: &JavaType) -> Option<String> {
+        if let Some(primitive) = Self::primitive(ty) {
+            return Some(String::from(primitive));
+        }
+        match ty {
+            JavaType::Object(class) if class == "java/lang/String" => Some(String::from("String")),
+            JavaType::Object(class) => self.wrapper(class),
+            JavaType::Array(element) => match &**element {
+                JavaType::Object(class) if class == "java/lang/String" => {
+                    Some(String::from("Vec<String>"))
+                }
+                element => Some(format!("Vec<{}>", Self::primitive(element)?)),
+            },
+            _ => None,
+        }
+    }
+
+    fn why_not(&self, ty: &JavaType) -> String {
+        match ty {
+            JavaType::Object(class) => format!("no binding for {}", class.replace('/', ".")),
+            JavaType::Array(element) => match &**element {
+                JavaType::Object(_) | JavaType::Array(_) => {
+                    String::from("arrays of objects other than String are not supported")
+                }
+                _ => self.why_not(element),
+            },
+            _ => String::from("unsupported type"),
+        }
+    }
+}
+
+/// rust source with four spaces of indentation per level
+#[derive(Default)]
+struct Source {
+    text: String,
+    depth: usize,
+}
+
+impl Source {
+    fn line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.text.push('\n');
+        } else {
+            let _ = writeln!(self.text, "{:width$}{}", "", line, width = self.depth * 4);
+        }
+    }
+
+    /// a blank line, except at the start of a block
+    fn blank(&mut self) {
+        if !self.text.ends_with("{\n") {
+            self.text.push('\n');
+        }
+    }
+
+    fn open(&mut self, line: &str) {
+        self.line(line);
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+}
@@ -0,0 +1,143 @@
+//! Reads the `.class` entries of a jar (which is a zip file) without extracting it.
+//! Entries may be stored or deflated; zip64 and encrypted archives are not supported.
+
+use crate::Error;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const CENTRAL_DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, Error> {
+    match bytes.get(offset..offset + 2) {
+        Some(b) => Ok(u16::from_le_bytes([b[0], b[1]])),
+        None => Err(Error::Format(String::from("truncated jar"))),
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    match bytes.get(offset..offset + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::Format(String::from("truncated jar"))),
+    }
+}
+
+/// `(entry name, uncompressed contents)` for every entry whose name ends in `.class`,
+/// except `module-info.class` and the `META-INF/versions/` copies of multi-release jars.
+pub fn class_entries(jar: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    // the end of central directory record is 22 bytes plus a comment of up to 64k
+    let search_from = jar.len().saturating_sub(22 + 0xFFFF);
+    let end = (search_from..jar.len().saturating_sub(21))
+        .rev()
+        .find(|&offset| u32_at(jar, offset).ok() == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| Error::Format(String::from("not a jar (no end of central directory)")))?;
+
+    let entry_count = u16_at(jar, end + 10)?;
+    let mut offset = u32_at(jar, end + 16)? as usize;
+    if offset == 0xFFFF_FFFF || entry_count == 0xFFFF {
+        return Err(Error::Format(String::from("zip64 jars are not supported")));
+    }
+
+    let mut rval = Vec::new();
+    for _ in 0..entry_count {
+        if u32_at(jar, offset)? != CENTRAL_DIRECTORY_ENTRY {
+            return Err(Error::Format(String::from("corrupt central directory")));
+        }
+        let flags = u16_at(jar, offset + 8)?;
+        let method = u16_at(jar, offset + 10)?;
+        let compressed_size = u32_at(jar, offset + 20)? as usize;
+        let uncompressed_size = u32_at(jar, offset + 24)? as usize;
+        let name_length = u16_at(jar, offset + 28)? as usize;
+        let extra_length = u16_at(jar, offset + 30)? as usize;
+        let comment_length = u16_at(jar, offset + 32)? as usize;
+        let local_header = u32_at(jar, offset + 42)? as usize;
+        let name = jar
+            .get(offset + 46..offset + 46 + name_length)
+            .ok_or_else(|| Error::Format(String::from("truncated jar")))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        offset += 46 + name_length + extra_length + comment_length;
+
+        if !name.ends_with(".class")
+            || name.ends_with("module-info.class")
+            || name.starts_with("META-INF/")
+        {
+            continue;
+        }
+        if flags & 1 != 0 {
+            return Err(Error::Format(format!("{} is encrypted", name)));
+        }
+
+        if u32_at(jar, local_header)? != LOCAL_HEADER {
+            return Err(Error::Format(format!("corrupt local header for {}", name)));
+        }
+        // the local header's own lengths can differ from the central directory's
+        let data = local_header
+            + 30
+            + u16_at(jar, local_header + 26)? as usize
+            + u16_at(jar, local_header + 28)? as usize;
+        let compressed = jar
+            .get(data..data + compressed_size)
+            .ok_or_else(|| Error::Format(format!("truncated contents of {}", name)))?;
+
+        let contents = match method {
+            STORED => compressed.to_vec(),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec(compressed)
+                .map_err(|e| Error::Format(format!("unable to inflate {}: {:?}", name, e)))?,
+            _ => {
+                return Err(Error::Format(format!(
+                    "{} uses unsupported compression method {}",
+                    name, method
+                )))
+            }
+        };
+        if contents.len() != uncompressed_size {
+            return Err(Error::Format(format!("{} has the wrong size", name)));
+        }
+        rval.push((name, contents));
+    }
+    Ok(rval)
+}
+
+#[cfg(test)]
+mod test {
+    use super::class_entries;
+
+    const GADGET: &[u8] =
+        include_bytes!("../tests/fixtures/com/purplefrog/bindgen_fixture/Gadget.class");
+
+    fn names(entries: &[(String, Vec<u8>)]) -> Vec<&str> {
+        entries.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn deflated() {
+        let entries = class_entries(include_bytes!("../tests/fixtures/gadget.jar")).unwrap();
+        assert_eq!(
+            vec![
+                "com/purplefrog/bindgen_fixture/Gadget$BigPart.class",
+                "com/purplefrog/bindgen_fixture/Gadget$Measured.class",
+                "com/purplefrog/bindgen_fixture/Gadget$Part.class",
+                "com/purplefrog/bindgen_fixture/Gadget.class"
+            ],
+            names(&entries)
+        );
+        assert_eq!(GADGET, &entries[3].1[..]);
+    }
+
+    #[test]
+    fn stored() {
+        let entries = class_entries(include_bytes!("../tests/fixtures/gadget_stored.jar")).unwrap();
+        assert_eq!(4, entries.len());
+        assert_eq!(GADGET, &entries[3].1[..]);
+    }
+
+    #[test]
+    fn not_a_jar() {
+        assert!(class_entries(GADGET).is_err());
+        assert!(class_entries(b"").is_err());
+        let jar = include_bytes!("../tests/fixtures/gadget.jar");
+        assert!(class_entries(&jar[..jar.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Generates rust bindings for java classes from their compiled `.class` files, so nobody has to
+//! transcribe `jni_instance_method!` lines from javadoc.
+//!
+//! Each public class becomes a `#[derive(JavaWrapper)]` struct in a module named after its package,
+//! with a `#[jni_class]` impl declaring its public constructors, methods, instance fields
+//! and `static final` constants.  Overloaded methods get rust names derived from their parameter
+//! types (`describe_int`, `describe_string`), or from their parameter counts, or names given
+//! explicitly with `Bindgen::rename`; see `Naming`.  A class whose superclass or interfaces are generated too
+//! gets a `jni_extends!` for them, so their bindings are reachable from it.  Members whose types have no rust counterpart
+//! (a class that is not among the generated ones, an array of objects) are listed in a comment instead.
+//!
+//! The generated code refers to the `jni`, `jni_boilerplate` and `jni_boilerplate_helper` crates,
+//! so those must be dependencies of the crate that includes it.
+//!
+//! From a `build.rs`:
+//! ```no_run
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! jni_boilerplate_bindgen::Bindgen::new()
+//!     .input("java/widgets.jar")
+//!     .include("com.purplefrog.widgets.*")
+//!     .exclude("*#finalize")
+//!     .write_to(std::path::Path::new(&out_dir).join("widgets.rs"))
+//!     .unwrap();
+//! println!("cargo:rerun-if-changed=java/widgets.jar");
+//! ```
+//! and then `include!(concat!(env!("OUT_DIR"), "/widgets.rs"));` in the crate.
+//!
+//! The `jni_boilerplate_bindgen` binary does the same from the command line.
+
+pub mod class_file;
+pub mod descriptor;
+pub mod filter;
+pub mod generate;
+pub mod jar;
+
+pub use class_file::ClassFile;
+pub use filter::Filter;
+pub use generate::Naming;
+pub use jni_boilerplate_helper::overload_names::OverloadNaming;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// a malformed class file or jar
+    Format(String),
+    /// two members of a class would get the same rust name
+    Naming(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Format(message) | Error::Naming(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            Error::Format(_) | Error::Naming(_) => None,
+        }
+    }
+}
+
+/// Collects inputs and a `Filter`, then generates the bindings for all of them at once,
+/// so that classes can refer to each other's wrappers.
+#[derive(Debug, Clone, Default)]
+pub struct Bindgen {
+    inputs: Vec<PathBuf>,
+    filter: Filter,
+    naming: Naming,
+}
+
+impl Bindgen {
+    pub fn new() -> Bindgen {
+        Bindgen::default()
+    }
+
+    /// a `.class` file, a `.jar` (or `.zip`), or a directory searched for `.class` files
+    pub fn input<P: Into<PathBuf>>(mut self, path: P) -> Bindgen {
+        self.inputs.push(path.into());
+        self
+    }
+
+    /// see `Filter` for the pattern syntax
+    pub fn include(mut self, pattern: &str) -> Bindgen {
+        self.filter = self.filter.include(pattern);
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Bindgen {
+        self.filter = self.filter.exclude(pattern);
+        self
+    }
+
+    pub fn filter(mut self, filter: Filter) -> Bindgen {
+        self.filter = filter;
+        self
+    }
+
+    /// how overloaded methods are told apart; by default from their parameter types
+    pub fn overloads(mut self, overloads: OverloadNaming) -> Bindgen {
+        self.naming.overloads = overloads;
+        self
+    }
+
+    /// Gives one method an explicit rust name, whatever the overload naming.
+    /// `method` is the dotted class name, the method name (`new` for constructors)
+    /// and its parameter descriptors: `com.example.Widget#describe(Ljava/lang/String;)`.
+    pub fn rename(mut self, method: &str, rust_name: &str) -> Bindgen {
+        self.naming
+            .renames
+            .insert(String::from(method), String::from(rust_name));
+        self
+    }
+
+    /// the rust source for every input
+    pub fn generate(&self) -> Result<String, Error> {
+        let mut classes = Vec::new();
+        for input in &self.inputs {
+            classes.extend(load_classes(input)?);
+        }
+        generate::generate(&classes, &self.filter, &self.naming)
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let source = self.generate()?;
+        fs::write(path, source).map_err(|e| Error::Io(path.to_path_buf(), e))
+    }
+}
+
+/// The classes in a `.class` file, a jar, or a directory tree of `.class` files.
+pub fn load_classes(path: &Path) -> Result<Vec<ClassFile>, Error> {
+    let io_error = |e| Error::Io(path.to_path_buf(), e);
+    let in_file = |e: Error| match e {
+        Error::Format(message) => Error::Format(format!("{}: {}", path.display(), message)),
+        e => e,
+    };
+
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()
+            .map_err(io_error)?;
+        entries.sort();
+        let mut rval = Vec::new();
+        for entry in entries {
+            let is_class = entry.extension().is_some_and(|e| e == "class");
+            if entry.is_dir() || is_class {
+                rval.extend(load_classes(&entry)?);
+            }
+        }
+        return Ok(rval);
+    }
+
+    let bytes = fs::read(path).map_err(io_error)?;
+    let is_archive = path.extension().is_some_and(|e| e == "jar" || e == "zip");
+    if is_archive {
+        jar::class_entries(&bytes)
+            .map_err(in_file)?
+            .iter()
+            .map(|(name, contents)| {
+                ClassFile::parse(contents)
+                    .map_err(|e| Error::Format(format!("{}!{}: {}", path.display(), name, e)))
+            })
+            .collect()
+    } else {
+        Ok(vec![ClassFile::parse(&bytes).map_err(in_file)?])
+    }
+}
@@ -0,0 +1,73 @@
+use jni_boilerplate_bindgen::Bindgen;
+use std::process;
+
+const USAGE: &str = "usage: jni_boilerplate_bindgen [--include PATTERN]... [--exclude PATTERN]... [--overloads POLICY] [--rename METHOD=NAME]... [--output FILE] INPUT...
+
+INPUT is a .class file, a .jar, or a directory of .class files.
+PATTERN is a dotted class name in which * matches anything, or Class#member to select members.
+POLICY names overloaded methods: types (describe_int, the default), arity (describe_1) or explicit.
+METHOD is Class#name(parameter descriptors), such as com.example.Widget#describe(I).
+The bindings go to standard output unless there is an --output.";
+
+fn main() {
+    let mut bindgen = Bindgen::new();
+    let mut output = None;
+    let mut inputs = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |option: &str| {
+            args.next().unwrap_or_else(|| {
+                eprintln!("{} needs a value\n\n{}", option, USAGE);
+                process::exit(2);
+            })
+        };
+        match arg.as_str() {
+            "--include" | "-i" => bindgen = bindgen.include(&value(&arg)),
+            "--exclude" | "-x" => bindgen = bindgen.exclude(&value(&arg)),
+            "--overloads" => match value(&arg).parse() {
+                Ok(overloads) => bindgen = bindgen.overloads(overloads),
+                Err(message) => {
+                    eprintln!("{}\n\n{}", message, USAGE);
+                    process::exit(2);
+                }
+            },
+            "--rename" => {
+                let rename = value(&arg);
+                match rename.rsplit_once('=') {
+                    Some((method, rust_name)) => bindgen = bindgen.rename(method, rust_name),
+                    None => {
+                        eprintln!("--rename needs METHOD=NAME\n\n{}", USAGE);
+                        process::exit(2);
+                    }
+                }
+            }
+            "--output" | "-o" => output = Some(value(&arg)),
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with('-') => {
+                eprintln!("unknown option {}\n\n{}", arg, USAGE);
+                process::exit(2);
+            }
+            _ => {
+                bindgen = bindgen.input(arg);
+                inputs += 1;
+            }
+        }
+    }
+    if inputs == 0 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let outcome = match output {
+        Some(path) => bindgen.write_to(path),
+        None => bindgen.generate().map(|source| print!("{}", source)),
+    };
+    if let Err(e) = outcome {
+        eprintln!("jni_boilerplate_bindgen: {}", e);
+        process::exit(1);
+    }
+}
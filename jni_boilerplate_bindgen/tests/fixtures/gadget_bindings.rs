@@ -0,0 +1,123 @@
+// generated by jni_boilerplate_bindgen from compiled java classes; do not edit
+
+pub mod com {
+    pub mod purplefrog {
+        pub mod bindgen_fixture {
+            /// `com.purplefrog.bindgen_fixture.Gadget`
+            #[derive(jni_boilerplate::JavaWrapper)]
+            #[java_class("com.purplefrog.bindgen_fixture.Gadget")]
+            #[repr(C)]
+            pub struct Gadget<'a: 'b, 'b> {
+                #[allow(dead_code)]
+                java_this: jni::objects::AutoLocal<'a, 'b>,
+                #[allow(dead_code)]
+                jni_env: &'b jni::JNIEnv<'a>,
+            }
+
+            #[jni_boilerplate::jni_class]
+            impl<'a: 'b, 'b> Gadget<'a, 'b> {
+                pub const MAX_COUNT: i32 = 10;
+                pub const BIG: i64 = 1099511627776;
+                pub const SMALL: i16 = -3;
+                pub const RATIO: f64 = 0.5;
+                pub const NOT_A_NUMBER: f32 = f32::NAN;
+                pub const ENABLED: bool = true;
+                pub const LETTER: char = 'g';
+                pub const GREETING: &'static str = "hi \"there\"\né\0";
+                // skipped instances: static fields other than constants are not supported
+                jni_boilerplate::jni_field! { count: i32 }
+
+                #[constructor]
+                pub fn new() -> Self;
+
+                #[constructor]
+                pub fn new_int(arg0: i32) -> Self;
+
+                pub fn getCount(&self) -> i32;
+
+                #[java_name = "describe"]
+                pub fn describe_int(&self, arg0: i32) -> String;
+
+                #[java_name = "describe"]
+                pub fn describe_string(&self, arg0: &str) -> String;
+
+                pub fn copy(&self) -> Gadget<'a, 'b>;
+
+                pub fn absorb(&self, arg0: &Gadget<'a, 'b>);
+
+                #[java_name = "match"]
+                pub fn match_(&self, arg0: &Gadget_Part<'a, 'b>) -> bool;
+
+                #[static_method]
+                pub fn sum(arg0: &[i32]) -> i32;
+
+                #[static_method]
+                pub fn split(arg0: &str) -> Vec<String>;
+                // skipped names()Ljava/util/List;: no binding for java.util.List
+            }
+
+            /// `com.purplefrog.bindgen_fixture.Gadget$BigPart`
+            #[derive(jni_boilerplate::JavaWrapper)]
+            #[java_class("com.purplefrog.bindgen_fixture.Gadget$BigPart")]
+            #[allow(non_camel_case_types)]
+            #[repr(C)]
+            pub struct Gadget_BigPart<'a: 'b, 'b> {
+                #[allow(dead_code)]
+                java_this: jni::objects::AutoLocal<'a, 'b>,
+                #[allow(dead_code)]
+                jni_env: &'b jni::JNIEnv<'a>,
+            }
+
+            jni_boilerplate_helper::jni_extends! { Gadget_BigPart extends Gadget_Part implements Gadget_Measured }
+
+            #[jni_boilerplate::jni_class]
+            impl<'a: 'b, 'b> Gadget_BigPart<'a, 'b> {
+                #[constructor]
+                pub fn new(arg0: i32) -> Self;
+
+                pub fn isBig(&self) -> bool;
+            }
+
+            /// `com.purplefrog.bindgen_fixture.Gadget$Measured`
+            #[derive(jni_boilerplate::JavaWrapper)]
+            #[java_class("com.purplefrog.bindgen_fixture.Gadget$Measured")]
+            #[allow(non_camel_case_types)]
+            #[repr(C)]
+            pub struct Gadget_Measured<'a: 'b, 'b> {
+                #[allow(dead_code)]
+                java_this: jni::objects::AutoLocal<'a, 'b>,
+                #[allow(dead_code)]
+                jni_env: &'b jni::JNIEnv<'a>,
+            }
+
+            #[jni_boilerplate::jni_class]
+            impl<'a: 'b, 'b> Gadget_Measured<'a, 'b> {
+                pub fn measure(&self) -> i32;
+            }
+
+            /// `com.purplefrog.bindgen_fixture.Gadget$Part`
+            #[derive(jni_boilerplate::JavaWrapper)]
+            #[java_class("com.purplefrog.bindgen_fixture.Gadget$Part")]
+            #[allow(non_camel_case_types)]
+            #[repr(C)]
+            pub struct Gadget_Part<'a: 'b, 'b> {
+                #[allow(dead_code)]
+                java_this: jni::objects::AutoLocal<'a, 'b>,
+                #[allow(dead_code)]
+                jni_env: &'b jni::JNIEnv<'a>,
+            }
+
+            jni_boilerplate_helper::jni_extends! { Gadget_Part implements Gadget_Measured }
+
+            #[jni_boilerplate::jni_class]
+            impl<'a: 'b, 'b> Gadget_Part<'a, 'b> {
+                jni_boilerplate::jni_field! { size: i32 }
+
+                #[constructor]
+                pub fn new(arg0: i32) -> Self;
+
+                pub fn measure(&self) -> i32;
+            }
+        }
+    }
+}
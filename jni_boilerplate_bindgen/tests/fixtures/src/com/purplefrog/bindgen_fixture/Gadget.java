@@ -0,0 +1,123 @@
+package com.purplefrog.bindgen_fixture;
+
+import java.util.List;
+import java.util.ArrayList;
+
+/**
+ * exercises the binding generator: constants, overloads, arrays, fields, inheritance and members it has to skip
+ */
+public class Gadget
+{
+    public static final int MAX_COUNT = 10;
+    public static final long BIG = 1L << 40;
+    public static final short SMALL = -3;
+    public static final double RATIO = 0.5;
+    public static final float NOT_A_NUMBER = Float.NaN;
+    public static final boolean ENABLED = true;
+    public static final char LETTER = 'g';
+    public static final String GREETING = "hi \"there\"\n\u00e9\u0000";
+
+    public static int instances;
+
+    public int count;
+    private String secret = "shh";
+
+    public Gadget()
+    {
+        instances++;
+    }
+
+    public Gadget(int count)
+    {
+        this();
+        this.count = count;
+    }
+
+    public int getCount()
+    {
+        return count;
+    }
+
+    public String describe(int n)
+    {
+        return "int " + n;
+    }
+
+    public String describe(String s)
+    {
+        return "string " + s;
+    }
+
+    public Gadget copy()
+    {
+        return new Gadget(count);
+    }
+
+    public void absorb(Gadget other)
+    {
+        count += other.count;
+    }
+
+    public boolean match(Part part)
+    {
+        return part.size == count;
+    }
+
+    public static int sum(int[] values)
+    {
+        int rval = 0;
+        for (int v : values) {
+            rval += v;
+        }
+        return rval;
+    }
+
+    public static String[] split(String text)
+    {
+        return text.split(" ");
+    }
+
+    public List<String> names()
+    {
+        return new ArrayList<>();
+    }
+
+    private void hidden()
+    {
+    }
+
+    public interface Measured
+    {
+        int measure();
+    }
+
+    public static class Part
+        implements Measured
+    {
+        public int size;
+
+        public Part(int size)
+        {
+            this.size = size;
+        }
+
+        public int measure()
+        {
+            return size;
+        }
+    }
+
+    public static class BigPart
+        extends Part
+    {
+        public BigPart(int size)
+        {
+            super(size * 10);
+        }
+
+        public boolean isBig()
+        {
+            return true;
+        }
+    }
+}
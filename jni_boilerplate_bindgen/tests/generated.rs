@@ -0,0 +1,237 @@
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JObject};
+use jni::JNIEnv;
+use jni_boilerplate_bindgen::{Bindgen, OverloadNaming};
+use jni_boilerplate_helper::inheritance::JavaObjectWrapper;
+use jni_boilerplate_helper::test_jvm;
+
+mod bindings {
+    include!("fixtures/gadget_bindings.rs");
+}
+
+use bindings::com::purplefrog::bindgen_fixture::{
+    Gadget, Gadget_BigPart, Gadget_Measured, Gadget_Part,
+};
+
+const EXPECTED: &str = include_str!("fixtures/gadget_bindings.rs");
+
+#[test]
+fn bindings_are_up_to_date() {
+    // the same classes, as a jar, a stored jar, a directory and individual files
+    let inputs: &[&[&str]] = &[
+        &["tests/fixtures/gadget.jar"],
+        &["tests/fixtures/gadget_stored.jar"],
+        &["tests/fixtures/com"],
+        &[
+            "tests/fixtures/com/purplefrog/bindgen_fixture/Gadget.class",
+            "tests/fixtures/com/purplefrog/bindgen_fixture/Gadget$Part.class",
+            "tests/fixtures/com/purplefrog/bindgen_fixture/Gadget$Measured.class",
+            "tests/fixtures/com/purplefrog/bindgen_fixture/Gadget$BigPart.class",
+        ],
+    ];
+    for paths in inputs {
+        let bindgen = paths.iter().fold(Bindgen::new(), |b, path| b.input(*path));
+        assert_eq!(EXPECTED, bindgen.generate().unwrap(), "from {:?}", paths);
+    }
+}
+
+#[test]
+fn filters() {
+    let source = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .exclude("*$Part")
+        .exclude("*#describe")
+        .exclude("*#GREETING")
+        .generate()
+        .unwrap();
+    assert!(!source.contains("pub struct Gadget_Part"));
+    // without its wrapper, the method that takes a Part is skipped
+    assert!(source.contains("// skipped match(Lcom/purplefrog/bindgen_fixture/Gadget$Part;)Z: no binding for com.purplefrog.bindgen_fixture.Gadget$Part"));
+    assert!(!source.contains("describe"));
+    assert!(!source.contains("GREETING"));
+    assert!(source.contains("pub fn getCount(&self) -> i32;"));
+
+    let source = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .include("com.purplefrog.other.*")
+        .generate()
+        .unwrap();
+    assert!(!source.contains("pub struct"));
+
+    assert!(Bindgen::new()
+        .input("tests/fixtures/no_such.jar")
+        .generate()
+        .is_err());
+}
+
+#[test]
+fn overload_naming() {
+    // both describes take one parameter, so they can not be named by arity
+    let source = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .overloads(OverloadNaming::Arity)
+        .exclude("*#describe")
+        .generate()
+        .unwrap();
+    assert!(source.contains("pub fn new_1(arg0: i32) -> Self;"));
+
+    let source = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .overloads(OverloadNaming::Explicit)
+        .rename("com.purplefrog.bindgen_fixture.Gadget#new(I)", "with_count")
+        .rename(
+            "com.purplefrog.bindgen_fixture.Gadget#describe(I)",
+            "describe",
+        )
+        .rename(
+            "com.purplefrog.bindgen_fixture.Gadget#describe(Ljava/lang/String;)",
+            "describe_text",
+        )
+        .generate()
+        .unwrap();
+    assert!(source.contains("pub fn with_count(arg0: i32) -> Self;"));
+    assert!(source.contains("pub fn describe(&self, arg0: i32) -> String;"));
+    assert!(source.contains(
+        "#[java_name = \"describe\"]\n                pub fn describe_text(&self, arg0: &str) -> String;"
+    ));
+
+    let message = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .overloads(OverloadNaming::Explicit)
+        .generate()
+        .unwrap_err()
+        .to_string();
+    assert_eq!("com.purplefrog.bindgen_fixture.Gadget: describe is overloaded; name each overload explicitly or choose an overload naming policy", message);
+
+    let message = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .overloads(OverloadNaming::Arity)
+        .generate()
+        .unwrap_err()
+        .to_string();
+    assert_eq!("com.purplefrog.bindgen_fixture.Gadget: describe(I) and describe(Ljava/lang/String;) would both be named describe_1 under the arity overload naming policy", message);
+
+    let message = Bindgen::new()
+        .input("tests/fixtures/gadget.jar")
+        .rename("com.purplefrog.bindgen_fixture.Gadget#copy()", "getCount")
+        .generate()
+        .unwrap_err()
+        .to_string();
+    assert_eq!(
+        "com.purplefrog.bindgen_fixture.Gadget: more than one member would be named getCount",
+        message
+    );
+}
+
+#[test]
+fn constants() {
+    assert_eq!(10, Gadget::MAX_COUNT);
+    assert_eq!(1 << 40, Gadget::BIG);
+    assert_eq!(-3, Gadget::SMALL);
+    assert!(Gadget::NOT_A_NUMBER.is_nan());
+    assert_eq!('g', Gadget::LETTER);
+    assert_eq!("hi \"there\"\né\0", Gadget::GREETING);
+}
+
+/// puts the fixture classes in the system class loader of the test JVM
+fn define_fixtures(jni_env: &JNIEnv) -> Result<(), Error> {
+    let loader = jni_env
+        .call_static_method(
+            "java/lang/ClassLoader",
+            "getSystemClassLoader",
+            "()Ljava/lang/ClassLoader;",
+            &[],
+        )?
+        .l()?;
+    let loader = AutoLocal::new(jni_env, loader);
+    if jni_env
+        .find_class("com/purplefrog/bindgen_fixture/Gadget")
+        .is_ok()
+    {
+        return Ok(());
+    }
+    jni_env.exception_clear()?;
+    let classes: [(&str, &[u8]); 4] = [
+        (
+            "com/purplefrog/bindgen_fixture/Gadget$Measured",
+            include_bytes!("fixtures/com/purplefrog/bindgen_fixture/Gadget$Measured.class"),
+        ),
+        (
+            "com/purplefrog/bindgen_fixture/Gadget$Part",
+            include_bytes!("fixtures/com/purplefrog/bindgen_fixture/Gadget$Part.class"),
+        ),
+        (
+            "com/purplefrog/bindgen_fixture/Gadget$BigPart",
+            include_bytes!("fixtures/com/purplefrog/bindgen_fixture/Gadget$BigPart.class"),
+        ),
+        (
+            "com/purplefrog/bindgen_fixture/Gadget",
+            include_bytes!("fixtures/com/purplefrog/bindgen_fixture/Gadget.class"),
+        ),
+    ];
+    for (name, bytes) in &classes {
+        let class = jni_env.define_class(*name, loader.as_obj(), bytes)?;
+        jni_env.delete_local_ref(JObject::from(class))?;
+    }
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn calls() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+    define_fixtures(&jni_env)?;
+
+    let gadget = Gadget::new_int(&jni_env, 3)?;
+    assert_eq!(3, gadget.getCount()?);
+    assert_eq!("int 4", gadget.describe_int(4)?);
+    assert_eq!("string four", gadget.describe_string("four")?);
+
+    let other = gadget.copy()?;
+    other.absorb(&gadget)?;
+    assert_eq!(6, other.getCount()?);
+
+    gadget.set_count(7)?;
+    assert_eq!(7, gadget.get_count()?);
+    assert!(gadget.match_(&Gadget_Part::new(&jni_env, 7)?)?);
+    assert!(!gadget.match_(&Gadget_Part::new(&jni_env, 8)?)?);
+
+    assert_eq!(6, Gadget::sum(&jni_env, &[1, 2, 3])?);
+    assert_eq!(
+        vec![String::from("a"), String::from("b")],
+        Gadget::split(&jni_env, "a b")?
+    );
+    assert_eq!(0, Gadget::new(&jni_env)?.getCount()?);
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs a JVM"]
+fn inheritance() -> Result<(), Error> {
+    let jni_env = test_jvm::java_vm().attach_current_thread()?;
+    define_fixtures(&jni_env)?;
+
+    let big = Gadget_BigPart::new(&jni_env, 2)?;
+    assert!(big.isBig()?);
+    // Part's field and method, through Deref
+    assert_eq!(20, big.get_size()?);
+    assert_eq!(20, big.measure()?);
+    let measured: &Gadget_Measured = big.as_ref();
+    assert_eq!(20, measured.measure()?);
+
+    let gadget = Gadget::new_int(&jni_env, 20)?;
+    assert!(gadget.match_(&big)?);
+
+    let part: Gadget_Part = big.upcast();
+    let Ok(big) = part.downcast::<Gadget_BigPart>() else {
+        panic!("the part is a BigPart");
+    };
+    assert!(big.isBig()?);
+    // a failed cast hands the wrapper back, with nothing thrown
+    let Err(part) = Gadget_Part::new(&jni_env, 1)?.downcast::<Gadget_BigPart>() else {
+        panic!("a plain Part is not a BigPart");
+    };
+    assert!(!jni_env.exception_check()?);
+    assert_eq!(1, part.get_size()?);
+    Ok(())
+}
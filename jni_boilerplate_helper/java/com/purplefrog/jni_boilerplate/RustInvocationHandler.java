@@ -0,0 +1,96 @@
+package com.purplefrog.jni_boilerplate;
+
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Method;
+
+/**
+ * The InvocationHandler behind the java.lang.reflect.Proxy objects made by JavaCallback.
+ * The class is defined at run time from the bytes embedded in jni_boilerplate_helper,
+ * and its native methods are registered by the rust side.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d java java/com/purplefrog/jni_boilerplate/RustInvocationHandler.java</pre>
+ * from the jni_boilerplate_helper directory.
+ */
+public final class RustInvocationHandler
+    implements InvocationHandler
+{
+    /** java.lang.ref.Cleaner.create() and Cleaner.register(), or nulls before java 9 */
+    private static final Object CLEANER;
+    private static final Method REGISTER;
+
+    static {
+        Object cleaner = null;
+        Method register = null;
+        try {
+            Class<?> cleanerClass = Class.forName("java.lang.ref.Cleaner");
+            cleaner = cleanerClass.getMethod("create").invoke(null);
+            register = cleanerClass.getMethod("register", Object.class, Runnable.class);
+        } catch (ReflectiveOperationException e) {
+            cleaner = null;
+            register = null;
+        }
+        CLEANER = cleaner;
+        REGISTER = register;
+    }
+
+    /** a pointer to the rust closure, freed when this handler is collected */
+    private final long handle;
+
+    /** whether the Cleaner frees the closure, which finalize() does otherwise */
+    private final boolean cleaned;
+
+    private RustInvocationHandler(long handle)
+    {
+        this.handle = handle;
+        this.cleaned = registerRelease(this, handle);
+    }
+
+    /**
+     * Frees the closure with a Cleaner, which still runs when finalization is disabled
+     * (--finalization=disabled on java 18 and later).  The action must not refer to the handler.
+     */
+    private static boolean registerRelease(Object handler, final long handle)
+    {
+        if (REGISTER == null) {
+            return false;
+        }
+        Runnable action = () -> release(handle);
+        try {
+            REGISTER.invoke(CLEANER, handler, action);
+            return true;
+        } catch (ReflectiveOperationException e) {
+            return false;
+        }
+    }
+
+    public Object invoke(Object proxy, Method method, Object[] args)
+        throws Throwable
+    {
+        if (method.getDeclaringClass() == Object.class) {
+            String name = method.getName();
+            if (name.equals("equals")) {
+                return proxy == args[0];
+            } else if (name.equals("hashCode")) {
+                return System.identityHashCode(proxy);
+            } else if (name.equals("toString")) {
+                return "RustCallback@" + Integer.toHexString(System.identityHashCode(proxy));
+            }
+        }
+        return invokeNative(handle, method.getName(), args == null ? new Object[0] : args);
+    }
+
+    /** an instance method, so this handler (and the closure) stays alive while rust runs */
+    private native Object invokeNative(long handle, String methodName, Object[] args);
+
+    private static native void release(long handle);
+
+    /** only for java 8, which has no Cleaner */
+    @SuppressWarnings("deprecation")
+    protected void finalize()
+    {
+        if (!cleaned) {
+            release(handle);
+        }
+    }
+}
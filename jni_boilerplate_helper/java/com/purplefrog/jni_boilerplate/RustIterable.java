@@ -0,0 +1,47 @@
+package com.purplefrog.jni_boilerplate;
+
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Method;
+import java.lang.reflect.UndeclaredThrowableException;
+import java.util.Iterator;
+
+/**
+ * A java.lang.Iterable whose iterators come from rust, made by JavaIterable.
+ * iterator() goes to a RustInvocationHandler; the default methods of Iterable do the rest.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d java java/com/purplefrog/jni_boilerplate/RustIterable.java</pre>
+ * from the jni_boilerplate_helper directory.
+ */
+public final class RustIterable
+    implements Iterable<Object>
+{
+    private static final Method ITERATOR;
+
+    static {
+        try {
+            ITERATOR = Iterable.class.getMethod("iterator");
+        } catch (NoSuchMethodException e) {
+            throw new AssertionError(e);
+        }
+    }
+
+    private final InvocationHandler handler;
+
+    private RustIterable(InvocationHandler handler)
+    {
+        this.handler = handler;
+    }
+
+    @SuppressWarnings("unchecked")
+    public Iterator<Object> iterator()
+    {
+        try {
+            return (Iterator<Object>) handler.invoke(this, ITERATOR, null);
+        } catch (RuntimeException | Error e) {
+            throw e;
+        } catch (Throwable t) {
+            throw new UndeclaredThrowableException(t);
+        }
+    }
+}
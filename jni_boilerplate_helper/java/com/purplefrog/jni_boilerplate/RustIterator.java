@@ -0,0 +1,58 @@
+package com.purplefrog.jni_boilerplate;
+
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Method;
+import java.lang.reflect.UndeclaredThrowableException;
+import java.util.Iterator;
+
+/**
+ * A java.util.Iterator over a rust iterator, made by JavaIterator and JavaIterable.
+ * hasNext() and next() go to a RustInvocationHandler; the default methods of Iterator do the rest.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d java java/com/purplefrog/jni_boilerplate/RustIterator.java</pre>
+ * from the jni_boilerplate_helper directory.
+ */
+public final class RustIterator
+    implements Iterator<Object>
+{
+    private static final Method HAS_NEXT;
+    private static final Method NEXT;
+
+    static {
+        try {
+            HAS_NEXT = Iterator.class.getMethod("hasNext");
+            NEXT = Iterator.class.getMethod("next");
+        } catch (NoSuchMethodException e) {
+            throw new AssertionError(e);
+        }
+    }
+
+    private final InvocationHandler handler;
+
+    private RustIterator(InvocationHandler handler)
+    {
+        this.handler = handler;
+    }
+
+    public boolean hasNext()
+    {
+        return (Boolean) call(HAS_NEXT);
+    }
+
+    public Object next()
+    {
+        return call(NEXT);
+    }
+
+    private Object call(Method method)
+    {
+        try {
+            return handler.invoke(this, method, null);
+        } catch (RuntimeException | Error e) {
+            throw e;
+        } catch (Throwable t) {
+            throw new UndeclaredThrowableException(t);
+        }
+    }
+}
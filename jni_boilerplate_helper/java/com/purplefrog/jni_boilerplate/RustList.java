@@ -0,0 +1,61 @@
+package com.purplefrog.jni_boilerplate;
+
+import java.lang.reflect.InvocationHandler;
+import java.lang.reflect.Method;
+import java.lang.reflect.UndeclaredThrowableException;
+import java.util.AbstractList;
+import java.util.List;
+import java.util.RandomAccess;
+
+/**
+ * A read-only java.util.List over a rust slice, made by JavaList.
+ * get() and size() go to a RustInvocationHandler; AbstractList does the rest.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d java java/com/purplefrog/jni_boilerplate/RustList.java</pre>
+ * from the jni_boilerplate_helper directory.
+ */
+public final class RustList
+    extends AbstractList<Object>
+    implements RandomAccess
+{
+    private static final Method GET;
+    private static final Method SIZE;
+
+    static {
+        try {
+            GET = List.class.getMethod("get", int.class);
+            SIZE = List.class.getMethod("size");
+        } catch (NoSuchMethodException e) {
+            throw new AssertionError(e);
+        }
+    }
+
+    private final InvocationHandler handler;
+
+    private RustList(InvocationHandler handler)
+    {
+        this.handler = handler;
+    }
+
+    public Object get(int index)
+    {
+        return call(GET, new Object[] { index });
+    }
+
+    public int size()
+    {
+        return (Integer) call(SIZE, null);
+    }
+
+    private Object call(Method method, Object[] args)
+    {
+        try {
+            return handler.invoke(this, method, args);
+        } catch (RuntimeException | Error e) {
+            throw e;
+        } catch (Throwable t) {
+            throw new UndeclaredThrowableException(t);
+        }
+    }
+}
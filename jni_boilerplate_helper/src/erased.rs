@@ -0,0 +1,281 @@
+//! Java generics are erased: to the JVM, `List<E>.get(int)` is `get(I)Ljava/lang/Object;`
+//! and `Comparable<T>.compareTo(T)` is `compareTo(Ljava/lang/Object;)I`, whatever `E` and `T` are.
+//! A binding that names the rust type it wants gets that type's descriptor instead, and a
+//! `NoSuchMethodError`.
+//!
+//! `Erased<T>` is the type parameter in a binding: its descriptor is `java.lang.Object`'s
+//! (or a bound's, with `Erased<T, Bound>`) while the value converts to and from `T`.
+//! Coming back from java the object is checked against `T`'s class first, as java's own cast would be.
+//! A primitive `T` is boxed and unboxed, so `Erased<i32>` is a `java.lang.Integer`.
+//!
+//! ```
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::erased::Erased;
+//! use jni_boilerplate_helper::{jni_wrapper_cliche_impl, JavaSignatureFor};
+//!
+//! jni_wrapper_cliche_impl! { Comparable, "java/lang/Comparable" }
+//!
+//! assert_eq!("Ljava/lang/Object;", Erased::<String>::signature_for());
+//! assert_eq!(
+//!     "Ljava/lang/Comparable;",
+//!     Erased::<i32, Comparable>::signature_for()
+//! );
+//! ```
+
+use crate::inheritance::throw_class_cast_exception;
+use crate::jni;
+use crate::local_frame::LeaveFrame;
+use crate::{
+    java_exception, ConvertJValueToRust, ConvertRustToJValue, JValueNonScalar, JavaClassNameFor,
+    JavaSignatureFor,
+};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JClass, JObject, JValue};
+use jni::JNIEnv;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+/// The erasure of a type parameter without a bound, `java.lang.Object`.
+pub struct Unbounded;
+
+impl JavaClassNameFor for Unbounded {
+    fn java_class_name() -> &'static str {
+        "java/lang/Object"
+    }
+}
+
+/// A `T` passed to or returned from java as its erasure, `Bound`'s class.
+/// `Bound` is anything with a `JavaClassNameFor`, usually a wrapper.
+pub struct Erased<T, Bound = Unbounded> {
+    value: T,
+    bound: PhantomData<fn() -> Bound>,
+}
+
+impl<T, Bound> Erased<T, Bound> {
+    pub fn new(value: T) -> Erased<T, Bound> {
+        Erased {
+            value,
+            bound: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, Bound> From<T> for Erased<T, Bound> {
+    fn from(value: T) -> Erased<T, Bound> {
+        Erased::new(value)
+    }
+}
+
+impl<T, Bound> Deref for Erased<T, Bound> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, Bound> DerefMut for Erased<T, Bound> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug, Bound> fmt::Debug for Erased<T, Bound> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Erased").field(&self.value).finish()
+    }
+}
+
+impl<T, Bound: JavaClassNameFor> JavaSignatureFor for Erased<T, Bound> {
+    fn signature_for() -> String {
+        format!("L{};", Bound::java_class_name())
+    }
+}
+
+/// so that a slice of them is a `Bound[]`, such as the `Object...` of `Arrays.asList(T...)`
+impl<T, Bound: JavaClassNameFor> JavaClassNameFor for Erased<T, Bound> {
+    fn java_class_name() -> &'static str {
+        Bound::java_class_name()
+    }
+}
+
+impl<T, Bound> JValueNonScalar for Erased<T, Bound> {}
+
+impl<'a: 'b, 'b, T: LeaveFrame<'a, 'b>, Bound> LeaveFrame<'a, 'b> for Erased<T, Bound> {
+    type Rest = T::Rest;
+
+    fn leave_frame(self) -> (JObject<'a>, T::Rest) {
+        self.value.leave_frame()
+    }
+
+    fn enter_frame(jni_env: &'b JNIEnv<'a>, object: JObject<'a>, rest: T::Rest) -> Self {
+        Erased::new(T::enter_frame(jni_env, object, rest))
+    }
+}
+
+/// the class that boxes a primitive descriptor, and the method that unboxes it
+pub fn boxing(descriptor: &str) -> Option<(&'static str, &'static str)> {
+    match descriptor {
+        "Z" => Some(("java/lang/Boolean", "booleanValue")),
+        "B" => Some(("java/lang/Byte", "byteValue")),
+        "C" => Some(("java/lang/Character", "charValue")),
+        "S" => Some(("java/lang/Short", "shortValue")),
+        "I" => Some(("java/lang/Integer", "intValue")),
+        "J" => Some(("java/lang/Long", "longValue")),
+        "F" => Some(("java/lang/Float", "floatValue")),
+        "D" => Some(("java/lang/Double", "doubleValue")),
+        _ => None,
+    }
+}
+
+/// The class an erased value must be an instance of to become a `T` with this descriptor:
+/// `Ljava/lang/String;` is `java/lang/String`, `I` is `java/lang/Integer`, and an array is its own descriptor.
+pub fn erased_class(descriptor: &str) -> &str {
+    if let Some((class, _)) = boxing(descriptor) {
+        return class;
+    }
+    descriptor
+        .strip_prefix('L')
+        .and_then(|class| class.strip_suffix(';'))
+        .unwrap_or(descriptor)
+}
+
+/// Fails with a `java.lang.ClassCastException` when the object is not an instance of `T`'s class,
+/// and with a `java.lang.NullPointerException` when it is a null that would have to be unboxed.
+impl<'a: 'b, 'b, T, Bound> ConvertJValueToRust<'a, 'b> for Erased<T, Bound>
+where
+    T: ConvertJValueToRust<'a, 'b> + JavaSignatureFor,
+{
+    fn to_rust(je: &'b JNIEnv<'a>, val: JValue<'a>) -> Result<Self, Error> {
+        let descriptor = T::signature_for();
+        // held before the class lookup, so that it goes away if that fails
+        let object = AutoLocal::new(je, val.l()?);
+        let class = erased_class(&descriptor);
+        let class_object = AutoLocal::new(je, *je.find_class(class)?);
+        if !je.is_instance_of(object.as_obj(), JClass::from(class_object.as_obj()))? {
+            return Err(throw_class_cast_exception(je, object.as_obj(), class));
+        }
+
+        let value = match boxing(&descriptor) {
+            Some((class, unbox)) => {
+                if object.as_obj().is_null() {
+                    je.throw_new(
+                        "java/lang/NullPointerException",
+                        format!("cannot unbox a null {}", class.replace('/', ".")),
+                    )?;
+                    return Err(java_exception());
+                }
+                let primitive =
+                    je.call_method(object.as_obj(), unbox, format!("(){}", descriptor), &[])?;
+                T::to_rust(je, primitive)?
+            }
+            // T's conversion deletes the reference itself
+            None => T::to_rust(je, JValue::from(object.forget()))?,
+        };
+        Ok(Erased::new(value))
+    }
+}
+
+impl<'a: 'b, 'b, T, Bound> ConvertRustToJValue<'a, 'b> for Erased<T, Bound>
+where
+    T: ConvertRustToJValue<'a, 'b> + JavaSignatureFor,
+{
+    /// the value's own temporary, and the box around it when it is a primitive
+    type T = (
+        <T as ConvertRustToJValue<'a, 'b>>::T,
+        Option<AutoLocal<'a, 'b>>,
+    );
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        let tmp = self.value.into_temporary(je)?;
+        let descriptor = T::signature_for();
+        let boxed = match boxing(&descriptor) {
+            Some((class, _)) => {
+                let boxed = je
+                    .call_static_method(
+                        class,
+                        "valueOf",
+                        format!("({})L{};", descriptor, class),
+                        &[T::temporary_into_jvalue(&tmp)],
+                    )?
+                    .l()?;
+                Some(AutoLocal::new(je, boxed))
+            }
+            None => None,
+        };
+        Ok((tmp, boxed))
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        match &tmp.1 {
+            Some(boxed) => JValue::from(boxed.as_obj()),
+            None => T::temporary_into_jvalue(&tmp.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{erased_class, Erased};
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::{ConvertJValueToRust, ConvertRustToJValue, JavaSignatureFor};
+    use jni::errors::Error;
+    use jni::objects::{AutoLocal, JValue};
+
+    #[test]
+    fn classes() {
+        assert_eq!("java/lang/String", erased_class("Ljava/lang/String;"));
+        assert_eq!("java/lang/Integer", erased_class("I"));
+        assert_eq!("java/lang/Boolean", erased_class("Z"));
+        assert_eq!("[I", erased_class("[I"));
+        assert_eq!("Ljava/lang/Object;", Erased::<i64>::signature_for());
+        assert_eq!("[Ljava/lang/Object;", <&[Erased<i64>]>::signature_for());
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn round_trips() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let list = jni_env.new_object("java/util/ArrayList", "()V", &[])?;
+        let list = AutoLocal::new(&jni_env, list);
+        let add = |value: JValue| {
+            jni_env
+                .call_method(list.as_obj(), "add", "(Ljava/lang/Object;)Z", &[value])
+                .and_then(|added| added.z())
+        };
+        let get = |index: i32| {
+            jni_env.call_method(
+                list.as_obj(),
+                "get",
+                "(I)Ljava/lang/Object;",
+                &[JValue::Int(index)],
+            )
+        };
+
+        let forty_two: Erased<i32> = Erased::new(42);
+        let tmp = forty_two.into_temporary(&jni_env)?;
+        assert!(add(Erased::<i32>::temporary_into_jvalue(&tmp))?);
+        let word: Erased<&str> = Erased::new("word");
+        let tmp = word.into_temporary(&jni_env)?;
+        assert!(add(Erased::<&str>::temporary_into_jvalue(&tmp))?);
+
+        assert_eq!(42, *Erased::<i32>::to_rust(&jni_env, get(0)?)?);
+        assert_eq!(
+            "word",
+            Erased::<String>::to_rust(&jni_env, get(1)?)?.into_inner()
+        );
+
+        assert!(Erased::<String>::to_rust(&jni_env, get(0)?).is_err());
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/lang/ClassCastException")?);
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+//! What to do with a java exception after `raise_if_exception` has cleared it.
+//!
+//! The policy is the same in debug and release builds.  The process-wide default is
+//! `ExceptionReporting::Log(log::Level::Debug)`; use `set_exception_reporting()` to change it.
+//!
+//! The generated bindings only ever use the process-wide policy; there is no per-binding setting.
+//! `raise_if_exception_with()` and `ClearIfErr::clear_if_err_with()` take a policy of their own,
+//! for hand-written JNI calls that want a different one.
+
+use crate::java_runtime_wrappers::stack_trace_string;
+use crate::jni;
+use jni::objects::JObject;
+use jni::JNIEnv;
+use log::{log, Level};
+use std::sync::{Arc, RwLock};
+
+/// The signature of a user-supplied exception reporter.
+/// The throwable has already been cleared from the JNI environment, so the callback is free to call back into java.
+pub type ExceptionCallback = dyn Fn(&JNIEnv, JObject) + Send + Sync;
+
+#[derive(Clone)]
+pub enum ExceptionReporting {
+    /// drop the throwable on the floor
+    Silent,
+    /// render the stack trace with `Throwable.printStackTrace(PrintWriter)` and send it to the `log` crate
+    Log(Level),
+    /// hand the throwable to a user callback
+    Callback(Arc<ExceptionCallback>),
+}
+
+impl ExceptionReporting {
+    pub fn callback<F>(f: F) -> ExceptionReporting
+    where
+        F: Fn(&JNIEnv, JObject) + Send + Sync + 'static,
+    {
+        ExceptionReporting::Callback(Arc::new(f))
+    }
+
+    /// `throwable` must already have been cleared from `jni_env` with `exception_clear()`.
+    /// Problems encountered while reporting are themselves only reported with `debug!`.
+    pub fn report(&self, jni_env: &JNIEnv, throwable: JObject) {
+        match self {
+            ExceptionReporting::Silent => {}
+            ExceptionReporting::Log(level) => {
+                let level = *level;
+                if !log::log_enabled!(level) {
+                    return;
+                }
+                match stack_trace_string(jni_env, throwable) {
+                    Ok(trace) => log!(level, "java exception: {}", trace),
+                    Err(e) => {
+                        let _ = jni_env.exception_clear();
+                        log!(
+                            level,
+                            "java exception (failed to render stack trace: {:?})",
+                            e
+                        )
+                    }
+                }
+            }
+            ExceptionReporting::Callback(callback) => callback(jni_env, throwable),
+        }
+    }
+}
+
+impl Default for ExceptionReporting {
+    fn default() -> Self {
+        ExceptionReporting::Log(Level::Debug)
+    }
+}
+
+static EXCEPTION_REPORTING: RwLock<Option<ExceptionReporting>> = RwLock::new(None);
+
+/// Replace the process-wide policy used by `raise_if_exception()` and every generated binding.
+pub fn set_exception_reporting(policy: ExceptionReporting) {
+    match EXCEPTION_REPORTING.write() {
+        Ok(mut guard) => *guard = Some(policy),
+        Err(poisoned) => *poisoned.into_inner() = Some(policy),
+    }
+}
+
+/// A copy of the current process-wide policy.
+pub fn exception_reporting() -> ExceptionReporting {
+    let guard = match EXCEPTION_REPORTING.read() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    guard.clone().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExceptionReporting;
+    use crate::test_jvm;
+    use crate::{jni, ClearIfErr, ConvertJValueToRust};
+    use jni::errors::Error;
+    use jni::objects::JValue;
+    use jni::JNIEnv;
+    use std::sync::{Arc, Mutex};
+
+    /// `Integer.parseInt(text)`
+    fn parse_int(jni_env: &JNIEnv, text: &str) -> Result<i32, Error> {
+        let text = jni_env.new_string(text)?;
+        jni_env
+            .call_static_method(
+                "java/lang/Integer",
+                "parseInt",
+                "(Ljava/lang/String;)I",
+                &[JValue::from(text)],
+            )
+            .and_then(|val| val.i())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn policy_for_one_call() -> Result<(), Error> {
+        let java_vm = test_jvm::java_vm();
+        let jni_env = java_vm.attach_current_thread()?;
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let reporting = {
+            let messages = messages.clone();
+            ExceptionReporting::callback(move |jni_env, throwable| {
+                let message = jni_env
+                    .call_method(throwable, "getMessage", "()Ljava/lang/String;", &[])
+                    .and_then(|message| String::to_rust(jni_env, message))
+                    .unwrap_or_else(|e| format!("{:?}", e));
+                messages.lock().unwrap().push(message);
+            })
+        };
+
+        assert!(parse_int(&jni_env, "12")
+            .clear_if_err_with(&jni_env, &reporting)
+            .is_ok());
+        assert!(parse_int(&jni_env, "twelve")
+            .clear_if_err_with(&jni_env, &reporting)
+            .is_err());
+        assert!(!jni_env.exception_check()?);
+        assert_eq!(
+            vec![String::from("For input string: \"twelve\"")],
+            *messages.lock().unwrap()
+        );
+
+        // a silent policy still clears the exception
+        assert!(parse_int(&jni_env, "")
+            .clear_if_err_with(&jni_env, &ExceptionReporting::Silent)
+            .is_err());
+        assert!(!jni_env.exception_check()?);
+        assert_eq!(1, messages.lock().unwrap().len());
+        Ok(())
+    }
+
+    /// implementations written before `clear_if_err_with()` existed still compile, and honour the policy they are given
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn older_implementations() -> Result<(), Error> {
+        struct Lenient(Result<i32, Error>);
+        impl ClearIfErr<i32> for Lenient {
+            fn clear_if_err(self, jni_env: &JNIEnv) -> Result<i32, Error> {
+                self.0.clear_if_err(jni_env).or(Ok(-1))
+            }
+        }
+
+        let java_vm = test_jvm::java_vm();
+        let jni_env = java_vm.attach_current_thread()?;
+        let reported = Arc::new(Mutex::new(0));
+        let reporting = {
+            let reported = reported.clone();
+            ExceptionReporting::callback(move |_, _| *reported.lock().unwrap() += 1)
+        };
+
+        let failed = Lenient(parse_int(&jni_env, "twelve"));
+        assert_eq!(-1, failed.clear_if_err_with(&jni_env, &reporting)?);
+        assert!(!jni_env.exception_check()?);
+        assert_eq!(1, *reported.lock().unwrap());
+
+        let parsed = Lenient(parse_int(&jni_env, "12"));
+        assert_eq!(12, parsed.clear_if_err_with(&jni_env, &reporting)?);
+        assert_eq!(1, *reported.lock().unwrap());
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+//! Wrappers that are not tied to a `JNIEnv` borrow.
+//!
+//! An ordinary wrapper holds a local reference, which is only good on its own thread until the native
+//! frame returns.  `Global<W>` holds a `GlobalRef` and the `JavaVM` instead, so it can live in a
+//! long-lived struct or a cache and cross threads.  To make calls, turn it back into an ordinary
+//! wrapper for whichever env the current thread has:
+//!
+//! ```
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::global::{Global, ToGlobal};
+//! use jni_boilerplate_helper::jni_wrapper_cliche_impl;
+//!
+//! jni_wrapper_cliche_impl! { Widget, "com/purplefrog/rust_callables/Widget" }
+//!
+//! fn keep(widget: &Widget) -> Result<Global<Widget<'static, 'static>>, jni::errors::Error> {
+//!     widget.to_global()
+//! }
+//!
+//! fn later(widget: &Global<Widget<'static, 'static>>) -> Result<(), jni::errors::Error> {
+//!     let jni_env = widget.attach()?;
+//!     let _widget: Widget = widget.with_local(&jni_env)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! `Weak<W>` is the same with a weak global reference, for caches that must not keep their objects alive.
+
+use crate::inheritance::JavaObjectWrapper;
+use crate::jni;
+use crate::native_method::new_local_ref;
+use crate::{wrap_jobject, JavaConstructible};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, GlobalRef, JObject};
+use jni::sys::jweak;
+use jni::{AttachGuard, JNIEnv, JavaVM};
+use log::debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// The same wrapper type with other lifetimes, so that a `Global` can hand back an ordinary wrapper
+/// for whatever env it is given.  Implemented by `jni_wrapper_cliche_impl!` and by `#[derive(JavaWrapper)]`
+/// on a struct whose only generic parameters are its two lifetimes.
+pub trait WrapperFor<'a: 'b, 'b> {
+    type Wrapper: JavaObjectWrapper<'a, 'b> + JavaConstructible<'a, 'b>;
+}
+
+/// A wrapper's object held by a global reference, along with the `JavaVM` it lives in.
+/// `W` is the wrapper type with any lifetimes; `to_global()` uses `'static` ones.
+pub struct Global<W> {
+    object: GlobalRef,
+    java_vm: Arc<JavaVM>,
+    wrapper: PhantomData<fn() -> W>,
+}
+
+impl<W> Global<W> {
+    /// a global reference to `wrapper`'s object
+    pub fn new<'a: 'b, 'b, L>(wrapper: &L) -> Result<Global<W>, Error>
+    where
+        L: JavaObjectWrapper<'a, 'b> + WrapperFor<'static, 'static, Wrapper = W>,
+    {
+        let jni_env = wrapper.jni_env();
+        Ok(Global {
+            object: jni_env.new_global_ref(wrapper.java_object())?,
+            java_vm: Arc::new(jni_env.get_java_vm()?),
+            wrapper: PhantomData,
+        })
+    }
+
+    /// An ordinary wrapper for the object, with a new local reference in `jni_env`,
+    /// which must belong to the current thread.
+    pub fn with_local<'a: 'b, 'b>(&self, jni_env: &'b JNIEnv<'a>) -> Result<W::Wrapper, Error>
+    where
+        W: WrapperFor<'a, 'b>,
+    {
+        let object = wrap_jobject(*self.object.as_obj());
+        let object = if object.is_null() {
+            object
+        } else {
+            new_local_ref(jni_env, object)?
+        };
+        Ok(W::Wrapper::wrap_jobject(
+            jni_env,
+            AutoLocal::new(jni_env, object),
+        ))
+    }
+
+    /// Attaches the current thread to the object's `JavaVM`, if it is not already, for `with_local()`.
+    /// A thread this attaches is detached again when the guard is dropped.
+    pub fn attach(&self) -> Result<AttachGuard<'_>, Error> {
+        self.java_vm.attach_current_thread()
+    }
+
+    pub fn java_vm(&self) -> &JavaVM {
+        &self.java_vm
+    }
+
+    pub fn as_global_ref(&self) -> &GlobalRef {
+        &self.object
+    }
+
+    /// a `Weak` for the same object
+    pub fn downgrade(&self) -> Result<Weak<W>, Error> {
+        let jni_env = self.attach()?;
+        Weak::from_object(&jni_env, wrap_jobject(*self.object.as_obj()))
+    }
+}
+
+impl<W> Clone for Global<W> {
+    /// another handle on the same global reference
+    fn clone(&self) -> Self {
+        Global {
+            object: self.object.clone(),
+            java_vm: self.java_vm.clone(),
+            wrapper: PhantomData,
+        }
+    }
+}
+
+/// A wrapper's object held by a weak global reference, which does not keep it from being collected.
+/// Like `Global`, it is `Send + Sync` and `W` is the wrapper type with any lifetimes.
+/// The weak reference is deleted when this is dropped.
+pub struct Weak<W> {
+    object: jweak,
+    java_vm: Arc<JavaVM>,
+    wrapper: PhantomData<fn() -> W>,
+}
+
+// a weak global reference is good on any thread, and nothing here mutates it
+unsafe impl<W> Send for Weak<W> {}
+unsafe impl<W> Sync for Weak<W> {}
+
+impl<W> Weak<W> {
+    /// a weak global reference to `wrapper`'s object
+    pub fn new<'a: 'b, 'b, L>(wrapper: &L) -> Result<Weak<W>, Error>
+    where
+        L: JavaObjectWrapper<'a, 'b> + WrapperFor<'static, 'static, Wrapper = W>,
+    {
+        Weak::from_object(wrapper.jni_env(), wrapper.java_object())
+    }
+
+    fn from_object(jni_env: &JNIEnv, object: JObject) -> Result<Weak<W>, Error> {
+        let raw = jni_env.get_native_interface();
+        let new_weak_global_ref = unsafe { (**raw).NewWeakGlobalRef }
+            .ok_or_else(|| method_not_found("NewWeakGlobalRef"))?;
+        let object = unsafe { new_weak_global_ref(raw, *object) };
+        Ok(Weak {
+            object,
+            java_vm: Arc::new(jni_env.get_java_vm()?),
+            wrapper: PhantomData,
+        })
+    }
+
+    /// An ordinary wrapper for the object, with a new local reference in `jni_env`,
+    /// or `None` once the object has been collected (or if it was a null to begin with).
+    pub fn upgrade<'a: 'b, 'b>(&self, jni_env: &'b JNIEnv<'a>) -> Result<Option<W::Wrapper>, Error>
+    where
+        W: WrapperFor<'a, 'b>,
+    {
+        // NewLocalRef of a weak reference to a collected object is a null
+        let object = new_local_ref(jni_env, wrap_jobject(self.object))?;
+        if object.is_null() {
+            return Ok(None);
+        }
+        Ok(Some(W::Wrapper::wrap_jobject(
+            jni_env,
+            AutoLocal::new(jni_env, object),
+        )))
+    }
+
+    /// a `Global` for the object, or `None` once it has been collected
+    pub fn upgrade_global(&self) -> Result<Option<Global<W>>, Error> {
+        let jni_env = self.attach()?;
+        let object = jni_env.new_global_ref(wrap_jobject(self.object))?;
+        if object.as_obj().is_null() {
+            return Ok(None);
+        }
+        Ok(Some(Global {
+            object,
+            java_vm: self.java_vm.clone(),
+            wrapper: PhantomData,
+        }))
+    }
+
+    /// Attaches the current thread to the object's `JavaVM`, if it is not already, for `upgrade()`.
+    pub fn attach(&self) -> Result<AttachGuard<'_>, Error> {
+        self.java_vm.attach_current_thread()
+    }
+}
+
+impl<W> Drop for Weak<W> {
+    fn drop(&mut self) {
+        if self.object.is_null() {
+            return;
+        }
+        match self.java_vm.attach_current_thread() {
+            Ok(jni_env) => {
+                let raw = jni_env.get_native_interface();
+                if let Some(delete_weak_global_ref) = unsafe { (**raw).DeleteWeakGlobalRef } {
+                    unsafe { delete_weak_global_ref(raw, self.object) };
+                }
+            }
+            Err(e) => debug!("error dropping weak global ref: {:#?}", e),
+        }
+    }
+}
+
+#[cfg(any(feature = "jni_0_18", feature = "jni_0_20"))]
+fn method_not_found(name: &'static str) -> Error {
+    Error::JNIEnvMethodNotFound(name)
+}
+
+#[cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+fn method_not_found(name: &'static str) -> Error {
+    Error::from_kind(jni::errors::ErrorKind::JNIEnvMethodNotFound(name))
+}
+
+/// `to_global()` and `to_weak()` for every wrapper that implements `WrapperFor`
+pub trait ToGlobal<'a: 'b, 'b>: JavaObjectWrapper<'a, 'b> + WrapperFor<'static, 'static> {
+    /// the object as a `Global`, which outlives this wrapper and its env
+    fn to_global(&self) -> Result<Global<<Self as WrapperFor<'static, 'static>>::Wrapper>, Error> {
+        Global::new(self)
+    }
+
+    /// the object as a `Weak`, which outlives this wrapper and its env but not the object
+    fn to_weak(&self) -> Result<Weak<<Self as WrapperFor<'static, 'static>>::Wrapper>, Error> {
+        Weak::new(self)
+    }
+}
+
+impl<'a: 'b, 'b, W> ToGlobal<'a, 'b> for W where
+    W: JavaObjectWrapper<'a, 'b> + WrapperFor<'static, 'static>
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Global, ToGlobal, Weak};
+    use crate::inheritance::JavaObjectWrapper;
+    use crate::jni;
+    use crate::object_methods::JavaObjectMethods;
+    use crate::test_jvm;
+    use crate::{jni_wrapper_cliche_impl, JavaConstructible};
+    use jni::errors::Error;
+    use jni::objects::AutoLocal;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::thread;
+
+    jni_wrapper_cliche_impl! { JavaString, "java/lang/String" }
+
+    fn check_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        check_send_sync::<Global<JavaString<'static, 'static>>>();
+        check_send_sync::<Weak<JavaString<'static, 'static>>>();
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn other_threads() -> Result<(), Error> {
+        let cache: Mutex<HashMap<&str, Global<JavaString<'static, 'static>>>> =
+            Mutex::new(HashMap::new());
+        {
+            let jni_env = test_jvm::java_vm().attach_current_thread()?;
+            let text = jni_env.new_string("made on the test thread")?;
+            let text = JavaString::wrap_jobject(&jni_env, AutoLocal::new(&jni_env, *text));
+            cache.lock().unwrap().insert("text", text.to_global()?);
+        }
+
+        let from_thread = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let cache = cache.lock().unwrap();
+                    let text = &cache["text"];
+                    let jni_env = text.attach()?;
+                    let text = text.with_local(&jni_env)?;
+                    text.java_to_string()
+                })
+                .join()
+                .unwrap()
+        })?;
+        assert_eq!("made on the test thread", from_thread);
+
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let text = cache.lock().unwrap()["text"].clone();
+        let null = JavaString::null(&jni_env).to_global()?;
+        assert!(null.with_local(&jni_env)?.java_object().is_null());
+        assert!(text
+            .with_local(&jni_env)?
+            .is_same_object(&text.with_local(&jni_env)?)?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn weak() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let text = jni_env.new_string("kept")?;
+        let text = JavaString::wrap_jobject(&jni_env, AutoLocal::new(&jni_env, *text));
+        let kept = text.to_weak()?;
+        let global = text.to_global()?;
+        let from_global = global.downgrade()?;
+        assert!(kept.upgrade(&jni_env)?.unwrap().is_same_object(&text)?);
+        assert!(from_global.upgrade_global()?.is_some());
+
+        let dropped = {
+            let text = jni_env.new_string("dropped")?;
+            let text = JavaString::wrap_jobject(&jni_env, AutoLocal::new(&jni_env, *text));
+            text.to_weak()?
+        };
+        drop(global);
+        drop(text);
+        // every reference but the weak ones is gone
+        let mut collected = false;
+        for _ in 0..100 {
+            jni_env.call_static_method("java/lang/System", "gc", "()V", &[])?;
+            if dropped.upgrade(&jni_env)?.is_none() && kept.upgrade(&jni_env)?.is_none() {
+                collected = true;
+                break;
+            }
+        }
+        assert!(collected);
+        assert!(from_global.upgrade_global()?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+//! Java's class hierarchy for wrapper structs: upcasts to superclasses and interfaces,
+//! checked downcasts, and inherited methods through `Deref`.
+//!
+//! `jni_extends!` declares the relationships:
+//! ```
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::{jni_extends, jni_wrapper_cliche_impl};
+//!
+//! jni_wrapper_cliche_impl! { Object, "java/lang/Object" }
+//! jni_wrapper_cliche_impl! { Component, "java/awt/Component" }
+//! jni_wrapper_cliche_impl! { Button, "java/awt/Button" }
+//! jni_wrapper_cliche_impl! { Accessible, "javax/accessibility/Accessible" }
+//!
+//! jni_extends! { Component extends Object }
+//! jni_extends! { Button extends Component, Object implements Accessible }
+//!
+//! fn takes_component(_: &Component) {}
+//!
+//! fn use_button(button: Button) {
+//!     takes_component(&button);
+//!     let _: &Accessible = button.as_ref();
+//!     let _: Object = jni_boilerplate_helper::inheritance::JavaObjectWrapper::upcast(button);
+//! }
+//! ```
+
+use crate::jni;
+use crate::{java_exception, raise_if_exception, JavaClassNameFor, JavaConstructible};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JObject};
+use jni::JNIEnv;
+
+/// The parts every wrapper struct has, for code that is generic over wrappers.
+/// Implemented by `jni_wrapper_cliche_impl!` and `#[derive(JavaWrapper)]`.
+pub trait JavaObjectWrapper<'a: 'b, 'b>: Sized {
+    fn jni_env(&self) -> &'b JNIEnv<'a>;
+
+    fn java_object(&self) -> JObject<'a>;
+
+    fn into_parts(self) -> (&'b JNIEnv<'a>, AutoLocal<'a, 'b>);
+
+    /// the same object as a wrapper for one of its superclasses or interfaces, without a new reference
+    fn upcast<S>(self) -> S
+    where
+        Self: JavaSubclassOf<S>,
+        S: JavaConstructible<'a, 'b>,
+    {
+        let (jni_env, java_this) = self.into_parts();
+        S::wrap_jobject(jni_env, java_this)
+    }
+
+    /// whether the object is an instance of `S`'s java class (a null is an instance of everything)
+    fn is_instance<S: JavaClassNameFor>(&self) -> Result<bool, Error> {
+        self.jni_env()
+            .is_instance_of(self.java_object(), S::java_class_name())
+    }
+
+    /// The same object as a wrapper for `S`, if it is an instance of `S`'s java class.
+    /// If not, nothing is thrown and the wrapper comes back as the `Err`.
+    /// So does it if the check itself fails (say, `S`'s class is missing), after reporting the exception
+    /// through the `ExceptionReporting` policy.
+    fn downcast<S>(self) -> Result<S, Self>
+    where
+        S: JavaConstructible<'a, 'b> + JavaClassNameFor,
+    {
+        match self.is_instance::<S>() {
+            Ok(true) => {
+                let (jni_env, java_this) = self.into_parts();
+                Ok(S::wrap_jobject(jni_env, java_this))
+            }
+            Ok(false) => Err(self),
+            Err(e) => {
+                report_failed_check(self.jni_env(), &e);
+                Err(self)
+            }
+        }
+    }
+}
+
+/// Clears and reports the exception a failed `IsInstanceOf` check left, or logs `e` if there is none.
+pub(crate) fn report_failed_check(jni_env: &JNIEnv, e: &Error) {
+    if raise_if_exception(jni_env).is_ok() {
+        log::debug!("unable to check the class of an object: {:?}", e);
+    }
+}
+
+/// Throws the `java.lang.ClassCastException` java would for casting `object` to `class_name`,
+/// and returns the error to report it with: `JavaException`, or whatever went wrong on the way.
+pub(crate) fn throw_class_cast_exception(
+    jni_env: &JNIEnv,
+    object: JObject,
+    class_name: &str,
+) -> Error {
+    let thrown = jni_env.get_object_class(object).and_then(|class| {
+        let actual: String = jni_env
+            .call_method(*class, "getName", "()Ljava/lang/String;", &[])?
+            .l()
+            .and_then(|name| jni_env.get_string(name.into()).map(String::from))?;
+        jni_env.throw_new(
+            "java/lang/ClassCastException",
+            format!(
+                "class {} cannot be cast to class {}",
+                actual,
+                class_name.replace('/', ".")
+            ),
+        )
+    });
+    match thrown {
+        Ok(()) => java_exception(),
+        Err(e) => e,
+    }
+}
+
+/// `Self`'s java class extends or implements `Super`'s.  Implemented by `jni_extends!`.
+pub trait JavaSubclassOf<Super> {}
+
+/// A wrapper that is nothing but a reference to a java object, so that a reference to one can
+/// stand in for a reference to another.  `jni_extends!` uses this for `AsRef` and `Deref`.
+///
+/// # Safety
+///
+/// Implement this only for `#[repr(C)]` structs whose fields are `java_this: AutoLocal<'a, 'b>` and then
+/// `jni_env: &'b JNIEnv<'a>`, followed by nothing but zero-sized fields.
+pub unsafe trait PlainJavaWrapper {}
+
+/// `sub` as a reference to its superclass or interface wrapper
+pub fn upcast_ref<Sub, Super>(sub: &Sub) -> &Super
+where
+    Sub: PlainJavaWrapper + JavaSubclassOf<Super>,
+    Super: PlainJavaWrapper,
+{
+    // both are a `#[repr(C)]` AutoLocal and &JNIEnv with the same lifetimes
+    unsafe { &*(sub as *const Sub as *const Super) }
+}
+
+/// Declares a wrapper's java superclass and interfaces.
+///
+/// <pre>jni_extends! { Sub extends Super[, Ancestor...] [implements Interface, ...] }
+/// jni_extends! { Sub implements Interface, ... }
+/// </pre>
+///
+/// The first class after `extends` is the direct superclass: `Sub` derefs to it, so its bindings
+/// (and through it, its own superclass's) can be called on a `Sub`.  Every named class and interface gets
+/// `AsRef` and `JavaObjectWrapper::upcast`; list further ancestors to get those too.
+/// The wrappers must be `PlainJavaWrapper`s with the usual two lifetime parameters;
+/// the superclass and interfaces may be paths.
+#[macro_export]
+macro_rules! jni_extends {
+    ($sub:ident extends $($super:ident)::+ $(, $($ancestor:ident)::+)* $(implements $($($interface:ident)::+),+)?) => {
+        impl<'a: 'b, 'b> std::ops::Deref for $sub<'a, 'b> {
+            type Target = $($super)::+<'a, 'b>;
+
+            fn deref(&self) -> &Self::Target {
+                $crate::inheritance::upcast_ref(self)
+            }
+        }
+
+        $crate::jni_extends! { @relation $sub, $($super)::+ }
+        $( $crate::jni_extends! { @relation $sub, $($ancestor)::+ } )*
+        $( $( $crate::jni_extends! { @relation $sub, $($interface)::+ } )+ )?
+    };
+    ($sub:ident implements $($($interface:ident)::+),+) => {
+        $( $crate::jni_extends! { @relation $sub, $($interface)::+ } )+
+    };
+    (@relation $sub:ident, $($super:ident)::+) => {
+        impl<'a, 'b> $crate::inheritance::JavaSubclassOf<$($super)::+<'a, 'b>> for $sub<'a, 'b> {}
+
+        impl<'a: 'b, 'b> AsRef<$($super)::+<'a, 'b>> for $sub<'a, 'b> {
+            fn as_ref(&self) -> &$($super)::+<'a, 'b> {
+                $crate::inheritance::upcast_ref(self)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::JavaObjectWrapper;
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::{jni_wrapper_cliche_impl, JavaConstructible};
+    use jni::errors::Error;
+    use jni::objects::{AutoLocal, JValue};
+
+    jni_wrapper_cliche_impl! { Object, "java/lang/Object" }
+    jni_wrapper_cliche_impl! { Number, "java/lang/Number" }
+    jni_wrapper_cliche_impl! { Integer, "java/lang/Integer" }
+    jni_wrapper_cliche_impl! { Comparable, "java/lang/Comparable" }
+    jni_wrapper_cliche_impl! { CharSequence, "java/lang/CharSequence" }
+
+    jni_extends! { Number extends Object }
+    jni_extends! { Integer extends Number, Object implements Comparable }
+
+    impl<'a: 'b, 'b> Object<'a, 'b> {
+        fn hash_code(&self) -> Result<i32, Error> {
+            self.jni_env
+                .call_method(self.java_this.as_obj(), "hashCode", "()I", &[])?
+                .i()
+        }
+    }
+
+    impl<'a: 'b, 'b> Number<'a, 'b> {
+        fn double_value(&self) -> Result<f64, Error> {
+            self.jni_env
+                .call_method(self.java_this.as_obj(), "doubleValue", "()D", &[])?
+                .d()
+        }
+    }
+
+    fn integer<'a: 'b, 'b>(
+        jni_env: &'b jni::JNIEnv<'a>,
+        value: i32,
+    ) -> Result<Integer<'a, 'b>, Error> {
+        let rval = jni_env.new_object("java/lang/Integer", "(I)V", &[JValue::Int(value)])?;
+        Ok(Integer::wrap_jobject(
+            jni_env,
+            AutoLocal::new(jni_env, rval),
+        ))
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn inherited_methods() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let seven = integer(&jni_env, 7)?;
+        // through Integer -> Number -> Object
+        assert_eq!(7.0, seven.double_value()?);
+        assert_eq!(7, seven.hash_code()?);
+
+        let as_number: &Number = seven.as_ref();
+        assert_eq!(7.0, as_number.double_value()?);
+        let _: &Comparable = seven.as_ref();
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn casts() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let object: Object = integer(&jni_env, 12)?.upcast();
+        assert!(object.is_instance::<Number>()?);
+        assert!(!object.is_instance::<CharSequence>()?);
+
+        let Ok(number) = object.downcast::<Number>() else {
+            panic!("an Integer is a Number");
+        };
+        assert_eq!(12.0, number.double_value()?);
+
+        // a failed cast throws nothing, and hands the wrapper back
+        let Err(number) = number.downcast::<CharSequence>() else {
+            panic!("an Integer is not a CharSequence");
+        };
+        assert!(!jni_env.exception_check()?);
+        assert_eq!(12.0, number.double_value()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,463 @@
+//! `JavaAny`, a value of whatever type an `Object` parameter or return value happens to hold.
+//!
+//! Its descriptor is `java.lang.Object`'s.  Primitives are boxed on the way to java and unboxed on the way back,
+//! strings come back as rust `String`s, and anything else is kept as a `JavaObject` to inspect or downcast:
+//!
+//! ```no_run
+//! # use jni_old as jni;
+//! use jni::errors::Error;
+//! use jni::JNIEnv;
+//! use jni_boilerplate_helper::java_any::JavaAny;
+//! use jni_boilerplate_helper::{ConvertJValueToRust, ConvertRustToJValue};
+//!
+//! fn describe(jni_env: &JNIEnv) -> Result<(), Error> {
+//!     // String.valueOf(Object) is handed a java.lang.Integer
+//!     let value = JavaAny::from(42);
+//!     let tmp = value.into_temporary(jni_env)?;
+//!     let text = jni_env.call_static_method(
+//!         "java/lang/String",
+//!         "valueOf",
+//!         "(Ljava/lang/Object;)Ljava/lang/String;",
+//!         &[JavaAny::temporary_into_jvalue(&tmp)],
+//!     )?;
+//!     let text = JavaAny::to_rust(jni_env, text)?;
+//!     assert_eq!(Some("42"), text.as_string());
+//!     assert_eq!(Some(String::from("java.lang.String")), text.class_name()?);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Slices of them are `Object[]`s, so `...JavaAny` binds varargs such as `String.format(String, Object...)`.
+
+use crate::erased::boxing;
+use crate::inheritance::{report_failed_check, JavaObjectWrapper};
+use crate::jni;
+use crate::local_frame::LeaveFrame;
+use crate::{
+    jni_wrapper_cliche_impl, unwrap_jobject, wrap_jobject, ConvertJValueToRust,
+    ConvertRustToJValue, JValueNonScalar, JavaClassNameFor, JavaConstructible, JavaSignatureFor,
+};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JClass, JObject, JValue};
+use jni::sys::jobject;
+use jni::JNIEnv;
+use std::fmt;
+
+jni_wrapper_cliche_impl! { JavaObject, "java/lang/Object" }
+
+/// A java value of any type, as an `Object` holds it
+pub enum JavaAny<'a: 'b, 'b> {
+    Null,
+    Boolean(bool),
+    Byte(i8),
+    Char(char),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    /// any other object
+    Object(JavaObject<'a, 'b>),
+}
+
+impl<'a: 'b, 'b> JavaAny<'a, 'b> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, JavaAny::Null)
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            JavaAny::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// The dotted name of the value's class, which for a primitive is its box's, or `None` for a null
+    pub fn class_name(&self) -> Result<Option<String>, Error> {
+        match self {
+            JavaAny::Null => Ok(None),
+            JavaAny::String(_) => Ok(Some(String::from("java.lang.String"))),
+            JavaAny::Object(object) => {
+                object_class_name(object.jni_env(), object.java_object()).map(Some)
+            }
+            primitive => Ok(primitive
+                .descriptor()
+                .and_then(boxing)
+                .map(|(class, _)| class.replace('/', "."))),
+        }
+    }
+
+    /// The value as a `java.lang.Object`, boxing a primitive and making a java string of a `String`
+    pub fn into_object(self, jni_env: &'b JNIEnv<'a>) -> Result<JavaObject<'a, 'b>, Error> {
+        match self {
+            JavaAny::Object(object) => Ok(object),
+            other => Ok(match other.new_object(jni_env)? {
+                Some(made) => JavaObject::wrap_jobject(jni_env, made),
+                None => JavaObject::null(jni_env),
+            }),
+        }
+    }
+
+    /// The value as a wrapper for `W`, if it is an instance of `W`'s class (a null is an instance of everything).
+    /// If not, nothing is thrown and the value comes back as the `Err`, as `JavaObjectWrapper::downcast()` does.
+    pub fn downcast<W>(self, jni_env: &'b JNIEnv<'a>) -> Result<W, Self>
+    where
+        W: JavaConstructible<'a, 'b> + JavaClassNameFor,
+    {
+        match self {
+            JavaAny::Object(object) => object.downcast().map_err(JavaAny::Object),
+            // a primitive or string is boxed to be checked, and handed back as it was if that fails
+            other => match other.new_object(jni_env) {
+                Ok(Some(made)) => JavaObject::wrap_jobject(jni_env, made)
+                    .downcast()
+                    .map_err(|_| other),
+                Ok(None) => Ok(W::wrap_jobject(
+                    jni_env,
+                    AutoLocal::new(jni_env, JObject::null()),
+                )),
+                Err(e) => {
+                    report_failed_check(jni_env, &e);
+                    Err(other)
+                }
+            },
+        }
+    }
+
+    /// the descriptor of the primitive a variant holds
+    fn descriptor(&self) -> Option<&'static str> {
+        Some(match self {
+            JavaAny::Boolean(_) => "Z",
+            JavaAny::Byte(_) => "B",
+            JavaAny::Char(_) => "C",
+            JavaAny::Short(_) => "S",
+            JavaAny::Int(_) => "I",
+            JavaAny::Long(_) => "J",
+            JavaAny::Float(_) => "F",
+            JavaAny::Double(_) => "D",
+            _ => return None,
+        })
+    }
+
+    /// a new box or java string for the variants that need one
+    fn new_object(&self, jni_env: &'b JNIEnv<'a>) -> Result<Option<AutoLocal<'a, 'b>>, Error> {
+        let made = match self {
+            JavaAny::Null | JavaAny::Object(_) => return Ok(None),
+            JavaAny::String(text) => JObject::from(jni_env.new_string(text)?),
+            JavaAny::Boolean(value) => box_value(jni_env, value)?,
+            JavaAny::Byte(value) => box_value(jni_env, value)?,
+            JavaAny::Char(value) => box_value(jni_env, value)?,
+            JavaAny::Short(value) => box_value(jni_env, value)?,
+            JavaAny::Int(value) => box_value(jni_env, value)?,
+            JavaAny::Long(value) => box_value(jni_env, value)?,
+            JavaAny::Float(value) => box_value(jni_env, value)?,
+            JavaAny::Double(value) => box_value(jni_env, value)?,
+        };
+        Ok(Some(AutoLocal::new(jni_env, made)))
+    }
+}
+
+/// `T.valueOf(value)` for the box class of the primitive `T`
+fn box_value<'a: 'b, 'b, T>(jni_env: &'b JNIEnv<'a>, value: &T) -> Result<JObject<'a>, Error>
+where
+    T: ConvertRustToJValue<'a, 'b> + JavaSignatureFor,
+{
+    let descriptor = T::signature_for();
+    let (class, _) = boxing(&descriptor).expect("a primitive");
+    let class_object = AutoLocal::new(jni_env, JObject::from(jni_env.find_class(class)?));
+    let tmp = value.into_temporary(jni_env)?;
+    jni_env
+        .call_static_method(
+            JClass::from(class_object.as_obj()),
+            "valueOf",
+            format!("({})L{};", descriptor, class),
+            &[T::temporary_into_jvalue(&tmp)],
+        )?
+        .l()
+}
+
+/// `object.getClass().getName()`
+fn object_class_name(jni_env: &JNIEnv, object: JObject) -> Result<String, Error> {
+    let class = jni_env
+        .call_method(object, "getClass", "()Ljava/lang/Class;", &[])?
+        .l()?;
+    let class = AutoLocal::new(jni_env, class);
+    let name = jni_env.call_method(class.as_obj(), "getName", "()Ljava/lang/String;", &[])?;
+    String::to_rust(jni_env, name)
+}
+
+impl fmt::Debug for JavaAny<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JavaAny::Null => f.write_str("Null"),
+            JavaAny::Boolean(value) => f.debug_tuple("Boolean").field(value).finish(),
+            JavaAny::Byte(value) => f.debug_tuple("Byte").field(value).finish(),
+            JavaAny::Char(value) => f.debug_tuple("Char").field(value).finish(),
+            JavaAny::Short(value) => f.debug_tuple("Short").field(value).finish(),
+            JavaAny::Int(value) => f.debug_tuple("Int").field(value).finish(),
+            JavaAny::Long(value) => f.debug_tuple("Long").field(value).finish(),
+            JavaAny::Float(value) => f.debug_tuple("Float").field(value).finish(),
+            JavaAny::Double(value) => f.debug_tuple("Double").field(value).finish(),
+            JavaAny::String(value) => f.debug_tuple("String").field(value).finish(),
+            JavaAny::Object(object) => f
+                .debug_tuple("Object")
+                .field(&unwrap_jobject(object.java_object()))
+                .finish(),
+        }
+    }
+}
+
+macro_rules! java_any_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl<'a: 'b, 'b> From<$ty> for JavaAny<'a, 'b> {
+                fn from(value: $ty) -> Self {
+                    JavaAny::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+java_any_from! { bool => Boolean, i8 => Byte, char => Char, i16 => Short, i32 => Int, i64 => Long }
+java_any_from! { f32 => Float, f64 => Double, String => String, &str => String }
+
+/// any wrapper, a null one becoming `JavaAny::Null`
+impl<'a: 'b, 'b, W> From<W> for JavaAny<'a, 'b>
+where
+    W: JavaObjectWrapper<'a, 'b>,
+{
+    fn from(wrapper: W) -> Self {
+        if wrapper.java_object().is_null() {
+            return JavaAny::Null;
+        }
+        let (jni_env, java_this) = wrapper.into_parts();
+        JavaAny::Object(JavaObject::wrap_jobject(jni_env, java_this))
+    }
+}
+
+/// Only `JavaAny::Object` holds a reference; the rest come out of a frame as they are.
+impl<'a: 'b, 'b> LeaveFrame<'a, 'b> for JavaAny<'a, 'b> {
+    type Rest = Option<JavaAny<'a, 'b>>;
+
+    fn leave_frame(self) -> (JObject<'a>, Self::Rest) {
+        match self {
+            JavaAny::Object(object) => (object.leave_frame().0, None),
+            other => (JObject::null(), Some(other)),
+        }
+    }
+
+    fn enter_frame(jni_env: &'b JNIEnv<'a>, object: JObject<'a>, rest: Self::Rest) -> Self {
+        rest.unwrap_or_else(|| JavaAny::Object(JavaObject::enter_frame(jni_env, object, ())))
+    }
+}
+
+impl JavaSignatureFor for JavaAny<'_, '_> {
+    fn signature_for() -> String {
+        String::from("Ljava/lang/Object;")
+    }
+}
+
+impl JavaSignatureFor for &JavaAny<'_, '_> {
+    fn signature_for() -> String {
+        String::from("Ljava/lang/Object;")
+    }
+}
+
+impl JavaClassNameFor for JavaAny<'_, '_> {
+    fn java_class_name() -> &'static str {
+        "java/lang/Object"
+    }
+}
+
+impl JValueNonScalar for JavaAny<'_, '_> {}
+
+impl<'a: 'b, 'b> ConvertRustToJValue<'a, 'b> for JavaAny<'a, 'b> {
+    /// the object passed, and the box or string made for it, if any
+    type T = (jobject, Option<AutoLocal<'a, 'b>>);
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        if let JavaAny::Object(object) = self {
+            return Ok((unwrap_jobject(object.java_object()), None));
+        }
+        let made = self.new_object(je)?;
+        let object = match &made {
+            Some(made) => *made.as_obj(),
+            None => std::ptr::null_mut(),
+        };
+        Ok((object, made))
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        JValue::from(wrap_jobject(tmp.0))
+    }
+}
+
+impl<'a: 'b, 'b> ConvertRustToJValue<'a, 'b> for &JavaAny<'a, 'b> {
+    type T = <JavaAny<'a, 'b> as ConvertRustToJValue<'a, 'b>>::T;
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        (*self).into_temporary(je)
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        JavaAny::temporary_into_jvalue(tmp)
+    }
+}
+
+/// Unboxes the boxes of primitives and copies strings; a null is `JavaAny::Null`.
+impl<'a: 'b, 'b> ConvertJValueToRust<'a, 'b> for JavaAny<'a, 'b> {
+    fn to_rust(je: &'b JNIEnv<'a>, val: JValue<'a>) -> Result<Self, Error> {
+        let object = val.l()?;
+        if object.is_null() {
+            return Ok(JavaAny::Null);
+        }
+        let object = AutoLocal::new(je, object);
+        let class_name = object_class_name(je, object.as_obj())?;
+        if class_name == "java.lang.String" {
+            let text = JValue::from(object.forget());
+            return Ok(JavaAny::String(String::to_rust(je, text)?));
+        }
+
+        for descriptor in &["Z", "B", "C", "S", "I", "J", "F", "D"] {
+            let (class, unbox) = boxing(descriptor).expect("a primitive");
+            if class_name != class.replace('/', ".") {
+                continue;
+            }
+            let value = je.call_method(object.as_obj(), unbox, format!("(){}", descriptor), &[])?;
+            return Ok(match *descriptor {
+                "Z" => JavaAny::Boolean(bool::to_rust(je, value)?),
+                "B" => JavaAny::Byte(i8::to_rust(je, value)?),
+                "C" => JavaAny::Char(char::to_rust(je, value)?),
+                "S" => JavaAny::Short(i16::to_rust(je, value)?),
+                "I" => JavaAny::Int(i32::to_rust(je, value)?),
+                "J" => JavaAny::Long(i64::to_rust(je, value)?),
+                "F" => JavaAny::Float(f32::to_rust(je, value)?),
+                _ => JavaAny::Double(f64::to_rust(je, value)?),
+            });
+        }
+
+        Ok(JavaAny::Object(JavaObject::wrap_jobject(je, object)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::JavaAny;
+    use crate::inheritance::JavaObjectWrapper;
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::{jni_wrapper_cliche_impl, ConvertJValueToRust, ConvertRustToJValue};
+    use jni::errors::Error;
+    use jni::objects::{AutoLocal, JValue};
+    use jni::JNIEnv;
+
+    jni_wrapper_cliche_impl! { StringBuilder, "java/lang/StringBuilder" }
+    jni_wrapper_cliche_impl! { Number, "java/lang/Number" }
+
+    /// through a java.util.ArrayList and back
+    fn round_trip<'a: 'b, 'b>(
+        jni_env: &'b JNIEnv<'a>,
+        value: JavaAny<'a, 'b>,
+    ) -> Result<JavaAny<'a, 'b>, Error> {
+        let list = jni_env.new_object("java/util/ArrayList", "()V", &[])?;
+        let list = AutoLocal::new(jni_env, list);
+        let tmp = value.into_temporary(jni_env)?;
+        jni_env.call_method(
+            list.as_obj(),
+            "add",
+            "(Ljava/lang/Object;)Z",
+            &[JavaAny::temporary_into_jvalue(&tmp)],
+        )?;
+        let got = jni_env.call_method(
+            list.as_obj(),
+            "get",
+            "(I)Ljava/lang/Object;",
+            &[JValue::Int(0)],
+        )?;
+        JavaAny::to_rust(jni_env, got)
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn boxing_and_unboxing() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let jni_env: &JNIEnv = &jni_env;
+
+        let values = vec![
+            (JavaAny::from(true), "java.lang.Boolean"),
+            (JavaAny::from(-8i8), "java.lang.Byte"),
+            (JavaAny::from('é'), "java.lang.Character"),
+            (JavaAny::from(-16i16), "java.lang.Short"),
+            (JavaAny::from(32), "java.lang.Integer"),
+            (JavaAny::from(1i64 << 40), "java.lang.Long"),
+            (JavaAny::from(0.5f32), "java.lang.Float"),
+            (JavaAny::from(0.25), "java.lang.Double"),
+            (JavaAny::from("text"), "java.lang.String"),
+        ];
+        for (value, class_name) in values {
+            let expected = format!("{:?}", value);
+            assert_eq!(Some(String::from(class_name)), value.class_name()?);
+            let back = round_trip(jni_env, value)?;
+            assert_eq!(expected, format!("{:?}", back));
+            assert_eq!(Some(String::from(class_name)), back.class_name()?);
+        }
+
+        let null = round_trip(jni_env, JavaAny::Null)?;
+        assert!(null.is_null());
+        assert_eq!(None, null.class_name()?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn objects() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let jni_env: &JNIEnv = &jni_env;
+
+        let builder = jni_env.new_object("java/lang/StringBuilder", "()V", &[])?;
+        let builder = StringBuilder {
+            java_this: AutoLocal::new(jni_env, builder),
+            jni_env,
+        };
+        let value = round_trip(jni_env, JavaAny::from(builder))?;
+        assert!(matches!(value, JavaAny::Object(_)));
+        assert_eq!(None, value.as_string());
+        assert_eq!(
+            Some(String::from("java.lang.StringBuilder")),
+            value.class_name()?
+        );
+
+        let Ok(builder) = value.downcast::<StringBuilder>(jni_env) else {
+            panic!("not a StringBuilder");
+        };
+        jni_env.call_method(
+            builder.java_object(),
+            "append",
+            "(I)Ljava/lang/StringBuilder;",
+            &[JValue::Int(7)],
+        )?;
+        let text = jni_env.call_method(
+            builder.java_object(),
+            "toString",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        assert_eq!("7", String::to_rust(jni_env, text)?);
+        assert!(JavaAny::from(StringBuilder::null(jni_env)).is_null());
+
+        // a primitive is boxed to be cast
+        let Ok(number) = JavaAny::from(12).downcast::<Number>(jni_env) else {
+            panic!("an Integer is a Number");
+        };
+        let value = jni_env.call_method(number.java_object(), "longValue", "()J", &[])?;
+        assert_eq!(12, value.j()?);
+        // and handed back as it was if the cast fails, with nothing thrown
+        let Err(twelve) = JavaAny::from("twelve").downcast::<Number>(jni_env) else {
+            panic!("a String is not a Number");
+        };
+        assert!(!jni_env.exception_check()?);
+        assert_eq!(Some("twelve"), twelve.as_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,574 @@
+//! Java interfaces implemented by rust closures.
+//!
+//! `JavaCallback::new()` makes a `java.lang.reflect.Proxy` for an interface such as `java/lang/Runnable`,
+//! `java/util/Comparator` or `java/util/function/Function`.  Its `InvocationHandler` is
+//! `com.purplefrog.jni_boilerplate.RustInvocationHandler`, which this module defines from the class bytes
+//! embedded in the crate (see the `java` directory) and whose native methods call the closure.
+//! The closure lives until java collects the handler.  A `java.lang.ref.Cleaner` frees it then, so this works with
+//! finalization disabled; only java 8, which has no `Cleaner`, relies on `finalize()`.
+//!
+//! `equals`, `hashCode` and `toString` are answered in java (by identity); every other method of the interface
+//! calls the closure.  Primitive arguments arrive boxed and are unboxed before `ConvertJValueToRust` sees them,
+//! and a primitive result is boxed again, as `Proxy` expects.
+
+use crate::java_runtime_wrappers::jni_workaround_jvalue;
+use crate::native_method::{catch_panic, new_local_ref, throw_for_native_error};
+use crate::{
+    java_exception, jni, unwrap_jobject, wrap_jobject, ConvertJValueToRust, ConvertRustToJValue,
+    JValueNonScalar, JavaConstructible,
+};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, GlobalRef, JClass, JObject, JValue};
+use jni::sys::{jlong, jobject, jobjectArray, jstring};
+use jni::{JNIEnv, NativeMethod};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+/// What the handler calls: the `&JNIEnv`, the name of the interface method, and its (boxed) arguments.
+/// It returns a new local reference, or null.
+pub type RawCallback =
+    dyn for<'a> Fn(&'a JNIEnv<'a>, &str, &[JObject<'a>]) -> Result<jobject, Error> + Send + Sync;
+
+/// A java object implementing an interface with a rust closure.
+pub struct JavaCallback<'a: 'b, 'b> {
+    java_this: AutoLocal<'a, 'b>,
+    jni_env: &'b JNIEnv<'a>,
+}
+
+impl<'a: 'b, 'b> JavaCallback<'a, 'b> {
+    /// Implements `interface` (e.g. `java/util/function/Function`) with `callback`,
+    /// whose arguments after the `&JNIEnv` are the arguments of the interface method, converted with `ConvertJValueToRust`.
+    /// It may return a plain value or a `Result<_, jni::errors::Error>`; an `Err` becomes a `java.lang.RuntimeException`
+    /// unless a java exception is already pending.
+    ///
+    /// The closure is called for every method of the interface except `equals`, `hashCode` and `toString`,
+    /// so an interface with several methods of different arity needs `new_raw()` instead.
+    ///
+    /// ```
+    /// # use jni_old as jni;
+    /// # use jni::JNIEnv;
+    /// # use jni_boilerplate_helper::java_callback::JavaCallback;
+    /// # fn example(jni_env: &JNIEnv) -> Result<(), jni::errors::Error> {
+    /// let shout = JavaCallback::new(jni_env, "java/util/function/Function", |_: &JNIEnv, text: String| {
+    ///     text.to_uppercase()
+    /// })?;
+    /// let by_length = JavaCallback::new(jni_env, "java/util/Comparator", |_: &JNIEnv, a: String, b: String| {
+    ///     a.len() as i32 - b.len() as i32
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<Args, F>(
+        jni_env: &'b JNIEnv<'a>,
+        interface: &str,
+        callback: F,
+    ) -> Result<JavaCallback<'a, 'b>, Error>
+    where
+        F: CallbackFn<Args>,
+    {
+        Self::new_raw(jni_env, interface, move |je, _method_name, args| {
+            callback.call(je, args)
+        })
+    }
+
+    /// Like `new()`, but the closure sees the method name and the raw arguments, and returns the raw result.
+    pub fn new_raw<F>(
+        jni_env: &'b JNIEnv<'a>,
+        interface: &str,
+        callback: F,
+    ) -> Result<JavaCallback<'a, 'b>, Error>
+    where
+        F: for<'c> Fn(&'c JNIEnv<'c>, &str, &[JObject<'c>]) -> Result<jobject, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let interface = AutoLocal::new(jni_env, *jni_env.find_class(interface)?);
+        let handler = invocation_handler(jni_env, callback)?;
+
+        let loader = jni_env
+            .call_method(
+                interface.as_obj(),
+                "getClassLoader",
+                "()Ljava/lang/ClassLoader;",
+                &[],
+            )?
+            .l()?;
+        let loader = AutoLocal::new(jni_env, loader);
+        let interfaces = AutoLocal::new(
+            jni_env,
+            wrap_jobject(jni_env.new_object_array(1, "java/lang/Class", interface.as_obj())?),
+        );
+        let proxy = jni_env
+            .call_static_method(
+                "java/lang/reflect/Proxy",
+                "newProxyInstance",
+                "(Ljava/lang/ClassLoader;[Ljava/lang/Class;Ljava/lang/reflect/InvocationHandler;)Ljava/lang/Object;",
+                &[
+                    JValue::from(loader.as_obj()),
+                    JValue::from(interfaces.as_obj()),
+                    JValue::from(handler.as_obj()),
+                ],
+            )?
+            .l()?;
+
+        Ok(JavaCallback {
+            java_this: AutoLocal::new(jni_env, proxy),
+            jni_env,
+        })
+    }
+
+    pub fn as_obj(&self) -> JObject<'a> {
+        self.java_this.as_obj()
+    }
+
+    /// Hands the proxy to a wrapper type for the interface, such as one made with `jni_wrapper_cliche_impl!`.
+    pub fn into_wrapper<W: JavaConstructible<'a, 'b>>(self) -> W {
+        W::wrap_jobject(self.jni_env, self.java_this)
+    }
+}
+
+//
+
+/// A `RustInvocationHandler` that calls `callback`.  It owns the closure from now on.
+pub(crate) fn invocation_handler<'a: 'b, 'b, F>(
+    je: &'b JNIEnv<'a>,
+    callback: F,
+) -> Result<AutoLocal<'a, 'b>, Error>
+where
+    F: for<'c> Fn(&'c JNIEnv<'c>, &str, &[JObject<'c>]) -> Result<jobject, Error>
+        + Send
+        + Sync
+        + 'static,
+{
+    let handler_class = HANDLER_CLASS.class(je, register_handler_natives)?;
+
+    let boxed: Box<Box<RawCallback>> = Box::new(Box::new(callback));
+    let handle = Box::into_raw(boxed) as jlong;
+    match je.new_object(
+        JClass::from(handler_class.as_obj()),
+        "(J)V",
+        &[JValue::Long(handle)],
+    ) {
+        Ok(handler) => Ok(AutoLocal::new(je, handler)),
+        Err(e) => {
+            // java never saw the handle, so the closure is still ours to free
+            release(handle);
+            Err(e)
+        }
+    }
+}
+
+/// A java class whose bytes are embedded in this crate.
+/// It is defined in the system class loader the first time it is needed.
+pub(crate) struct EmbeddedClass {
+    name: &'static str,
+    bytes: &'static [u8],
+    class: Mutex<Option<GlobalRef>>,
+}
+
+impl EmbeddedClass {
+    pub(crate) const fn new(name: &'static str, bytes: &'static [u8]) -> EmbeddedClass {
+        EmbeddedClass {
+            name,
+            bytes,
+            class: Mutex::new(None),
+        }
+    }
+
+    /// The class, defined (and handed to `prepare`, e.g. to register its natives) on first use.
+    pub(crate) fn class<P>(&self, je: &JNIEnv, prepare: P) -> Result<GlobalRef, Error>
+    where
+        P: FnOnce(&JNIEnv, JClass) -> Result<(), Error>,
+    {
+        let mut guard = match self.class.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(class) = &*guard {
+            return Ok(class.clone());
+        }
+
+        let class = AutoLocal::new(je, *self.define(je)?);
+        prepare(je, JClass::from(class.as_obj()))?;
+        let class = je.new_global_ref(class.as_obj())?;
+        *guard = Some(class.clone());
+        Ok(class)
+    }
+
+    /// If another copy of this crate in the process got there first, we use that definition.
+    fn define<'a>(&self, je: &JNIEnv<'a>) -> Result<JClass<'a>, Error> {
+        let loader = je
+            .call_static_method(
+                "java/lang/ClassLoader",
+                "getSystemClassLoader",
+                "()Ljava/lang/ClassLoader;",
+                &[],
+            )?
+            .l()?;
+        let loader = AutoLocal::new(je, loader);
+        match je.define_class(self.name, loader.as_obj(), self.bytes) {
+            Ok(class) => Ok(class),
+            Err(defining) => {
+                if !je.is_instance_of(je.exception_occurred()?, "java/lang/LinkageError")? {
+                    return Err(defining);
+                }
+                je.exception_clear()?;
+                let binary_name = je.new_string(self.name.replace('/', "."))?;
+                let class = je
+                    .call_method(
+                        loader.as_obj(),
+                        "loadClass",
+                        "(Ljava/lang/String;)Ljava/lang/Class;",
+                        &[JValue::from(JObject::from(binary_name))],
+                    )?
+                    .l()?;
+                Ok(JClass::from(class))
+            }
+        }
+    }
+}
+
+static HANDLER_CLASS: EmbeddedClass = EmbeddedClass::new(
+    "com/purplefrog/jni_boilerplate/RustInvocationHandler",
+    include_bytes!("../java/com/purplefrog/jni_boilerplate/RustInvocationHandler.class"),
+);
+
+fn register_handler_natives(je: &JNIEnv, class: JClass) -> Result<(), Error> {
+    je.register_native_methods(
+        class,
+        &[
+            NativeMethod {
+                name: "invokeNative".into(),
+                sig: "(JLjava/lang/String;[Ljava/lang/Object;)Ljava/lang/Object;".into(),
+                fn_ptr: invoke_native as *mut c_void,
+            },
+            NativeMethod {
+                name: "release".into(),
+                sig: "(J)V".into(),
+                fn_ptr: release_native as *mut c_void,
+            },
+        ],
+    )
+}
+
+extern "system" fn invoke_native(
+    jni_env: JNIEnv,
+    _java_this: jobject,
+    handle: jlong,
+    method_name: jstring,
+    args: jobjectArray,
+) -> jobject {
+    let je = &jni_env;
+    let outcome = catch_panic(je, || -> Result<jobject, Error> {
+        let callback = unsafe { &*(handle as *const Box<RawCallback>) };
+        let method_name = String::to_rust(je, jni_workaround_jvalue(method_name))?;
+        let count = je.get_array_length(args)?;
+        let args = (0..count)
+            .map(|i| je.get_object_array_element(args, i))
+            .collect::<Result<Vec<_>, _>>()?;
+        callback(je, &method_name, &args)
+    });
+    match outcome {
+        Some(Ok(rval)) => rval,
+        Some(Err(e)) => {
+            throw_for_native_error(je, &e);
+            std::ptr::null_mut()
+        }
+        None => std::ptr::null_mut(),
+    }
+}
+
+extern "system" fn release_native(_jni_env: JNIEnv, _java_class: jobject, handle: jlong) {
+    // nobody is left to catch a panic from the closure's destructor, so it is only logged
+    let outcome = std::panic::catch_unwind(|| release(handle));
+    if outcome.is_err() {
+        log::error!("dropping a JavaCallback closure panicked");
+    }
+}
+
+fn release(handle: jlong) {
+    drop(unsafe { Box::from_raw(handle as *mut Box<RawCallback>) });
+}
+
+//
+
+/// An argument of a `JavaCallback` closure.
+/// Primitive types are unboxed from their `java.lang` wrapper first; everything else goes straight to `ConvertJValueToRust`.
+pub trait CallbackArgument: Sized {
+    fn from_java<'a>(je: &'a JNIEnv<'a>, obj: JObject<'a>) -> Result<Self, Error>;
+}
+
+macro_rules! impl_callback_argument_unboxed {
+    ( $( $t:ty, $unbox:literal, $sig:literal );* ) => {
+        $(
+        impl CallbackArgument for $t {
+            fn from_java<'a>(je: &'a JNIEnv<'a>, obj: JObject<'a>) -> Result<Self, Error> {
+                let val = je.call_method(obj, $unbox, $sig, &[])?;
+                je.delete_local_ref(obj)?;
+                <$t as ConvertJValueToRust>::to_rust(je, val)
+            }
+        }
+        )*
+    };
+}
+
+impl_callback_argument_unboxed! {
+    bool, "booleanValue", "()Z";
+    char, "charValue", "()C";
+    i8, "byteValue", "()B";
+    i16, "shortValue", "()S";
+    i32, "intValue", "()I";
+    i64, "longValue", "()J";
+    f32, "floatValue", "()F";
+    f64, "doubleValue", "()D"
+}
+
+impl<T> CallbackArgument for T
+where
+    T: JValueNonScalar + for<'a> ConvertJValueToRust<'a, 'a>,
+{
+    fn from_java<'a>(je: &'a JNIEnv<'a>, obj: JObject<'a>) -> Result<Self, Error> {
+        T::to_rust(je, JValue::Object(obj))
+    }
+}
+
+/// A rust value java can hold as an `Object`.
+/// Primitive types are boxed in their `java.lang` wrapper; everything else goes through `ConvertRustToJValue`.
+pub trait ToJavaObject {
+    /// a new local reference, or null
+    fn to_java_object<'a>(&self, je: &'a JNIEnv<'a>) -> Result<jobject, Error>;
+}
+
+macro_rules! impl_to_java_object_boxed {
+    ( $( $t:ty, $class:literal, $sig:literal );* ) => {
+        $(
+        impl ToJavaObject for $t {
+            fn to_java_object<'a>(&self, je: &'a JNIEnv<'a>) -> Result<jobject, Error> {
+                let tmp = <$t as ConvertRustToJValue>::into_temporary(self, je)?;
+                let boxed = je
+                    .call_static_method($class, "valueOf", $sig, &[<$t as ConvertRustToJValue>::temporary_into_jvalue(&tmp)])?
+                    .l()?;
+                Ok(unwrap_jobject(boxed))
+            }
+        }
+        )*
+    };
+}
+
+impl_to_java_object_boxed! {
+    bool, "java/lang/Boolean", "(Z)Ljava/lang/Boolean;";
+    char, "java/lang/Character", "(C)Ljava/lang/Character;";
+    i8, "java/lang/Byte", "(B)Ljava/lang/Byte;";
+    i16, "java/lang/Short", "(S)Ljava/lang/Short;";
+    i32, "java/lang/Integer", "(I)Ljava/lang/Integer;";
+    i64, "java/lang/Long", "(J)Ljava/lang/Long;";
+    f32, "java/lang/Float", "(F)Ljava/lang/Float;";
+    f64, "java/lang/Double", "(D)Ljava/lang/Double;"
+}
+
+impl<T> ToJavaObject for T
+where
+    T: JValueNonScalar + for<'a> ConvertRustToJValue<'a, 'a>,
+{
+    fn to_java_object<'a>(&self, je: &'a JNIEnv<'a>) -> Result<jobject, Error> {
+        let tmp = self.into_temporary(je)?;
+        let obj = T::temporary_into_jvalue(&tmp).l()?;
+        if obj.is_null() {
+            Ok(unwrap_jobject(obj))
+        } else {
+            // the temporary may delete its own reference when it is dropped
+            Ok(unwrap_jobject(new_local_ref(je, obj)?))
+        }
+    }
+}
+
+/// The result of a `JavaCallback` closure: `()`, anything that is `ToJavaObject`, or a `Result` of either.
+pub trait CallbackReturn {
+    /// a new local reference, or null
+    fn into_java<'a>(self, je: &'a JNIEnv<'a>) -> Result<jobject, Error>;
+}
+
+impl CallbackReturn for () {
+    fn into_java<'a>(self, _je: &'a JNIEnv<'a>) -> Result<jobject, Error> {
+        Ok(std::ptr::null_mut())
+    }
+}
+
+impl<T: ToJavaObject> CallbackReturn for T {
+    fn into_java<'a>(self, je: &'a JNIEnv<'a>) -> Result<jobject, Error> {
+        self.to_java_object(je)
+    }
+}
+
+impl<R: CallbackReturn> CallbackReturn for Result<R, Error> {
+    fn into_java<'a>(self, je: &'a JNIEnv<'a>) -> Result<jobject, Error> {
+        self?.into_java(je)
+    }
+}
+
+/// A closure taking the `&JNIEnv` and then the arguments of the interface method.
+/// `Args` is the tuple of argument types, which only serves to tell the implementations for each arity apart.
+pub trait CallbackFn<Args>: Send + Sync + 'static {
+    fn call<'a>(&self, je: &'a JNIEnv<'a>, args: &[JObject<'a>]) -> Result<jobject, Error>;
+}
+
+/// Java called the interface method with a different number of arguments than the closure takes.
+fn wrong_argument_count(je: &JNIEnv, expected: usize, actual: usize) -> Error {
+    let message = format!(
+        "JavaCallback closure takes {} arguments, but was called with {}",
+        expected, actual
+    );
+    if let Err(e) = je.throw_new("java/lang/IllegalArgumentException", message) {
+        return e;
+    }
+    java_exception()
+}
+
+macro_rules! impl_callback_fn {
+    ( $count:literal; $( $arg:ident, $index:literal ),* ) => {
+        impl<F, R, $( $arg ),*> CallbackFn<( $( $arg, )* )> for F
+        where
+            F: Fn(&JNIEnv, $( $arg ),*) -> R + Send + Sync + 'static,
+            R: CallbackReturn,
+            $( $arg: CallbackArgument, )*
+        {
+            fn call<'a>(&self, je: &'a JNIEnv<'a>, args: &[JObject<'a>]) -> Result<jobject, Error> {
+                if args.len() != $count {
+                    return Err(wrong_argument_count(je, $count, args.len()));
+                }
+                self(je, $( $arg::from_java(je, args[$index])? ),*).into_java(je)
+            }
+        }
+    };
+}
+
+impl_callback_fn! { 0; }
+impl_callback_fn! { 1; A, 0 }
+impl_callback_fn! { 2; A, 0, B, 1 }
+impl_callback_fn! { 3; A, 0, B, 1, C, 2 }
+impl_callback_fn! { 4; A, 0, B, 1, C, 2, D, 3 }
+
+#[cfg(test)]
+mod test {
+    use super::JavaCallback;
+    use crate::{jni, test_jvm, ConvertJValueToRust};
+    use jni::errors::Error;
+    use jni::objects::JValue;
+    use jni::JNIEnv;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn function() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let shout = JavaCallback::new(
+            &jni_env,
+            "java/util/function/Function",
+            |_: &JNIEnv, text: String| text.to_uppercase(),
+        )?;
+        let text = jni_env.new_string("hello")?;
+        let rval = jni_env.call_method(
+            shout.as_obj(),
+            "apply",
+            "(Ljava/lang/Object;)Ljava/lang/Object;",
+            &[JValue::from(text)],
+        )?;
+        assert_eq!("HELLO", String::to_rust(&jni_env, rval)?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn comparator_and_object_methods() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let by_length = JavaCallback::new(
+            &jni_env,
+            "java/util/Comparator",
+            |_: &JNIEnv, a: String, b: String| a.len() as i32 - b.len() as i32,
+        )?;
+        let a = jni_env.new_string("apple")?;
+        let b = jni_env.new_string("fig")?;
+        let rval = jni_env.call_method(
+            by_length.as_obj(),
+            "compare",
+            "(Ljava/lang/Object;Ljava/lang/Object;)I",
+            &[JValue::from(a), JValue::from(b)],
+        )?;
+        assert_eq!(2, rval.i()?);
+
+        // answered in java, without calling the closure
+        let equal = jni_env.call_method(
+            by_length.as_obj(),
+            "equals",
+            "(Ljava/lang/Object;)Z",
+            &[JValue::from(by_length.as_obj())],
+        )?;
+        assert!(equal.z()?);
+        let text =
+            jni_env.call_method(by_length.as_obj(), "toString", "()Ljava/lang/String;", &[])?;
+        assert!(String::to_rust(&jni_env, text)?.starts_with("RustCallback@"));
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn runnable_and_errors() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let count = Arc::new(AtomicUsize::new(0));
+        let runnable = {
+            let count = count.clone();
+            JavaCallback::new(&jni_env, "java/lang/Runnable", move |_: &JNIEnv| {
+                count.fetch_add(1, Ordering::SeqCst);
+            })?
+        };
+        jni_env.call_method(runnable.as_obj(), "run", "()V", &[])?;
+        jni_env.call_method(runnable.as_obj(), "run", "()V", &[])?;
+        assert_eq!(2, count.load(Ordering::SeqCst));
+
+        let failing = JavaCallback::new(
+            &jni_env,
+            "java/util/concurrent/Callable",
+            |_: &JNIEnv| -> Result<i32, Error> { Err(crate::java_exception()) },
+        )?;
+        assert!(jni_env
+            .call_method(failing.as_obj(), "call", "()Ljava/lang/Object;", &[])
+            .is_err());
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/lang/RuntimeException")?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn closure_is_dropped_after_collection() -> Result<(), Error> {
+        struct Tracker(Arc<AtomicUsize>);
+        impl Drop for Tracker {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let dropped = Arc::new(AtomicUsize::new(0));
+        {
+            let tracker = Tracker(dropped.clone());
+            let _runnable =
+                JavaCallback::new(&jni_env, "java/lang/Runnable", move |_: &JNIEnv| {
+                    let _ = &tracker;
+                })?;
+        }
+        for _ in 0..50 {
+            if dropped.load(Ordering::SeqCst) > 0 {
+                break;
+            }
+            jni_env.call_static_method("java/lang/System", "gc", "()V", &[])?;
+            // the Cleaner frees the closure on its own thread
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(1, dropped.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
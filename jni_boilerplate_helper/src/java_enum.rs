@@ -0,0 +1,189 @@
+//! Fieldless rust enums standing for java enums, as `#[derive(JavaEnum)]` makes them.
+//!
+//! A variant goes to java as its enum constant, read from the class's static field once and kept as a global reference.
+//! A constant comes back through its `name()`, so reordering the java enum does not mix the variants up;
+//! one the rust enum does not know is an `IllegalArgumentException`, as `Enum.valueOf()` would throw.
+//!
+//! `#[derive(JavaEnum)]` in `jni_boilerplate` implements `JavaEnum` and the conversions on top of it.
+
+use crate::jni;
+use crate::{java_exception, wrap_jobject, JavaClassNameFor};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, GlobalRef, JClass, JObject, JValue};
+use jni::JNIEnv;
+use std::sync::OnceLock;
+
+/// A fieldless rust enum for a java enum.  Implemented by `#[derive(JavaEnum)]`.
+pub trait JavaEnum: Copy + JavaClassNameFor + 'static {
+    /// the names of the java constants, in the order of the rust variants
+    const JAVA_NAMES: &'static [&'static str];
+
+    /// the variant's position in `JAVA_NAMES`
+    fn index(self) -> usize;
+
+    fn from_index(index: usize) -> Self;
+
+    /// where the constants are kept once they have been looked up
+    fn constants() -> &'static EnumConstants;
+
+    /// the name of the java constant
+    fn java_name(self) -> &'static str {
+        Self::JAVA_NAMES[self.index()]
+    }
+}
+
+/// Global references to an enum's constants, in the order of its `JAVA_NAMES`
+pub struct EnumConstants {
+    constants: OnceLock<Vec<GlobalRef>>,
+}
+
+impl EnumConstants {
+    pub const fn new() -> EnumConstants {
+        EnumConstants {
+            constants: OnceLock::new(),
+        }
+    }
+
+    fn get<E: JavaEnum>(&self, jni_env: &JNIEnv) -> Result<&[GlobalRef], Error> {
+        if let Some(constants) = self.constants.get() {
+            return Ok(constants);
+        }
+        let class_name = E::java_class_name();
+        let class = AutoLocal::new(jni_env, JObject::from(jni_env.find_class(class_name)?));
+        let signature = format!("L{};", class_name);
+        let constants = E::JAVA_NAMES
+            .iter()
+            .map(|name| {
+                let constant = jni_env
+                    .get_static_field(JClass::from(class.as_obj()), *name, &signature)?
+                    .l()?;
+                let constant = AutoLocal::new(jni_env, constant);
+                jni_env.new_global_ref(constant.as_obj())
+            })
+            .collect::<Result<Vec<GlobalRef>, Error>>()?;
+        // another thread may have got there first, with the same constants
+        let _ = self.constants.set(constants);
+        Ok(self.constants.get().expect("just set"))
+    }
+}
+
+impl Default for EnumConstants {
+    fn default() -> Self {
+        EnumConstants::new()
+    }
+}
+
+/// The java constant for `value`.  The reference is global, and must not be deleted.
+pub fn enum_constant<'a, E: JavaEnum>(
+    jni_env: &JNIEnv<'a>,
+    value: E,
+) -> Result<JObject<'a>, Error> {
+    let constants = E::constants().get::<E>(jni_env)?;
+    Ok(wrap_jobject(*constants[value.index()].as_obj()))
+}
+
+/// The variant for the java constant in `val`, whose local reference this deletes.
+/// A null is a `NullPointerException`, and a constant without a variant an `IllegalArgumentException`.
+pub fn enum_from_java<'a, E: JavaEnum>(jni_env: &JNIEnv<'a>, val: JValue<'a>) -> Result<E, Error> {
+    let constant = AutoLocal::new(jni_env, val.l()?);
+    if constant.as_obj().is_null() {
+        jni_env.throw_new(
+            "java/lang/NullPointerException",
+            format!("no {} constant", E::java_class_name().replace('/', ".")),
+        )?;
+        return Err(java_exception());
+    }
+    let name = jni_env.call_method(constant.as_obj(), "name", "()Ljava/lang/String;", &[])?;
+    let name = <String as crate::ConvertJValueToRust>::to_rust(jni_env, name)?;
+    match E::JAVA_NAMES.iter().position(|known| *known == name) {
+        Some(index) => Ok(E::from_index(index)),
+        None => {
+            jni_env.throw_new(
+                "java/lang/IllegalArgumentException",
+                format!(
+                    "No enum constant {}.{}",
+                    E::java_class_name().replace('/', "."),
+                    name
+                ),
+            )?;
+            Err(java_exception())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{enum_constant, enum_from_java, EnumConstants, JavaEnum};
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::JavaClassNameFor;
+    use jni::errors::Error;
+    use jni::objects::JValue;
+
+    /// a few of java.util.concurrent.TimeUnit's constants, in an order of our own
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum TimeUnit {
+        Seconds,
+        Milliseconds,
+    }
+
+    impl JavaClassNameFor for TimeUnit {
+        fn java_class_name() -> &'static str {
+            "java/util/concurrent/TimeUnit"
+        }
+    }
+
+    impl JavaEnum for TimeUnit {
+        const JAVA_NAMES: &'static [&'static str] = &["SECONDS", "MILLISECONDS"];
+
+        fn index(self) -> usize {
+            self as usize
+        }
+
+        fn from_index(index: usize) -> Self {
+            [TimeUnit::Seconds, TimeUnit::Milliseconds][index]
+        }
+
+        fn constants() -> &'static EnumConstants {
+            static CONSTANTS: EnumConstants = EnumConstants::new();
+            &CONSTANTS
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn constants() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let seconds = enum_constant(&jni_env, TimeUnit::Seconds)?;
+        let millis = jni_env
+            .call_method(seconds, "toMillis", "(J)J", &[JValue::Long(3)])?
+            .j()?;
+        assert_eq!(3000, millis);
+        assert_eq!("SECONDS", TimeUnit::Seconds.java_name());
+
+        let millis = jni_env.get_static_field(
+            "java/util/concurrent/TimeUnit",
+            "MILLISECONDS",
+            "Ljava/util/concurrent/TimeUnit;",
+        )?;
+        assert_eq!(TimeUnit::Milliseconds, enum_from_java(&jni_env, millis)?);
+
+        // the rust enum has no variant for DAYS
+        let days = jni_env.get_static_field(
+            "java/util/concurrent/TimeUnit",
+            "DAYS",
+            "Ljava/util/concurrent/TimeUnit;",
+        )?;
+        assert!(enum_from_java::<TimeUnit>(&jni_env, days).is_err());
+        assert!(jni_env.exception_check()?);
+        jni_env.exception_clear()?;
+
+        assert!(
+            enum_from_java::<TimeUnit>(&jni_env, JValue::from(jni::objects::JObject::null()))
+                .is_err()
+        );
+        jni_env.exception_clear()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 /// This is just where I stash some helper functions for calling important stuff in the java runtime
 use crate::{jni, wrap_jobject};
-use jni::objects::{JObject, JValue};
+use jni::objects::{AutoLocal, JObject, JValue};
 use jni::sys::jobject;
 use jni::JNIEnv;
 
@@ -10,6 +10,33 @@ pub fn class_is_array(je: &JNIEnv, class_object: &JObject) -> Result<bool, jni::
     rval.z()
 }
 
+/// renders a java.lang.Throwable the way printStackTrace() would, but into a rust String instead of System.err
+pub fn stack_trace_string(je: &JNIEnv, throwable: JObject) -> Result<String, jni::errors::Error> {
+    let string_writer = AutoLocal::new(je, je.new_object("java/io/StringWriter", "()V", &[])?);
+    let print_writer = AutoLocal::new(
+        je,
+        je.new_object(
+            "java/io/PrintWriter",
+            "(Ljava/io/Writer;)V",
+            &[JValue::from(string_writer.as_obj())],
+        )?,
+    );
+    je.call_method(
+        throwable,
+        "printStackTrace",
+        "(Ljava/io/PrintWriter;)V",
+        &[JValue::from(print_writer.as_obj())],
+    )?;
+    je.call_method(print_writer.as_obj(), "flush", "()V", &[])?;
+    let rendered = je.call_method(
+        string_writer.as_obj(),
+        "toString",
+        "()Ljava/lang/String;",
+        &[],
+    )?;
+    <String as crate::ConvertJValueToRust>::to_rust(je, rendered)
+}
+
 #[cfg(not(feature = "jni_0_20"))]
 pub fn jni_workaround_jvalue<'a>(val: jobject) -> JValue<'a> {
     JValue::from(val)
@@ -87,6 +114,25 @@ impl<'a: 'b, 'b> crate::ConvertJValueToRust<'a, 'b> for Throwable<'a, 'b> {
     }
 }
 
+impl<'a: 'b, 'b> crate::local_frame::LeaveFrame<'a, 'b> for Throwable<'a, 'b> {
+    type Rest = ();
+
+    fn leave_frame(self) -> (jni::objects::JObject<'a>, ()) {
+        (self.java_this.forget(), ())
+    }
+
+    fn enter_frame(
+        jni_env: &'b jni::JNIEnv<'a>,
+        object: jni::objects::JObject<'a>,
+        _rest: (),
+    ) -> Self {
+        Throwable {
+            java_this: jni::objects::AutoLocal::new(jni_env, object),
+            jni_env,
+        }
+    }
+}
+
 impl Throwable<'_, '_> {
     #[allow(non_snake_case)]
     pub fn printStackTrace(&self) -> Result<(), jni::errors::Error> {
@@ -0,0 +1,406 @@
+//! Rust iterators and slices handed to java as `java.util.Iterator`, `java.lang.Iterable` or a read-only `java.util.List`,
+//! without building a whole `Object[]` first.  Each element is converted (with `ToJavaObject`) only when java asks for it.
+//!
+//! These are parameter types: `JavaSignatureFor` and `ConvertRustToJValue` make them usable with `jni_instance_method!`
+//! and friends.  The java side is one of the embedded classes `RustIterator`, `RustIterable` or `RustList`, which hand
+//! their abstract methods to a `RustInvocationHandler` (see `java_callback`) and keep the rust data alive until
+//! java collects them.  So the data must be `Send + 'static`; a huge `Vec` can be moved in rather than copied.
+
+use crate::java_callback::{invocation_handler, CallbackArgument, EmbeddedClass, ToJavaObject};
+use crate::native_method::new_local_ref;
+use crate::{
+    java_exception, jni, unwrap_jobject, ConvertRustToJValue, JValueNonScalar, JavaClassNameFor,
+    JavaSignatureFor,
+};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JClass, JObject, JValue};
+use jni::sys::jobject;
+use jni::JNIEnv;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+static ITERATOR_CLASS: EmbeddedClass = EmbeddedClass::new(
+    "com/purplefrog/jni_boilerplate/RustIterator",
+    include_bytes!("../java/com/purplefrog/jni_boilerplate/RustIterator.class"),
+);
+
+static ITERABLE_CLASS: EmbeddedClass = EmbeddedClass::new(
+    "com/purplefrog/jni_boilerplate/RustIterable",
+    include_bytes!("../java/com/purplefrog/jni_boilerplate/RustIterable.class"),
+);
+
+static LIST_CLASS: EmbeddedClass = EmbeddedClass::new(
+    "com/purplefrog/jni_boilerplate/RustList",
+    include_bytes!("../java/com/purplefrog/jni_boilerplate/RustList.class"),
+);
+
+fn nothing_to_prepare(_je: &JNIEnv, _class: JClass) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Makes a `Rust...` view whose `handler` answers its abstract methods.
+fn new_view<'a: 'b, 'b, F>(
+    je: &'b JNIEnv<'a>,
+    class: &EmbeddedClass,
+    handler: F,
+) -> Result<AutoLocal<'a, 'b>, Error>
+where
+    F: for<'c> Fn(&'c JNIEnv<'c>, &str, &[JObject<'c>]) -> Result<jobject, Error>
+        + Send
+        + Sync
+        + 'static,
+{
+    let class = class.class(je, nothing_to_prepare)?;
+    let handler = invocation_handler(je, handler)?;
+    let view = je.new_object(
+        JClass::from(class.as_obj()),
+        "(Ljava/lang/reflect/InvocationHandler;)V",
+        &[JValue::from(handler.as_obj())],
+    )?;
+    Ok(AutoLocal::new(je, view))
+}
+
+/// Throws a new `class_name` and returns the `Err` that says an exception is pending.
+fn throw(je: &JNIEnv, class_name: &str, message: &str) -> Error {
+    if let Err(e) = je.throw_new(class_name, message) {
+        return e;
+    }
+    java_exception()
+}
+
+fn unexpected_method(je: &JNIEnv, method_name: &str) -> Error {
+    throw(
+        je,
+        "java/lang/UnsupportedOperationException",
+        &format!("{} is not implemented in rust", method_name),
+    )
+}
+
+//
+
+/// A rust iterator for java, as a `java.util.Iterator` whose `remove()` is unsupported.
+///
+/// Every java object made from one `JavaIterator` shares the same underlying iterator.
+pub struct JavaIterator<I: Iterator> {
+    iter: Arc<Mutex<Peekable<I>>>,
+}
+
+impl<I> JavaIterator<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: ToJavaObject + Send,
+{
+    pub fn new<C: IntoIterator<IntoIter = I>>(iter: C) -> JavaIterator<I> {
+        JavaIterator {
+            iter: Arc::new(Mutex::new(iter.into_iter().peekable())),
+        }
+    }
+}
+
+/// A `RustIterator` over `iter`.
+fn new_java_iterator<'a: 'b, 'b, I>(
+    je: &'b JNIEnv<'a>,
+    iter: Arc<Mutex<Peekable<I>>>,
+) -> Result<AutoLocal<'a, 'b>, Error>
+where
+    I: Iterator + Send + 'static,
+    I::Item: ToJavaObject + Send,
+{
+    new_view(je, &ITERATOR_CLASS, move |je, method_name, _args| {
+        let mut iter = match iter.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        match method_name {
+            "hasNext" => iter.peek().is_some().to_java_object(je),
+            "next" => match iter.next() {
+                Some(item) => item.to_java_object(je),
+                None => Err(throw(
+                    je,
+                    "java/util/NoSuchElementException",
+                    "the rust iterator is exhausted",
+                )),
+            },
+            _ => Err(unexpected_method(je, method_name)),
+        }
+    })
+}
+
+impl<I: Iterator> JValueNonScalar for JavaIterator<I> {}
+
+impl<I: Iterator> JavaClassNameFor for JavaIterator<I> {
+    fn java_class_name() -> &'static str {
+        "java/util/Iterator"
+    }
+}
+
+impl<I: Iterator> JavaSignatureFor for JavaIterator<I> {
+    fn signature_for() -> String {
+        String::from("Ljava/util/Iterator;")
+    }
+}
+
+impl<'a: 'b, 'b, I> ConvertRustToJValue<'a, 'b> for JavaIterator<I>
+where
+    I: Iterator + Send + 'static,
+    I::Item: ToJavaObject + Send,
+{
+    type T = AutoLocal<'a, 'b>;
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        new_java_iterator(je, self.iter.clone())
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        JValue::from(tmp.as_obj())
+    }
+}
+
+//
+
+/// A `java.lang.Iterable` whose `iterator()` calls `factory` for a fresh rust iterator each time.
+pub struct JavaIterable<F> {
+    factory: Arc<F>,
+}
+
+impl<F, I> JavaIterable<F>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: Iterator + Send + 'static,
+    I::Item: ToJavaObject + Send,
+{
+    pub fn new(factory: F) -> JavaIterable<F> {
+        JavaIterable {
+            factory: Arc::new(factory),
+        }
+    }
+}
+
+impl<F> JValueNonScalar for JavaIterable<F> {}
+
+impl<F> JavaClassNameFor for JavaIterable<F> {
+    fn java_class_name() -> &'static str {
+        "java/lang/Iterable"
+    }
+}
+
+impl<F> JavaSignatureFor for JavaIterable<F> {
+    fn signature_for() -> String {
+        String::from("Ljava/lang/Iterable;")
+    }
+}
+
+impl<'a: 'b, 'b, F, I> ConvertRustToJValue<'a, 'b> for JavaIterable<F>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: Iterator + Send + 'static,
+    I::Item: ToJavaObject + Send,
+{
+    type T = AutoLocal<'a, 'b>;
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        let factory = self.factory.clone();
+        new_view(
+            je,
+            &ITERABLE_CLASS,
+            move |je, method_name, _args| match method_name {
+                "iterator" => {
+                    let iter = Arc::new(Mutex::new(factory().peekable()));
+                    let iterator = new_java_iterator(je, iter)?;
+                    Ok(unwrap_jobject(new_local_ref(je, iterator.as_obj())?))
+                }
+                _ => Err(unexpected_method(je, method_name)),
+            },
+        )
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        JValue::from(tmp.as_obj())
+    }
+}
+
+//
+
+/// A slice for java, as a read-only `java.util.List` (a `RandomAccess` `AbstractList`).
+///
+/// `items` is anything that can lend out a `&[T]`: a `Vec<T>`, a `Box<[T]>`, an `Arc<[T]>` or a `&'static [T]`.
+/// It is shared, not copied, by every java object made from this `JavaList`.
+pub struct JavaList<T, S = Vec<T>> {
+    items: Arc<S>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T, S> JavaList<T, S>
+where
+    S: AsRef<[T]> + Send + Sync + 'static,
+    T: ToJavaObject,
+{
+    pub fn new(items: S) -> JavaList<T, S> {
+        JavaList {
+            items: Arc::new(items),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, S> JValueNonScalar for JavaList<T, S> {}
+
+impl<T, S> JavaClassNameFor for JavaList<T, S> {
+    fn java_class_name() -> &'static str {
+        "java/util/List"
+    }
+}
+
+impl<T, S> JavaSignatureFor for JavaList<T, S> {
+    fn signature_for() -> String {
+        String::from("Ljava/util/List;")
+    }
+}
+
+impl<'a: 'b, 'b, T, S> ConvertRustToJValue<'a, 'b> for JavaList<T, S>
+where
+    S: AsRef<[T]> + Send + Sync + 'static,
+    T: ToJavaObject,
+{
+    type T = AutoLocal<'a, 'b>;
+
+    fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<Self::T, Error> {
+        let items = self.items.clone();
+        new_view(je, &LIST_CLASS, move |je, method_name, args| {
+            let items = (*items).as_ref();
+            match (method_name, args) {
+                ("size", []) => (items.len() as i32).to_java_object(je),
+                ("get", [index]) => {
+                    let index = i32::from_java(je, *index)?;
+                    match usize::try_from(index).ok().and_then(|i| items.get(i)) {
+                        Some(item) => item.to_java_object(je),
+                        None => Err(throw(
+                            je,
+                            "java/lang/IndexOutOfBoundsException",
+                            &format!("Index: {}, Size: {}", index, items.len()),
+                        )),
+                    }
+                }
+                _ => Err(unexpected_method(je, method_name)),
+            }
+        })
+    }
+
+    fn temporary_into_jvalue(tmp: &Self::T) -> JValue<'a> {
+        JValue::from(tmp.as_obj())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JavaIterable, JavaIterator, JavaList};
+    use crate::{jni, test_jvm, ConvertJValueToRust, ConvertRustToJValue};
+    use jni::errors::Error;
+    use jni::objects::JValue;
+    use jni::JNIEnv;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// `String.join(",", iterable)`
+    fn join(jni_env: &JNIEnv, iterable: JValue) -> Result<String, Error> {
+        let comma = jni_env.new_string(",")?;
+        let joined = jni_env.call_static_method(
+            "java/lang/String",
+            "join",
+            "(Ljava/lang/CharSequence;Ljava/lang/Iterable;)Ljava/lang/String;",
+            &[JValue::from(comma), iterable],
+        )?;
+        String::to_rust(jni_env, joined)
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn list() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let names = JavaList::new(vec![
+            String::from("a"),
+            String::from("b"),
+            String::from("c"),
+        ]);
+        let tmp = names.into_temporary(&jni_env)?;
+        assert_eq!("a,b,c", join(&jni_env, JValue::from(tmp.as_obj()))?);
+
+        let numbers = JavaList::new(vec![10, 20, 30]);
+        let tmp = numbers.into_temporary(&jni_env)?;
+        let list = tmp.as_obj();
+        assert_eq!(3, jni_env.call_method(list, "size", "()I", &[])?.i()?);
+        let second =
+            jni_env.call_method(list, "get", "(I)Ljava/lang/Object;", &[JValue::Int(1)])?;
+        let second = jni_env.call_method(second.l()?, "intValue", "()I", &[])?;
+        assert_eq!(20, second.i()?);
+        let text = jni_env.call_method(list, "toString", "()Ljava/lang/String;", &[])?;
+        assert_eq!("[10, 20, 30]", String::to_rust(&jni_env, text)?);
+
+        assert!(jni_env
+            .call_method(list, "get", "(I)Ljava/lang/Object;", &[JValue::Int(3)])
+            .is_err());
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/lang/IndexOutOfBoundsException")?);
+
+        // read-only
+        assert!(jni_env.call_method(list, "clear", "()V", &[]).is_err());
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/lang/UnsupportedOperationException")?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn iterator_is_lazy() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let converted = Arc::new(AtomicUsize::new(0));
+        let counter = converted.clone();
+        let squares = JavaIterator::new((1..1_000_000i64).map(move |i| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            i * i
+        }));
+        let tmp = squares.into_temporary(&jni_env)?;
+        let iterator = tmp.as_obj();
+
+        assert!(jni_env.call_method(iterator, "hasNext", "()Z", &[])?.z()?);
+        for expected in &[1i64, 4, 9] {
+            let next = jni_env.call_method(iterator, "next", "()Ljava/lang/Object;", &[])?;
+            let next = jni_env.call_method(next.l()?, "longValue", "()J", &[])?;
+            assert_eq!(*expected, next.j()?);
+        }
+        // hasNext() peeks at one more
+        assert_eq!(3, converted.load(Ordering::SeqCst));
+        assert!(jni_env.call_method(iterator, "hasNext", "()Z", &[])?.z()?);
+        assert_eq!(4, converted.load(Ordering::SeqCst));
+
+        let empty = JavaIterator::new(Vec::<String>::new());
+        let tmp = empty.into_temporary(&jni_env)?;
+        assert!(!jni_env
+            .call_method(tmp.as_obj(), "hasNext", "()Z", &[])?
+            .z()?);
+        assert!(jni_env
+            .call_method(tmp.as_obj(), "next", "()Ljava/lang/Object;", &[])
+            .is_err());
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/util/NoSuchElementException")?);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn iterable() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let words = vec!["x", "y", "z"];
+        let iterable = JavaIterable::new(move || words.clone().into_iter().map(String::from));
+        let tmp = iterable.into_temporary(&jni_env)?;
+        // every iterator() starts over
+        assert_eq!("x,y,z", join(&jni_env, JValue::from(tmp.as_obj()))?);
+        assert_eq!("x,y,z", join(&jni_env, JValue::from(tmp.as_obj()))?);
+        Ok(())
+    }
+}
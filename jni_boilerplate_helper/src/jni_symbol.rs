@@ -0,0 +1,193 @@
+//! The names the JVM looks for when it resolves a java `native` method to an exported function,
+//! as described in the "Resolving Native Method Names" section of the JNI specification.
+//!
+//! Used by `#[jni_native_method]`, and usable from build scripts that need to know what a library exports.
+
+/// The exported symbol for a native method.
+///
+/// `class_name` may use `.` or `/` between package components (`com.foo.Bar` or `com/foo/Bar`),
+/// and `$` for inner classes (`com/foo/Bar$Inner`).
+///
+/// Without `argument_descriptor` this is the short name, `Java_` + mangled class + `_` + mangled method.
+/// An overloaded native method needs the long name, which appends `__` and the mangled argument descriptor.
+/// `argument_descriptor` may be the argument part alone (`ILjava/lang/String;`)
+/// or a whole method descriptor (`(ILjava/lang/String;)V`), whose return type is ignored.
+pub fn jni_symbol_name(
+    class_name: &str,
+    method_name: &str,
+    argument_descriptor: Option<&str>,
+) -> String {
+    let mut rval = format!(
+        "Java_{}_{}",
+        mangle_jni_name(class_name),
+        mangle_jni_name(method_name)
+    );
+    if let Some(descriptor) = argument_descriptor {
+        rval.push_str("__");
+        rval.push_str(&mangle_jni_name(argument_part(descriptor)));
+    }
+    rval
+}
+
+/// Escapes one component (class, method or argument descriptor) of a native method's symbol name:
+/// `/` and `.` become `_`, `_` becomes `_1`, `;` becomes `_2`, `[` becomes `_3`,
+/// and anything but an ASCII letter or digit becomes `_0xxxx` (one per UTF-16 code unit, lower case hex).
+pub fn mangle_jni_name(name: &str) -> String {
+    let mut rval = String::new();
+    for ch in name.chars() {
+        match ch {
+            '/' | '.' => rval.push('_'),
+            '_' => rval.push_str("_1"),
+            ';' => rval.push_str("_2"),
+            '[' => rval.push_str("_3"),
+            'a'..='z' | 'A'..='Z' | '0'..='9' => rval.push(ch),
+            _ => {
+                let mut buf = [0u16; 2];
+                for unit in ch.encode_utf16(&mut buf) {
+                    rval.push_str(&format!("_0{:04x}", unit));
+                }
+            }
+        }
+    }
+    rval
+}
+
+/// `(ILjava/lang/String;)V` becomes `ILjava/lang/String;`; anything without the parentheses is returned unchanged.
+fn argument_part(descriptor: &str) -> &str {
+    match descriptor.strip_prefix('(') {
+        Some(rest) => match rest.find(')') {
+            Some(end) => &rest[..end],
+            None => rest,
+        },
+        None => descriptor,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{jni_symbol_name, mangle_jni_name};
+
+    #[test]
+    fn escape_table() {
+        // the escape sequences table of the specification
+        assert_eq!("_0abcd", mangle_jni_name("\u{abcd}"));
+        assert_eq!("_1", mangle_jni_name("_"));
+        assert_eq!("_2", mangle_jni_name(";"));
+        assert_eq!("_3", mangle_jni_name("["));
+        // the "/" in fully qualified class names
+        assert_eq!("java_lang_String", mangle_jni_name("java/lang/String"));
+        assert_eq!("java_lang_String", mangle_jni_name("java.lang.String"));
+    }
+
+    #[test]
+    fn plain_characters() {
+        let alphanumeric = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+        assert_eq!(alphanumeric, mangle_jni_name(alphanumeric));
+        assert_eq!("", mangle_jni_name(""));
+    }
+
+    #[test]
+    fn unicode() {
+        // other ASCII punctuation is escaped like any other non-alphanumeric character
+        assert_eq!("_00024", mangle_jni_name("$"));
+        assert_eq!("_0002d", mangle_jni_name("-"));
+        // lower case hex, as the specification requires
+        assert_eq!("caf_000e9", mangle_jni_name("café"));
+        assert_eq!("_000c9t_000e9", mangle_jni_name("Été"));
+        assert_eq!("_04e2d_06587", mangle_jni_name("中文"));
+        // outside the basic multilingual plane java names are UTF-16, so each surrogate is escaped
+        assert_eq!("_0d834_0dd1e", mangle_jni_name("\u{1d11e}"));
+    }
+
+    #[test]
+    fn short_names() {
+        // the example from the specification
+        assert_eq!("Java_p_q_r_A_f", jni_symbol_name("p/q/r/A", "f", None));
+        assert_eq!(
+            "Java_com_purplefrog_rust_1callables_Widget_nativeDescribe",
+            jni_symbol_name(
+                "com.purplefrog.rust_callables.Widget",
+                "nativeDescribe",
+                None
+            )
+        );
+        assert_eq!(
+            "Java_Widget_set_1count",
+            jni_symbol_name("Widget", "set_count", None)
+        );
+    }
+
+    #[test]
+    fn inner_classes() {
+        assert_eq!(
+            "Java_com_foo_Outer_00024Inner_run",
+            jni_symbol_name("com/foo/Outer$Inner", "run", None)
+        );
+        assert_eq!(
+            "Java_com_foo_Outer_00024Inner_00024Deeper_run",
+            jni_symbol_name("com.foo.Outer$Inner$Deeper", "run", None)
+        );
+        assert_eq!(
+            "Java_com_foo_Outer_000241_run",
+            jni_symbol_name("com/foo/Outer$1", "run", None)
+        );
+    }
+
+    #[test]
+    fn long_names() {
+        // the example from the specification: native double f(int i, String s) in p.q.r.A
+        assert_eq!(
+            "Java_p_q_r_A_f__ILjava_lang_String_2",
+            jni_symbol_name("p/q/r/A", "f", Some("ILjava/lang/String;"))
+        );
+        assert_eq!(
+            "Java_p_q_r_A_f__ILjava_lang_String_2",
+            jni_symbol_name("p.q.r.A", "f", Some("(ILjava/lang/String;)D"))
+        );
+        // an overload without arguments still gets the separator
+        assert_eq!(
+            "Java_p_q_r_A_f__",
+            jni_symbol_name("p/q/r/A", "f", Some("()D"))
+        );
+        assert_eq!(
+            "Java_p_q_r_A_f__",
+            jni_symbol_name("p/q/r/A", "f", Some(""))
+        );
+    }
+
+    #[test]
+    fn long_names_with_arrays() {
+        assert_eq!(
+            "Java_Widget_sum___3I",
+            jni_symbol_name("Widget", "sum", Some("([I)I"))
+        );
+        assert_eq!(
+            "Java_Widget_sum___3_3J",
+            jni_symbol_name("Widget", "sum", Some("([[J)J"))
+        );
+        assert_eq!(
+            "Java_Widget_join___3Ljava_lang_String_2Ljava_lang_String_2",
+            jni_symbol_name(
+                "Widget",
+                "join",
+                Some("([Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;")
+            )
+        );
+        assert_eq!(
+            "Java_Widget_echo__ZBCSIJFD",
+            jni_symbol_name("Widget", "echo", Some("(ZBCSIJFD)V"))
+        );
+    }
+
+    #[test]
+    fn long_names_with_inner_class_arguments() {
+        assert_eq!(
+            "Java_com_foo_Bar_take__Lcom_foo_Bar_00024Inner_2",
+            jni_symbol_name("com/foo/Bar", "take", Some("(Lcom/foo/Bar$Inner;)V"))
+        );
+        assert_eq!(
+            "Java_com_foo_Bar_take__Lcom_foo_my_1pkg_Thing_2",
+            jni_symbol_name("com/foo/Bar", "take", Some("(Lcom/foo/my_pkg/Thing;)V"))
+        );
+    }
+}
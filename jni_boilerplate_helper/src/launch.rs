@@ -0,0 +1,495 @@
+//! Starting a JVM inside a rust program, for command-line tools and tests.
+//!
+//! `JvmConfig` loads libjvm itself, from `JAVA_HOME` or a directory you name, so it works the same
+//! whichever jni version the crate was built against and needs no `invocation` feature or linker flags.
+//!
+//! ```no_run
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::launch::{run_main, JvmConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! JvmConfig::new()
+//!     .class_path("target/classes")
+//!     .class_path("lib/widgets.jar")
+//!     .property("widgets.verbose", "true")
+//!     .max_heap("512m")
+//!     .launch()?;
+//! run_main("com.purplefrog.widgets.Main", &["--list"])?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! There can only be one JVM in a process.  It is registered with `thread_env`, and launching again
+//! (or after someone else started one) hands back the running JVM without applying the new configuration.
+
+use crate::jni;
+use crate::thread_env;
+use crate::{ClearIfErr, ConvertRustToJValue};
+use jni::objects::{AutoLocal, JClass};
+use jni::sys::{jint, jsize, JavaVMInitArgs, JavaVMOption, JNI_FALSE, JNI_OK, JNI_VERSION_1_8};
+use jni::JavaVM;
+use std::ffi::{CString, OsStr, OsString};
+use std::fmt;
+use std::os::raw::{c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::Mutex;
+
+/// What went wrong starting the JVM
+#[derive(Debug)]
+pub enum LaunchError {
+    /// neither `java_home()` nor `JAVA_HOME` says where the JDK is
+    NoJavaHome,
+    /// none of the usual places under this java home has a libjvm
+    NoLibjvm(PathBuf),
+    /// libjvm could not be loaded, or does not export the invocation API
+    Library(String),
+    /// an option the JVM can not be given, such as one with a NUL in it
+    BadOption(String),
+    /// a JNI invocation function failed with this error code
+    Jni(&'static str, jint),
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LaunchError::NoJavaHome => write!(f, "JAVA_HOME is not set"),
+            LaunchError::NoLibjvm(java_home) => {
+                write!(f, "no libjvm under {}", java_home.display())
+            }
+            LaunchError::Library(message) => write!(f, "{}", message),
+            LaunchError::BadOption(option) => write!(f, "unusable JVM option {:?}", option),
+            LaunchError::Jni(function, code) => write!(f, "{} failed with {}", function, code),
+        }
+    }
+}
+
+impl std::error::Error for LaunchError {}
+
+/// where JDKs keep the JVM library, relative to `JAVA_HOME`: 9 and later, then 8's `jre` directory
+const LIBJVM_LOCATIONS: &[&str] = &[
+    "lib/server/libjvm.so",
+    "lib/server/libjvm.dylib",
+    "bin/server/jvm.dll",
+    "jre/lib/amd64/server/libjvm.so",
+    "jre/lib/aarch64/server/libjvm.so",
+    "jre/lib/server/libjvm.dylib",
+    "jre/bin/server/jvm.dll",
+];
+
+/// The options of a JVM to start.  Sizes are in the JVM's own syntax, like `"512m"` or `"2g"`.
+#[derive(Debug, Clone, Default)]
+pub struct JvmConfig {
+    java_home: Option<PathBuf>,
+    class_path: Vec<PathBuf>,
+    properties: Vec<(String, String)>,
+    initial_heap: Option<String>,
+    max_heap: Option<String>,
+    check_jni: bool,
+    options: Vec<String>,
+}
+
+impl JvmConfig {
+    pub fn new() -> JvmConfig {
+        JvmConfig::default()
+    }
+
+    /// the JDK or JRE to load libjvm from, instead of `JAVA_HOME`
+    pub fn java_home<P: Into<PathBuf>>(mut self, java_home: P) -> JvmConfig {
+        self.java_home = Some(java_home.into());
+        self
+    }
+
+    /// appends a directory or jar to the class path
+    pub fn class_path<P: Into<PathBuf>>(mut self, entry: P) -> JvmConfig {
+        self.class_path.push(entry.into());
+        self
+    }
+
+    /// a system property, `-Dname=value`
+    pub fn property(mut self, name: &str, value: &str) -> JvmConfig {
+        self.properties
+            .push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// `-Xms`
+    pub fn initial_heap(mut self, size: &str) -> JvmConfig {
+        self.initial_heap = Some(String::from(size));
+        self
+    }
+
+    /// `-Xmx`
+    pub fn max_heap(mut self, size: &str) -> JvmConfig {
+        self.max_heap = Some(String::from(size));
+        self
+    }
+
+    /// `-Xcheck:jni`, which makes the JVM check every JNI call, at some cost in speed
+    pub fn check_jni(mut self) -> JvmConfig {
+        self.check_jni = true;
+        self
+    }
+
+    /// any other option, as it would be given to the `java` command
+    pub fn option(mut self, option: &str) -> JvmConfig {
+        self.options.push(String::from(option));
+        self
+    }
+
+    /// the options to hand `JNI_CreateJavaVM`
+    pub fn jvm_options(&self) -> Result<Vec<OsString>, LaunchError> {
+        let mut options = Vec::new();
+        if !self.class_path.is_empty() {
+            let class_path = std::env::join_paths(&self.class_path)
+                .map_err(|e| LaunchError::BadOption(e.to_string()))?;
+            let mut option = OsString::from("-Djava.class.path=");
+            option.push(class_path);
+            options.push(option);
+        }
+        for (name, value) in &self.properties {
+            options.push(OsString::from(format!("-D{}={}", name, value)));
+        }
+        if let Some(size) = &self.initial_heap {
+            options.push(OsString::from(format!("-Xms{}", size)));
+        }
+        if let Some(size) = &self.max_heap {
+            options.push(OsString::from(format!("-Xmx{}", size)));
+        }
+        if self.check_jni {
+            options.push(OsString::from("-Xcheck:jni"));
+        }
+        options.extend(self.options.iter().map(OsString::from));
+        Ok(options)
+    }
+
+    /// the JVM library this configuration would load
+    pub fn libjvm(&self) -> Result<PathBuf, LaunchError> {
+        let java_home = match &self.java_home {
+            Some(java_home) => java_home.clone(),
+            None => PathBuf::from(std::env::var_os("JAVA_HOME").ok_or(LaunchError::NoJavaHome)?),
+        };
+        LIBJVM_LOCATIONS
+            .iter()
+            .map(|location| java_home.join(location))
+            .find(|path| path.is_file())
+            .or_else(|| arch_libjvm(&java_home.join("jre/lib")))
+            .or_else(|| arch_libjvm(&java_home.join("lib")))
+            .ok_or(LaunchError::NoLibjvm(java_home))
+    }
+
+    /// Starts the JVM, or finds the one already running, and registers it with `thread_env`.
+    pub fn launch(&self) -> Result<&'static JavaVM, LaunchError> {
+        // two threads must not both try to create the JVM
+        static LAUNCHING: Mutex<()> = Mutex::new(());
+        let _launching = LAUNCHING
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(java_vm) = thread_env::java_vm() {
+            return Ok(java_vm);
+        }
+
+        let library = load_library(&self.libjvm()?)?;
+        let raw_vm = match created_java_vm(library)? {
+            Some(raw_vm) => raw_vm,
+            None => self.create_java_vm(library)?,
+        };
+        let java_vm = unsafe { JavaVM::from_raw(raw_vm) }
+            .map_err(|_| LaunchError::Jni("JNI_CreateJavaVM", jni::sys::JNI_ERR))?;
+        Ok(thread_env::set_java_vm(java_vm))
+    }
+
+    fn create_java_vm(&self, library: Library) -> Result<*mut jni::sys::JavaVM, LaunchError> {
+        let create: CreateJavaVm =
+            unsafe { std::mem::transmute(symbol(library, "JNI_CreateJavaVM")?) };
+
+        let option_strings = self
+            .jvm_options()?
+            .into_iter()
+            .map(c_string)
+            .collect::<Result<Vec<CString>, LaunchError>>()?;
+        let mut options: Vec<JavaVMOption> = option_strings
+            .iter()
+            .map(|option| JavaVMOption {
+                optionString: option.as_ptr() as *mut c_char,
+                extraInfo: ptr::null_mut(),
+            })
+            .collect();
+        let mut init_args = JavaVMInitArgs {
+            version: JNI_VERSION_1_8,
+            nOptions: options.len() as jint,
+            options: options.as_mut_ptr(),
+            ignoreUnrecognized: JNI_FALSE,
+        };
+
+        let mut raw_vm: *mut jni::sys::JavaVM = ptr::null_mut();
+        let mut raw_env: *mut c_void = ptr::null_mut();
+        let code = unsafe {
+            create(
+                &mut raw_vm,
+                &mut raw_env,
+                &mut init_args as *mut JavaVMInitArgs as *mut c_void,
+            )
+        };
+        if code != JNI_OK {
+            return Err(LaunchError::Jni("JNI_CreateJavaVM", code));
+        }
+        Ok(raw_vm)
+    }
+}
+
+/// Calls `public static void main(String[])` of `main_class` (dotted or slashed) on the current thread,
+/// attaching it if need be.  An exception thrown out of `main` is reported, cleared and returned as the error.
+/// Note that a `main` which calls `System.exit()` ends the whole process.
+pub fn run_main(main_class: &str, args: &[&str]) -> Result<(), jni::errors::Error> {
+    thread_env::with_env(|jni_env| {
+        let main_class = main_class.replace('.', "/");
+        let class = jni_env
+            .find_class(main_class.as_str())
+            .clear_if_err(jni_env)?;
+        let class = AutoLocal::new(jni_env, *class);
+
+        let args: Vec<String> = args.iter().map(|&arg| String::from(arg)).collect();
+        let args = args.into_temporary(jni_env)?;
+        jni_env
+            .call_static_method(
+                JClass::from(class.as_obj()),
+                "main",
+                "([Ljava/lang/String;)V",
+                &[<Vec<String> as ConvertRustToJValue>::temporary_into_jvalue(
+                    &args,
+                )],
+            )
+            .clear_if_err(jni_env)?;
+        Ok(())
+    })
+}
+
+/// `<arch>/server/libjvm.so` under `lib`, for the architectures `LIBJVM_LOCATIONS` does not name,
+/// in a JDK 8 (`jre/lib`) or a JRE 8 (`lib`)
+fn arch_libjvm(lib: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(lib)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|arch| arch.path().join("server/libjvm.so"))
+        .find(|path| path.is_file())
+}
+
+type CreateJavaVm =
+    unsafe extern "system" fn(*mut *mut jni::sys::JavaVM, *mut *mut c_void, *mut c_void) -> jint;
+type GetCreatedJavaVms =
+    unsafe extern "system" fn(*mut *mut jni::sys::JavaVM, jsize, *mut jsize) -> jint;
+
+/// a JVM started by someone else in this process, which is the only one there can be
+fn created_java_vm(library: Library) -> Result<Option<*mut jni::sys::JavaVM>, LaunchError> {
+    let get_created: GetCreatedJavaVms =
+        unsafe { std::mem::transmute(symbol(library, "JNI_GetCreatedJavaVMs")?) };
+    let mut raw_vm: *mut jni::sys::JavaVM = ptr::null_mut();
+    let mut count: jsize = 0;
+    let code = unsafe { get_created(&mut raw_vm, 1, &mut count) };
+    if code != JNI_OK {
+        return Err(LaunchError::Jni("JNI_GetCreatedJavaVMs", code));
+    }
+    Ok(if count > 0 { Some(raw_vm) } else { None })
+}
+
+fn c_string(option: OsString) -> Result<CString, LaunchError> {
+    let bad_option = |option: &OsStr| LaunchError::BadOption(option.to_string_lossy().into_owned());
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        option.as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = option
+        .to_str()
+        .ok_or_else(|| bad_option(&option))?
+        .as_bytes()
+        .to_vec();
+    CString::new(bytes).map_err(|_| bad_option(&option))
+}
+
+/// a loaded library; it is never unloaded, since a JVM can not be
+#[derive(Clone, Copy)]
+struct Library(*mut c_void);
+
+#[cfg(unix)]
+fn load_library(path: &Path) -> Result<Library, LaunchError> {
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| LaunchError::Library(e.to_string()))?;
+    let handle = unsafe { libc::dlopen(c_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL) };
+    if handle.is_null() {
+        let message = unsafe { std::ffi::CStr::from_ptr(libc::dlerror()) };
+        return Err(LaunchError::Library(format!(
+            "unable to load {}: {}",
+            path.display(),
+            message.to_string_lossy()
+        )));
+    }
+    Ok(Library(handle))
+}
+
+#[cfg(unix)]
+fn symbol(library: Library, name: &str) -> Result<*mut c_void, LaunchError> {
+    let c_name = CString::new(name).unwrap();
+    let address = unsafe { libc::dlsym(library.0, c_name.as_ptr()) };
+    if address.is_null() {
+        Err(LaunchError::Library(format!(
+            "libjvm does not export {}",
+            name
+        )))
+    } else {
+        Ok(address)
+    }
+}
+
+#[cfg(windows)]
+extern "system" {
+    fn LoadLibraryW(name: *const u16) -> *mut c_void;
+    fn GetProcAddress(module: *mut c_void, name: *const c_char) -> *mut c_void;
+}
+
+#[cfg(windows)]
+fn load_library(path: &Path) -> Result<Library, LaunchError> {
+    use std::os::windows::ffi::OsStrExt;
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let handle = unsafe { LoadLibraryW(wide.as_ptr()) };
+    if handle.is_null() {
+        return Err(LaunchError::Library(format!(
+            "unable to load {}: {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(Library(handle))
+}
+
+#[cfg(windows)]
+fn symbol(library: Library, name: &str) -> Result<*mut c_void, LaunchError> {
+    let c_name = CString::new(name).unwrap();
+    let address = unsafe { GetProcAddress(library.0, c_name.as_ptr()) };
+    if address.is_null() {
+        Err(LaunchError::Library(format!(
+            "libjvm does not export {}",
+            name
+        )))
+    } else {
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run_main, JvmConfig, LaunchError};
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::ConvertJValueToRust;
+    use std::ffi::OsString;
+    use std::fs;
+
+    #[test]
+    fn options() -> Result<(), LaunchError> {
+        let config = JvmConfig::new()
+            .class_path("classes")
+            .class_path("lib/widgets.jar")
+            .property("widgets.verbose", "true")
+            .initial_heap("64m")
+            .max_heap("1g")
+            .check_jni()
+            .option("-Xss4m");
+        let class_path = std::env::join_paths(["classes", "lib/widgets.jar"]).unwrap();
+        let mut class_path_option = OsString::from("-Djava.class.path=");
+        class_path_option.push(class_path);
+        assert_eq!(
+            vec![
+                class_path_option,
+                OsString::from("-Dwidgets.verbose=true"),
+                OsString::from("-Xms64m"),
+                OsString::from("-Xmx1g"),
+                OsString::from("-Xcheck:jni"),
+                OsString::from("-Xss4m"),
+            ],
+            config.jvm_options()?
+        );
+
+        assert!(matches!(
+            JvmConfig::new().java_home("/no/such/jdk").libjvm(),
+            Err(LaunchError::NoLibjvm(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn jdk8_layouts() -> Result<(), LaunchError> {
+        let java_home = std::env::temp_dir().join(format!("jvm_jdk8_{}", std::process::id()));
+        let config = JvmConfig::new().java_home(&java_home);
+        let mut found = Vec::new();
+        for libjvm in [
+            "jre/lib/aarch64/server/libjvm.so",
+            "jre/lib/ppc64le/server/libjvm.so",
+            "lib/s390x/server/libjvm.so",
+        ] {
+            let libjvm = java_home.join(libjvm);
+            fs::create_dir_all(libjvm.parent().unwrap()).unwrap();
+            fs::write(&libjvm, "").unwrap();
+            found.push(config.libjvm()?);
+            fs::remove_file(&libjvm).unwrap();
+        }
+        fs::remove_dir_all(&java_home).unwrap();
+        assert_eq!(
+            vec![
+                java_home.join("jre/lib/aarch64/server/libjvm.so"),
+                java_home.join("jre/lib/ppc64le/server/libjvm.so"),
+                java_home.join("lib/s390x/server/libjvm.so"),
+            ],
+            found
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn main_class() -> Result<(), jni::errors::Error> {
+        let java_vm = test_jvm::java_vm();
+        // there is only the one JVM, however it is configured
+        let launched = JvmConfig::new().max_heap("64m").launch().unwrap();
+        assert_eq!(
+            java_vm.get_java_vm_pointer(),
+            launched.get_java_vm_pointer()
+        );
+
+        // a main() that records its arguments, defined in the loader run_main() finds classes with
+        let jni_env = java_vm.attach_current_thread()?;
+        let loader = jni_env
+            .call_static_method(
+                "java/lang/ClassLoader",
+                "getSystemClassLoader",
+                "()Ljava/lang/ClassLoader;",
+                &[],
+            )?
+            .l()?;
+        let class = jni_env.define_class(
+            "com/purplefrog/jni_boilerplate/RecordingMain",
+            loader,
+            include_bytes!("../tests/java/com/purplefrog/jni_boilerplate/RecordingMain.class"),
+        )?;
+        jni_env.delete_local_ref(loader)?;
+
+        run_main(
+            "com.purplefrog.jni_boilerplate.RecordingMain",
+            &["one", "two"],
+        )?;
+        let recorded = jni_env.get_static_field(class, "lastArgs", "Ljava/lang/String;")?;
+        assert_eq!("one two", String::to_rust(&jni_env, recorded)?);
+
+        // an exception out of main() is the error, and is not left pending
+        assert!(run_main("com.purplefrog.jni_boilerplate.RecordingMain", &["fail"]).is_err());
+        assert!(!jni_env.exception_check()?);
+        jni_env.delete_local_ref(*class)?;
+
+        assert!(run_main("java.lang.Object", &[]).is_err());
+        assert!(run_main("com.purplefrog.NoSuchClass", &[]).is_err());
+        Ok(())
+    }
+}
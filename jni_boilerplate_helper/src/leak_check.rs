@@ -0,0 +1,550 @@
+//! Counting local references, to find the bindings and conversions that leak them.
+//!
+//! Every generated binding runs between `LeakCheck::start()` and `LeakCheck::finish()`, which do nothing
+//! unless the `leak_check` feature is on.  With it, the first binding to run has JVMTI swap JNI's function table
+//! for one that counts, on each thread, the local references made by the functions that return them and
+//! freed by `DeleteLocalRef` and `PopLocalFrame`.  A binding that leaves more references behind than its
+//! result holds is logged as a warning, with its name, and tests can check any block of code with
+//! `leaked_local_refs()`:
+//!
+//! ```no_run
+//! # use jni_old as jni;
+//! # #[cfg(feature = "leak_check")]
+//! # fn check(jni_env: &jni::JNIEnv) -> Result<(), jni::errors::Error> {
+//! use jni_boilerplate_helper::leak_check::leaked_local_refs;
+//!
+//! let leaked = leaked_local_refs(jni_env, || {
+//!     let text = jni_env.new_string("counted")?;
+//!     jni_env.delete_local_ref(*text)
+//! })?;
+//! assert_eq!(0, leaked);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the functions that take `jvalue` arrays are counted, which are the ones the jni crate calls;
+//! references made through the variadic and `va_list` ones are not.  Counts are only meaningful within
+//! a single call: the references of a native method are freed behind the counters' backs when it returns.
+//! `-Xcheck:jni` swaps the function table as well, so under it leak checking stays off, with a warning.
+
+use crate::jni;
+use jni::objects::JValue;
+use jni::JNIEnv;
+
+/// What a binding's leak check needs to remember between `start()` and `finish()`
+pub struct LeakCheck {
+    #[cfg(feature = "leak_check")]
+    started: Option<Started>,
+}
+
+#[cfg(feature = "leak_check")]
+struct Started {
+    live: isize,
+    /// where the references deleted during the binding start, in `counting`'s record of them
+    mark: usize,
+    type_name: &'static str,
+    java_name: &'static str,
+}
+
+impl LeakCheck {
+    /// before a binding of `java_name` on the rust type `type_name` does anything
+    #[cfg(feature = "leak_check")]
+    pub fn start(jni_env: &JNIEnv, type_name: &'static str, java_name: &'static str) -> LeakCheck {
+        let started = if counting::enabled(jni_env) {
+            Some(Started {
+                live: counting::live(),
+                mark: counting::begin(),
+                type_name,
+                java_name,
+            })
+        } else {
+            None
+        };
+        LeakCheck { started }
+    }
+
+    #[cfg(not(feature = "leak_check"))]
+    #[inline(always)]
+    pub fn start(
+        _jni_env: &JNIEnv,
+        _type_name: &'static str,
+        _java_name: &'static str,
+    ) -> LeakCheck {
+        LeakCheck {}
+    }
+
+    /// After the binding has converted `result`, the value its java call returned.
+    /// The result's reference counts as kept unless the conversion deleted it, as a `String`'s does.
+    #[cfg(feature = "leak_check")]
+    pub fn finish(self, result: JValue) {
+        if let (Some(started), Some(leaked)) = (&self.started, self.leaked(result)) {
+            if leaked > 0 {
+                log::warn!(
+                    "{}::{} left {} local reference(s) behind",
+                    started.type_name,
+                    started.java_name,
+                    leaked
+                );
+            }
+        }
+    }
+
+    /// the references made since `start()` and still live, less the result's if it was kept
+    #[cfg(feature = "leak_check")]
+    fn leaked(&self, result: JValue) -> Option<isize> {
+        let started = self.started.as_ref()?;
+        let kept = match result {
+            JValue::Object(object)
+                if !object.is_null() && !counting::deleted(started.mark, *object) =>
+            {
+                1
+            }
+            _ => 0,
+        };
+        Some(counting::live() - started.live - kept)
+    }
+
+    #[cfg(not(feature = "leak_check"))]
+    #[inline(always)]
+    pub fn finish(self, _result: JValue) {}
+}
+
+/// a binding that failed before `finish()` stops recording deletions too
+#[cfg(feature = "leak_check")]
+impl Drop for LeakCheck {
+    fn drop(&mut self) {
+        if let Some(started) = &self.started {
+            counting::end(started.mark);
+        }
+    }
+}
+
+/// The local references made by `f` on this thread, less those it freed.
+/// Fails if leak checking is not possible, such as under `-Xcheck:jni`.
+#[cfg(feature = "leak_check")]
+pub fn leaked_local_refs<F>(jni_env: &JNIEnv, f: F) -> Result<isize, jni::errors::Error>
+where
+    F: FnOnce() -> Result<(), jni::errors::Error>,
+{
+    if !counting::enabled(jni_env) {
+        return Err(counting::unavailable());
+    }
+    let before = counting::live();
+    f()?;
+    Ok(counting::live() - before)
+}
+
+#[cfg(feature = "leak_check")]
+mod counting {
+    use crate::jni;
+    use crate::ConvertJValueToRust;
+    use jni::errors::Error;
+    use jni::sys::{
+        jboolean, jbyte, jchar, jclass, jfieldID, jint, jlong, jmethodID, jobject, jsize, jvalue,
+        JNINativeInterface_, JNI_OK,
+    };
+    use jni::JNIEnv;
+    use std::cell::{Cell, RefCell};
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+    use std::sync::OnceLock;
+
+    const JVMTI_VERSION_1_2: jint = 0x3001_0200;
+    // Indices into the JVMTI function table.  The JVM Tool Interface specification numbers its functions
+    // from 1, the first being reserved (https://docs.oracle.com/javase/8/docs/platform/jvmti/jvmti.html,
+    // and the comments in jvmti.h), so each index is the function's number less one.
+    /// Deallocate, function 47
+    const JVMTI_DEALLOCATE: usize = 46;
+    /// SetJNIFunctionTable, function 120
+    const JVMTI_SET_JNI_FUNCTION_TABLE: usize = 119;
+    /// GetJNIFunctionTable, function 121
+    const JVMTI_GET_JNI_FUNCTION_TABLE: usize = 120;
+
+    type JvmtiEnv = *const *const c_void;
+    type Deallocate = unsafe extern "system" fn(*mut JvmtiEnv, *mut u8) -> jint;
+    type SetJniFunctionTable =
+        unsafe extern "system" fn(*mut JvmtiEnv, *const JNINativeInterface_) -> jint;
+    type GetJniFunctionTable =
+        unsafe extern "system" fn(*mut JvmtiEnv, *mut *mut JNINativeInterface_) -> jint;
+
+    /// the JVM's own functions, stored before the counting table is swapped in
+    static ORIGINAL: OnceLock<Original> = OnceLock::new();
+
+    /// whether the counting table was swapped in
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+        /// the counts of the enclosing local frames
+        static FRAMES: RefCell<Vec<isize>> = const { RefCell::new(Vec::new()) };
+        /// how many bindings' checks are running
+        static CHECKS: Cell<usize> = const { Cell::new(0) };
+        /// the references `DeleteLocalRef` freed while any were, so they can tell whether they kept their results
+        static DELETED: RefCell<Vec<jobject>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn live() -> isize {
+        LIVE.with(Cell::get)
+    }
+
+    /// starts recording deletions for a binding's check, returning the mark to pass to `deleted()` and `end()`
+    pub fn begin() -> usize {
+        CHECKS.with(|checks| checks.set(checks.get() + 1));
+        DELETED.with(|deleted| deleted.borrow().len())
+    }
+
+    /// whether `object` was deleted since `mark`
+    pub fn deleted(mark: usize, object: jobject) -> bool {
+        DELETED.with(|deleted| deleted.borrow()[mark..].contains(&object))
+    }
+
+    /// forgets the deletions since `mark`, and stops recording them if no other check is running
+    pub fn end(mark: usize) {
+        CHECKS.with(|checks| checks.set(checks.get() - 1));
+        DELETED.with(|deleted| deleted.borrow_mut().truncate(mark));
+    }
+
+    /// whether references are being counted, swapping the function table first if nobody has tried yet
+    pub fn enabled(jni_env: &JNIEnv) -> bool {
+        *INSTALLED.get_or_init(|| match install(jni_env) {
+            Ok(installed) => installed,
+            Err(e) => {
+                log::warn!("unable to count local references: {:?}", e);
+                false
+            }
+        })
+    }
+
+    fn install(jni_env: &JNIEnv) -> Result<bool, Error> {
+        if check_jni(jni_env)? {
+            log::warn!(
+                "-Xcheck:jni has its own JNI function table, so local references are not counted"
+            );
+            return Ok(false);
+        }
+
+        let java_vm = jni_env.get_java_vm()?.get_java_vm_pointer();
+        let mut jvmti: *mut JvmtiEnv = ptr::null_mut();
+        let get_env = unsafe { (**java_vm).GetEnv }.expect("JavaVM without GetEnv");
+        let code = unsafe {
+            get_env(
+                java_vm,
+                &mut jvmti as *mut *mut JvmtiEnv as *mut *mut c_void,
+                JVMTI_VERSION_1_2,
+            )
+        };
+        if code != JNI_OK || jvmti.is_null() {
+            log::warn!(
+                "no JVMTI environment ({}), so local references are not counted",
+                code
+            );
+            return Ok(false);
+        }
+
+        unsafe {
+            let functions = *jvmti;
+            let get_table: GetJniFunctionTable =
+                std::mem::transmute(*functions.add(JVMTI_GET_JNI_FUNCTION_TABLE));
+            let set_table: SetJniFunctionTable =
+                std::mem::transmute(*functions.add(JVMTI_SET_JNI_FUNCTION_TABLE));
+            let deallocate: Deallocate = std::mem::transmute(*functions.add(JVMTI_DEALLOCATE));
+
+            let mut table: *mut JNINativeInterface_ = ptr::null_mut();
+            if get_table(jvmti, &mut table) != JNI_OK {
+                return Ok(false);
+            }
+            let mut counting = *table;
+            deallocate(jvmti, table as *mut u8);
+
+            let Some(original) = Original::from_table(&counting) else {
+                log::warn!("the JNI function table lacks functions to count with, so local references are not counted");
+                return Ok(false);
+            };
+            // the counting functions call through ORIGINAL, so it must be there before any of them can run
+            ORIGINAL.get_or_init(|| original);
+            counting_functions(&mut counting);
+            Ok(set_table(jvmti, &counting) == JNI_OK)
+        }
+    }
+
+    fn check_jni(jni_env: &JNIEnv) -> Result<bool, Error> {
+        let bean = jni_env
+            .call_static_method(
+                "java/lang/management/ManagementFactory",
+                "getRuntimeMXBean",
+                "()Ljava/lang/management/RuntimeMXBean;",
+                &[],
+            )?
+            .l()?;
+        let arguments = jni_env
+            .call_method(bean, "getInputArguments", "()Ljava/util/List;", &[])?
+            .l()?;
+        jni_env.delete_local_ref(bean)?;
+        let arguments = jni_env.call_method(arguments, "toString", "()Ljava/lang/String;", &[])?;
+        Ok(String::to_rust(jni_env, arguments)?.contains("-Xcheck:jni"))
+    }
+
+    pub fn unavailable() -> Error {
+        unavailable_error()
+    }
+
+    #[cfg(any(feature = "jni_0_18", feature = "jni_0_20"))]
+    fn unavailable_error() -> Error {
+        Error::MethodNotFound {
+            name: String::from("SetJNIFunctionTable"),
+            sig: String::from("local references are not being counted"),
+        }
+    }
+
+    #[cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+    fn unavailable_error() -> Error {
+        Error::from_kind(jni::errors::ErrorKind::MethodNotFound(
+            String::from("SetJNIFunctionTable"),
+            String::from("local references are not being counted"),
+        ))
+    }
+
+    /// The functions the counting ones call through to.  `install()` stores them before swapping the tables,
+    /// so none of the counting functions can run without them.
+    fn original() -> &'static Original {
+        match ORIGINAL.get() {
+            Some(original) => original,
+            None => std::process::abort(),
+        }
+    }
+
+    fn created(object: jobject) -> jobject {
+        if !object.is_null() {
+            LIVE.with(|live| live.set(live.get() + 1));
+        }
+        object
+    }
+
+    /// the functions that return a new local reference
+    macro_rules! counting {
+        ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+            /// the JVM's own versions of the functions the counting table replaces
+            #[allow(non_snake_case)]
+            struct Original {
+                $( $name: unsafe extern "system" fn(*mut jni::sys::JNIEnv, $($ty),*) -> jobject, )*
+                DeleteLocalRef: unsafe extern "system" fn(*mut jni::sys::JNIEnv, jobject),
+                PushLocalFrame: unsafe extern "system" fn(*mut jni::sys::JNIEnv, jint) -> jint,
+                PopLocalFrame: unsafe extern "system" fn(*mut jni::sys::JNIEnv, jobject) -> jobject,
+            }
+
+            impl Original {
+                /// the functions in `table`, unless it lacks any of them
+                fn from_table(table: &JNINativeInterface_) -> Option<Original> {
+                    Some(Original {
+                        $( $name: table.$name?, )*
+                        DeleteLocalRef: table.DeleteLocalRef?,
+                        PushLocalFrame: table.PushLocalFrame?,
+                        PopLocalFrame: table.PopLocalFrame?,
+                    })
+                }
+            }
+
+            $(
+                #[allow(non_snake_case)]
+                unsafe extern "system" fn $name(env: *mut jni::sys::JNIEnv, $($arg: $ty),*) -> jobject {
+                    created((original().$name)(env, $($arg),*))
+                }
+            )*
+
+            fn counting_functions(table: &mut JNINativeInterface_) {
+                $( table.$name = Some($name); )*
+                table.DeleteLocalRef = Some(DeleteLocalRef);
+                table.PushLocalFrame = Some(PushLocalFrame);
+                table.PopLocalFrame = Some(PopLocalFrame);
+            }
+        };
+    }
+
+    counting! {
+        DefineClass(name: *const c_char, loader: jobject, buf: *const jbyte, len: jsize);
+        FindClass(name: *const c_char);
+        ToReflectedMethod(cls: jclass, method_id: jmethodID, is_static: jboolean);
+        GetSuperclass(sub: jclass);
+        ToReflectedField(cls: jclass, field_id: jfieldID, is_static: jboolean);
+        ExceptionOccurred();
+        NewLocalRef(object: jobject);
+        AllocObject(class: jclass);
+        NewObjectA(class: jclass, method_id: jmethodID, args: *const jvalue);
+        GetObjectClass(object: jobject);
+        CallObjectMethodA(object: jobject, method_id: jmethodID, args: *const jvalue);
+        CallNonvirtualObjectMethodA(object: jobject, class: jclass, method_id: jmethodID, args: *const jvalue);
+        GetObjectField(object: jobject, field_id: jfieldID);
+        CallStaticObjectMethodA(class: jclass, method_id: jmethodID, args: *const jvalue);
+        GetStaticObjectField(class: jclass, field_id: jfieldID);
+        NewString(unicode: *const jchar, len: jsize);
+        NewStringUTF(utf: *const c_char);
+        NewObjectArray(len: jsize, class: jclass, init: jobject);
+        GetObjectArrayElement(array: jobject, index: jsize);
+        NewBooleanArray(len: jsize);
+        NewByteArray(len: jsize);
+        NewCharArray(len: jsize);
+        NewShortArray(len: jsize);
+        NewIntArray(len: jsize);
+        NewLongArray(len: jsize);
+        NewFloatArray(len: jsize);
+        NewDoubleArray(len: jsize);
+        NewDirectByteBuffer(address: *mut c_void, capacity: jlong);
+    }
+
+    #[allow(non_snake_case)]
+    unsafe extern "system" fn DeleteLocalRef(env: *mut jni::sys::JNIEnv, object: jobject) {
+        if !object.is_null() {
+            LIVE.with(|live| live.set(live.get() - 1));
+            if CHECKS.with(Cell::get) > 0 {
+                DELETED.with(|deleted| deleted.borrow_mut().push(object));
+            }
+        }
+        (original().DeleteLocalRef)(env, object)
+    }
+
+    #[allow(non_snake_case)]
+    unsafe extern "system" fn PushLocalFrame(env: *mut jni::sys::JNIEnv, capacity: jint) -> jint {
+        let code = (original().PushLocalFrame)(env, capacity);
+        if code == JNI_OK {
+            FRAMES.with(|frames| frames.borrow_mut().push(live()));
+        }
+        code
+    }
+
+    #[allow(non_snake_case)]
+    unsafe extern "system" fn PopLocalFrame(
+        env: *mut jni::sys::JNIEnv,
+        result: jobject,
+    ) -> jobject {
+        let result = (original().PopLocalFrame)(env, result);
+        // a frame pushed before counting began leaves the count alone
+        if let Some(outer) = FRAMES.with(|frames| frames.borrow_mut().pop()) {
+            LIVE.with(|live| live.set(outer));
+        }
+        created(result)
+    }
+}
+
+#[cfg(all(test, feature = "leak_check"))]
+mod test {
+    use super::{leaked_local_refs, LeakCheck};
+    use crate::java_any::JavaAny;
+    use crate::jni;
+    use crate::local_frame::with_local_frame;
+    use crate::test_jvm;
+    use crate::wrap_jobject;
+    use crate::ConvertJValueToRust;
+    use jni::errors::Error;
+    use jni::objects::JValue;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn counts() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let leaked = leaked_local_refs(&jni_env, || {
+            jni_env.new_string("left behind")?;
+            // looking the class up by name leaves its reference behind
+            let text = jni_env.call_static_method(
+                "java/lang/String",
+                "valueOf",
+                "(I)Ljava/lang/String;",
+                &[JValue::Int(42)],
+            )?;
+            assert_eq!("42", String::to_rust(&jni_env, text)?);
+            Ok(())
+        })?;
+        assert_eq!(2, leaked);
+
+        let leaked = leaked_local_refs(&jni_env, || {
+            let text = jni_env.new_string("four")?;
+            let upper = jni_env.call_method(*text, "toUpperCase", "()Ljava/lang/String;", &[])?;
+            assert_eq!("FOUR", String::to_rust(&jni_env, upper)?);
+            jni_env.delete_local_ref(*text)?;
+            with_local_frame(&jni_env, 4, |jni_env| {
+                jni_env.new_string("framed")?;
+                Ok(())
+            })
+        })?;
+        assert_eq!(0, leaked);
+
+        let leaked = leaked_local_refs(&jni_env, || {
+            let kept = with_local_frame(&jni_env, 4, |jni_env| {
+                jni_env.new_string("dropped")?;
+                Ok(*jni_env.new_string("kept")?)
+            })?;
+            assert!(!kept.is_null());
+            Ok(())
+        })?;
+        assert_eq!(1, leaked);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn kept_results() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let call = |check: &LeakCheck| {
+            let text = jni_env.new_string("four")?;
+            let upper = jni_env.call_method(*text, "toUpperCase", "()Ljava/lang/String;", &[])?;
+            jni_env.delete_local_ref(*text)?;
+            Ok::<_, Error>((check.leaked(upper), upper))
+        };
+
+        // a String's conversion deletes the result, which leaves nothing to count as kept
+        let check = LeakCheck::start(&jni_env, "String", "toUpperCase");
+        let (_, upper) = call(&check)?;
+        assert_eq!("FOUR", String::to_rust(&jni_env, upper)?);
+        assert_eq!(Some(0), check.leaked(upper));
+        check.finish(upper);
+
+        // a wrapper keeps it
+        let check = LeakCheck::start(&jni_env, "String", "toUpperCase");
+        let (leaked, upper) = call(&check)?;
+        assert_eq!(Some(0), leaked);
+        jni_env.delete_local_ref(upper.l()?)?;
+        check.finish(upper);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn panic_pops_the_frame() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let leaked = leaked_local_refs(&jni_env, || {
+            let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+                with_local_frame(&jni_env, 4, |jni_env| -> Result<(), Error> {
+                    jni_env.new_string("dropped")?;
+                    panic!("in the frame");
+                })
+            }));
+            assert!(panicked.is_err());
+            Ok(())
+        })?;
+        assert_eq!(0, leaked);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn large_arrays() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        // each element is converted in a frame of its own, whatever the conversion leaves behind
+        let leaked = leaked_local_refs(&jni_env, || {
+            let class = jni_env.find_class("java/lang/Object")?;
+            let text = jni_env.new_string("element")?;
+            let array = jni_env.new_object_array(50_000, class, *text)?;
+            jni_env.delete_local_ref(*text)?;
+            jni_env.delete_local_ref(*class)?;
+            let elements = Vec::<JavaAny>::to_rust(&jni_env, JValue::Object(wrap_jobject(array)))?;
+            assert_eq!(50_000, elements.len());
+            assert_eq!(Some("element"), elements[49_999].as_string());
+            Ok(())
+        })?;
+        assert_eq!(0, leaked);
+        Ok(())
+    }
+}
@@ -10,8 +10,8 @@ use jni_020plus as jni;
 use jni_old as jni;
 
 use crate::array_copy_back::*;
+use crate::exception_reporting::{exception_reporting, ExceptionReporting};
 use java_runtime_wrappers::class_is_array;
-use java_runtime_wrappers::Throwable;
 use jni::errors::Error;
 use jni::objects::{AutoLocal, JClass, JObject, JValue};
 use jni::sys::{
@@ -19,9 +19,30 @@ use jni::sys::{
     jobjectArray, jshortArray, jsize,
 };
 use jni::JNIEnv;
+use local_frame::{with_local_frame, LeaveFrame};
 
 pub mod array_copy_back;
+pub mod erased;
+pub mod exception_reporting;
+pub mod global;
+pub mod inheritance;
+pub mod java_any;
+pub mod java_callback;
+pub mod java_enum;
 pub mod java_runtime_wrappers;
+pub mod java_views;
+pub mod jni_symbol;
+pub mod launch;
+pub mod leak_check;
+pub mod local_frame;
+pub mod native_method;
+pub mod nonvirtual;
+pub mod object_methods;
+pub mod overload_names;
+pub mod register_natives;
+#[cfg(any(test, feature = "test_jvm"))]
+pub mod test_jvm;
+pub mod thread_env;
 
 pub struct JClassWrapper<'a, 'b> {
     pub jni_env: &'a JNIEnv<'a>,
@@ -429,49 +450,54 @@ impl ConvertJValueToRust<'_, '_> for Vec<f64> {
 }
 
 /// does not free the resources referenced by src
-pub fn convert_jvalue_list_or_array_to_rust<'a, 'b, T>(
+pub fn convert_jvalue_list_or_array_to_rust<'a: 'b, 'b, T>(
     je: &'b JNIEnv<'a>,
     src: JObject<'a>,
 ) -> Result<Vec<T>, jni::errors::Error>
 where
-    T: ConvertJValueToRust<'a, 'b>,
+    T: ConvertJValueToRust<'a, 'b> + LeaveFrame<'a, 'b>,
 {
     //println!("convert_jvalue_list_or_array_to_rust");
 
-    let cls = je.get_object_class(src)?;
-    if class_is_array(je, &cls)? {
+    let cls = AutoLocal::new(je, *je.get_object_class(src)?);
+    if class_is_array(je, &cls.as_obj())? {
         convert_jarray_to_rust(je, src)
     } else {
         convert_iterable_to_rust_vec(je, src)
     }
 }
 
-pub fn convert_jarray_to_rust<'a, 'b, T>(
+/// Each element is converted in a local frame of its own, so a large array does not pile up references.
+pub fn convert_jarray_to_rust<'a: 'b, 'b, T>(
     je: &'b JNIEnv<'a>,
     array: JObject,
 ) -> Result<Vec<T>, jni::errors::Error>
 where
-    T: ConvertJValueToRust<'a, 'b>,
+    T: ConvertJValueToRust<'a, 'b> + LeaveFrame<'a, 'b>,
 {
     let count = je.get_array_length(*array)?;
     let mut rval: Vec<T> = Vec::new();
     for i in 0..count {
-        let obj_i = je.get_object_array_element(*array, i)?;
-        let val: T = T::to_rust(je, JValue::from(obj_i))?;
+        let val: T = with_local_frame(je, 4, |je| {
+            let obj_i = je.get_object_array_element(*array, i)?;
+            T::to_rust(je, JValue::from(obj_i))
+        })?;
         rval.push(val);
     }
     Ok(rval)
 }
 
-pub fn convert_iterable_to_rust_vec<'a, 'b, T>(
+/// Like `convert_jarray_to_rust()`, each element is converted in a local frame of its own.
+pub fn convert_iterable_to_rust_vec<'a: 'b, 'b, T>(
     je: &'b JNIEnv<'a>,
     iterable: JObject<'a>,
 ) -> Result<Vec<T>, jni::errors::Error>
 where
-    T: ConvertJValueToRust<'a, 'b>,
+    T: ConvertJValueToRust<'a, 'b> + LeaveFrame<'a, 'b>,
 {
     let iter = je.call_method(iterable, "iterator", "()Ljava/util/Iterator;", &[])?;
-    let iter = iter.l()?;
+    let iter = AutoLocal::new(je, iter.l()?);
+    let iter = iter.as_obj();
 
     let mut rval: Vec<T> = Vec::new();
     loop {
@@ -479,13 +505,13 @@ where
         if !has_next.z()? {
             break;
         }
-        let val = je.call_method(iter, "next", "()Ljava/lang/Object;", &[])?;
-        let val: T = T::to_rust(je, val)?;
+        let val: T = with_local_frame(je, 4, |je| {
+            let val = je.call_method(iter, "next", "()Ljava/lang/Object;", &[])?;
+            T::to_rust(je, val)
+        })?;
         rval.push(val);
     }
 
-    je.delete_local_ref(iter)?;
-
     Ok(rval)
 }
 
@@ -512,15 +538,13 @@ impl JValueNonScalar for String {}
 impl<T> JValueNonScalar for Vec<T> {}
 impl<T> JValueNonScalar for &[T] {}
 
-impl<'a, 'b, T: JValueNonScalar + ConvertJValueToRust<'a, 'b>> ConvertJValueToRust<'a, 'b>
-    for Vec<T>
+impl<'a: 'b, 'b, T> ConvertJValueToRust<'a, 'b> for Vec<T>
+where
+    T: JValueNonScalar + ConvertJValueToRust<'a, 'b> + LeaveFrame<'a, 'b>,
 {
     fn to_rust(je: &'b JNIEnv<'a>, val: JValue<'a>) -> Result<Self, jni::errors::Error> {
-        let jobject: JObject<'a> = val.l()?;
-        let rval = convert_jvalue_list_or_array_to_rust(je, jobject)?;
-        je.delete_local_ref(jobject)?;
-
-        Ok(rval)
+        let jobject = AutoLocal::new(je, val.l()?);
+        convert_jvalue_list_or_array_to_rust(je, jobject.as_obj())
     }
 }
 
@@ -638,6 +662,16 @@ pub fn wrap_jobject<'a>(rval: jobject) -> JObject<'a> {
     unsafe { JObject::from_raw(rval) }
 }
 
+#[cfg(not(feature = "jni_0_20"))]
+pub fn unwrap_jobject(obj: JObject) -> jobject {
+    obj.into_inner()
+}
+
+#[cfg(feature = "jni_0_20")]
+pub fn unwrap_jobject(obj: JObject) -> jobject {
+    obj.into_raw()
+}
+
 impl<'a: 'b, 'b> ConvertRustToJValue<'a, 'b> for &[char] {
     type T = AutoLocal<'a, 'b>;
     fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<AutoLocal<'a, 'b>, jni::errors::Error> {
@@ -687,7 +721,7 @@ impl<'a: 'b, 'b> ConvertRustToJValue<'a, 'b> for &str {
 impl<'a: 'b, 'b> ConvertRustToJValue<'a, 'b> for String {
     type T = AutoLocal<'a, 'b>;
     fn into_temporary(&self, je: &'b JNIEnv<'a>) -> Result<AutoLocal<'a, 'b>, jni::errors::Error> {
-        let rval = je.new_string(&self)?;
+        let rval = je.new_string(self)?;
         Ok(AutoLocal::new(je, JObject::from(rval)))
     }
     fn temporary_into_jvalue(tmp: &AutoLocal<'a, 'b>) -> JValue<'a> {
@@ -1001,11 +1035,10 @@ where
     }
 }
 */
-impl<'a: 'b, 'b, 'c, S> ConvertRustToJValue<'a, 'b> for &[S]
+impl<'a: 'b, 'b, S> ConvertRustToJValue<'a, 'b> for &[S]
 where
     S: ConvertRustToJValue<'a, 'b>,
     S: JavaClassNameFor + JValueNonScalar, // I need JValueNonScalar to void conflicting with &[i8] and friends
-    Self: 'c,
 {
     type T = AutoLocal<'a, 'b>;
 
@@ -1074,18 +1107,24 @@ pub trait JavaConstructible<'a, 'b> {
 
 //
 
+/// If a java exception is pending, clear it, hand it to the process-wide `ExceptionReporting` policy,
+/// and return `Err(JavaException)`.
 pub fn raise_if_exception(jni_env: &JNIEnv) -> Result<(), Error> {
+    raise_if_exception_with(jni_env, &exception_reporting())
+}
+
+/// Like `raise_if_exception()`, but with a specific reporting policy instead of the process-wide one.
+pub fn raise_if_exception_with(
+    jni_env: &JNIEnv,
+    reporting: &ExceptionReporting,
+) -> Result<(), Error> {
     match jni_env.exception_check() {
         Ok(boom) => {
             if boom {
                 if let Ok(throwable) = jni_env.exception_occurred() {
                     jni_env.exception_clear()?;
-                    #[cfg(debug_assertions)]
-                    {
-                        let t2 =
-                            Throwable::wrap_jobject(jni_env, AutoLocal::new(jni_env, *throwable));
-                        let _ = t2.printStackTrace();
-                    }
+                    let throwable = AutoLocal::new(jni_env, *throwable);
+                    reporting.report(jni_env, throwable.as_obj());
                 } else {
                     jni_env.exception_clear()?;
                 }
@@ -1104,6 +1143,17 @@ pub fn raise_if_exception(jni_env: &JNIEnv) -> Result<(), Error> {
 /// If the Result is an Err, and jni_env.exception_check() reports true, we will call jni_env.exception_clear()
 pub trait ClearIfErr<T> {
     fn clear_if_err(self, jni_env: &JNIEnv) -> Result<T, Error>;
+    /// like `clear_if_err()` but with a specific `ExceptionReporting` policy.
+    /// The default reports (and clears) a pending exception with `reporting` first,
+    /// so `clear_if_err()` finds none left to report with the process-wide policy.
+    fn clear_if_err_with(self, jni_env: &JNIEnv, reporting: &ExceptionReporting) -> Result<T, Error>
+    where
+        Self: Sized,
+    {
+        // Err(JavaException) here only means there was an exception, which `self` already accounts for
+        let _ = raise_if_exception_with(jni_env, reporting);
+        self.clear_if_err(jni_env)
+    }
 }
 
 impl<T> ClearIfErr<T> for Result<T, Error> {
@@ -1116,6 +1166,20 @@ impl<T> ClearIfErr<T> for Result<T, Error> {
             }
         }
     }
+
+    fn clear_if_err_with(
+        self,
+        jni_env: &JNIEnv,
+        reporting: &ExceptionReporting,
+    ) -> Result<T, Error> {
+        match self {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                raise_if_exception_with(jni_env, reporting)?;
+                Err(e)
+            }
+        }
+    }
 }
 
 //
@@ -1134,9 +1198,12 @@ impl<T> ClearIfErr<T> for Result<T, Error> {
 ///
 /// ` jni_wrapper_cliche_impl! { rust_type_name, "package/path/to/java/class" }`
 ///
+/// To write the struct yourself (with other fields, derives or docs) use `#[derive(JavaWrapper)]` from `jni_boilerplate` instead.
+///
 #[macro_export]
 macro_rules! jni_wrapper_cliche_impl {
     ($ty:ident, $java_class_slash:literal) => {
+        #[repr(C)]
         pub struct $ty<'a: 'b, 'b> {
             #[allow(dead_code)]
             java_this: jni::objects::AutoLocal<'a, 'b>,
@@ -1144,6 +1211,27 @@ macro_rules! jni_wrapper_cliche_impl {
             jni_env: &'b jni::JNIEnv<'a>,
         }
 
+        // a repr(C) AutoLocal and &JNIEnv
+        unsafe impl<'a, 'b> $crate::inheritance::PlainJavaWrapper for $ty<'a, 'b> {}
+
+        impl<'a: 'b, 'b> $crate::inheritance::JavaObjectWrapper<'a, 'b> for $ty<'a, 'b> {
+            fn jni_env(&self) -> &'b jni::JNIEnv<'a> {
+                self.jni_env
+            }
+
+            fn java_object(&self) -> jni::objects::JObject<'a> {
+                $crate::wrap_jobject(*self.java_this.as_obj())
+            }
+
+            fn into_parts(self) -> (&'b jni::JNIEnv<'a>, jni::objects::AutoLocal<'a, 'b>) {
+                (self.jni_env, self.java_this)
+            }
+        }
+
+        impl<'a: 'b, 'b, 'c: 'd, 'd> $crate::global::WrapperFor<'a, 'b> for $ty<'c, 'd> {
+            type Wrapper = $ty<'a, 'b>;
+        }
+
         impl<'a, 'b> $ty<'a, 'b> {
             pub fn null(jni_env: &'b jni::JNIEnv<'a>) -> $ty<'a, 'b> {
                 $ty {
@@ -1155,7 +1243,7 @@ macro_rules! jni_wrapper_cliche_impl {
 
         impl<'a, 'b> $crate::JValueNonScalar for $ty<'a, 'b> {}
 
-        impl<'a, 'b> jni_boilerplate_helper::JavaClassNameFor for $ty<'a, 'b> {
+        impl<'a, 'b> $crate::JavaClassNameFor for $ty<'a, 'b> {
             fn java_class_name() -> &'static str {
                 $java_class_slash
             }
@@ -1227,6 +1315,7 @@ macro_rules! jni_wrapper_cliche_impl {
 #[macro_export]
 macro_rules! jni_wrapper_cliche_impl_T {
     ($ty:ident, $java_class_slash:literal) => {
+        #[repr(C)]
         pub struct $ty<'a: 'b, 'b, T: ConvertJValueToRust<'a, 'b>> {
             #[allow(dead_code)]
             java_this: jni::objects::AutoLocal<'a, 'b>,
@@ -1235,6 +1324,28 @@ macro_rules! jni_wrapper_cliche_impl_T {
             phantom: PhantomData<T>,
         }
 
+        // a repr(C) AutoLocal and &JNIEnv, and a zero-sized PhantomData
+        unsafe impl<'a, 'b, T: ConvertJValueToRust<'a, 'b>> $crate::inheritance::PlainJavaWrapper
+            for $ty<'a, 'b, T>
+        {
+        }
+
+        impl<'a: 'b, 'b, T: ConvertJValueToRust<'a, 'b>>
+            $crate::inheritance::JavaObjectWrapper<'a, 'b> for $ty<'a, 'b, T>
+        {
+            fn jni_env(&self) -> &'b jni::JNIEnv<'a> {
+                self.jni_env
+            }
+
+            fn java_object(&self) -> jni::objects::JObject<'a> {
+                $crate::wrap_jobject(*self.java_this.as_obj())
+            }
+
+            fn into_parts(self) -> (&'b jni::JNIEnv<'a>, jni::objects::AutoLocal<'a, 'b>) {
+                (self.jni_env, self.java_this)
+            }
+        }
+
         impl<'a, 'b, T: ConvertJValueToRust<'a, 'b>> $ty<'a, 'b, T> {
             pub fn null(jni_env: &'b jni::JNIEnv<'a>) -> $ty<'a, 'b, T> {
                 $ty {
@@ -1247,9 +1358,7 @@ macro_rules! jni_wrapper_cliche_impl_T {
 
         impl<'a, 'b, T: ConvertJValueToRust<'a, 'b>> $crate::JValueNonScalar for $ty<'a, 'b, T> {}
 
-        impl<'a, 'b, T: ConvertJValueToRust<'a, 'b>> jni_boilerplate_helper::JavaClassNameFor
-            for $ty<'a, 'b, T>
-        {
+        impl<'a, 'b, T: ConvertJValueToRust<'a, 'b>> $crate::JavaClassNameFor for $ty<'a, 'b, T> {
             fn java_class_name() -> &'static str {
                 $java_class_slash
             }
@@ -0,0 +1,210 @@
+//! Local reference frames, so that a thread which never returns to java does not pile up local references.
+//!
+//! A native method's local references are freed when it returns to java, but a rust thread that calls java
+//! in a loop has nothing to free them.  `with_local_frame()` frees every local reference made in a scope,
+//! except the one in the value the scope returns:
+//!
+//! ```
+//! # use jni_old as jni;
+//! use jni::errors::Error;
+//! use jni::JNIEnv;
+//! use jni_boilerplate_helper::local_frame::with_local_frame;
+//!
+//! fn total_length(jni_env: &JNIEnv, words: &[&str]) -> Result<i32, Error> {
+//!     let mut total = 0;
+//!     for word in words {
+//!         // the string and the call's leftovers are freed at the end of each round
+//!         total += with_local_frame(jni_env, 4, |jni_env| {
+//!             let word = jni_env.new_string(*word)?;
+//!             jni_env.call_method(*word, "length", "()I", &[])?.i()
+//!         })?;
+//!     }
+//!     Ok(total)
+//! }
+//! ```
+//!
+//! The generated bindings run each call in a frame of its own, and hand back only the converted result.
+
+use crate::inheritance::JavaObjectWrapper;
+use crate::jni;
+use crate::JavaConstructible;
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JObject, JValue};
+use jni::JNIEnv;
+
+/// A value that can leave a local reference frame: it holds at most one local reference,
+/// which `with_local_frame` moves into the enclosing frame.
+pub trait LeaveFrame<'a: 'b, 'b>: Sized {
+    /// everything but the local reference
+    type Rest;
+
+    /// Splits off the local reference (a null when there is none).  It must not be deleted.
+    fn leave_frame(self) -> (JObject<'a>, Self::Rest);
+
+    /// puts the value back together around `object`, the same reference in the enclosing frame
+    fn enter_frame(jni_env: &'b JNIEnv<'a>, object: JObject<'a>, rest: Self::Rest) -> Self;
+}
+
+/// Runs `f` in a new local reference frame with room for at least `capacity` local references.
+/// Every local reference made in it is freed when it ends, except the one `f`'s result holds.
+/// On an error, or a panic in `f`, the whole frame is freed.
+///
+/// Wrappers come out as a new wrapper for the same object, so a derived wrapper's other fields start over
+/// from `Default`.  Anything else holding a local reference must not leave the frame, since it would be freed.
+pub fn with_local_frame<'a: 'b, 'b, F, R>(
+    jni_env: &'b JNIEnv<'a>,
+    capacity: i32,
+    f: F,
+) -> Result<R, Error>
+where
+    F: FnOnce(&'b JNIEnv<'a>) -> Result<R, Error>,
+    R: LeaveFrame<'a, 'b>,
+{
+    jni_env.push_local_frame(capacity)?;
+    let frame = Frame { jni_env };
+    let (object, rest) = f(jni_env)?.leave_frame();
+    let object = frame.pop(object)?;
+    Ok(R::enter_frame(jni_env, object, rest))
+}
+
+/// A pushed local frame, popped with nothing kept if it is dropped, as it is by an error or a panic
+struct Frame<'a: 'b, 'b> {
+    jni_env: &'b JNIEnv<'a>,
+}
+
+impl<'a: 'b, 'b> Frame<'a, 'b> {
+    /// pops the frame, moving `object` into the enclosing one
+    fn pop(self, object: JObject<'a>) -> Result<JObject<'a>, Error> {
+        let jni_env = self.jni_env;
+        std::mem::forget(self);
+        jni_env.pop_local_frame(object)
+    }
+}
+
+impl Drop for Frame<'_, '_> {
+    fn drop(&mut self) {
+        if let Err(e) = self.jni_env.pop_local_frame(JObject::null()) {
+            log::debug!("jni failed to pop_local_frame() : {:?}", e);
+        }
+    }
+}
+
+impl<'a: 'b, 'b> LeaveFrame<'a, 'b> for JObject<'a> {
+    type Rest = ();
+
+    fn leave_frame(self) -> (JObject<'a>, ()) {
+        (self, ())
+    }
+
+    fn enter_frame(_jni_env: &'b JNIEnv<'a>, object: JObject<'a>, _rest: ()) -> Self {
+        object
+    }
+}
+
+/// what a generated binding's java call returns
+impl<'a: 'b, 'b> LeaveFrame<'a, 'b> for JValue<'a> {
+    type Rest = JValue<'a>;
+
+    fn leave_frame(self) -> (JObject<'a>, JValue<'a>) {
+        match self {
+            JValue::Object(object) => (object, self),
+            _ => (JObject::null(), self),
+        }
+    }
+
+    fn enter_frame(_jni_env: &'b JNIEnv<'a>, object: JObject<'a>, rest: JValue<'a>) -> Self {
+        match rest {
+            JValue::Object(_) => JValue::Object(object),
+            _ => rest,
+        }
+    }
+}
+
+impl<'a: 'b, 'b, W> LeaveFrame<'a, 'b> for W
+where
+    W: JavaObjectWrapper<'a, 'b> + JavaConstructible<'a, 'b>,
+{
+    type Rest = ();
+
+    fn leave_frame(self) -> (JObject<'a>, ()) {
+        let (_jni_env, java_this) = self.into_parts();
+        (java_this.forget(), ())
+    }
+
+    fn enter_frame(jni_env: &'b JNIEnv<'a>, object: JObject<'a>, _rest: ()) -> Self {
+        W::wrap_jobject(jni_env, AutoLocal::new(jni_env, object))
+    }
+}
+
+/// values that hold no local reference
+macro_rules! leave_frame_plain {
+    ($($ty:ty),*) => {
+        $(
+            impl<'a: 'b, 'b> LeaveFrame<'a, 'b> for $ty {
+                type Rest = $ty;
+
+                fn leave_frame(self) -> (JObject<'a>, $ty) {
+                    (JObject::null(), self)
+                }
+
+                fn enter_frame(_jni_env: &'b JNIEnv<'a>, _object: JObject<'a>, rest: $ty) -> Self {
+                    rest
+                }
+            }
+        )*
+    };
+}
+
+leave_frame_plain! { (), bool, char, i8, i16, i32, i64, f32, f64, String }
+leave_frame_plain! { Vec<bool>, Vec<char>, Vec<i8>, Vec<u8>, Vec<i16>, Vec<i32>, Vec<i64>, Vec<f32>, Vec<f64>, Vec<String> }
+
+#[cfg(test)]
+mod test {
+    use super::with_local_frame;
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::{java_exception, jni_wrapper_cliche_impl, JavaConstructible};
+    use jni::errors::Error;
+    use jni::objects::{AutoLocal, JValue};
+
+    jni_wrapper_cliche_impl! { StringBuilder, "java/lang/StringBuilder" }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn values_leave_the_frame() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let builder = with_local_frame(&jni_env, 4, |jni_env| {
+            let text = jni_env.new_string("kept")?;
+            let builder = jni_env.new_object(
+                "java/lang/StringBuilder",
+                "(Ljava/lang/String;)V",
+                &[JValue::from(*text)],
+            )?;
+            Ok(StringBuilder::wrap_jobject(
+                jni_env,
+                AutoLocal::new(jni_env, builder),
+            ))
+        })?;
+        let length = jni_env
+            .call_method(builder.java_this.as_obj(), "length", "()I", &[])?
+            .i()?;
+        assert_eq!(4, length);
+
+        let failed: Result<String, Error> = with_local_frame(&jni_env, 4, |jni_env| {
+            jni_env.new_string("dropped")?;
+            Err(java_exception())
+        });
+        assert!(failed.is_err());
+
+        // thousands of references, none of which outlive their round
+        for _ in 0..10_000 {
+            let length: i32 = with_local_frame(&jni_env, 2, |jni_env| {
+                let text = jni_env.new_string("four")?;
+                jni_env.call_method(*text, "length", "()I", &[])?.i()
+            })?;
+            assert_eq!(4, length);
+        }
+        Ok(())
+    }
+}
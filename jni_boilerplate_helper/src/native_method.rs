@@ -0,0 +1,259 @@
+//! Support code for the `#[jni_native_method]` macro, which exports rust functions for java `native` methods.
+//!
+//! A panic must not unwind into the JVM, so the generated entry points run the rust side inside `catch_panic()`,
+//! which turns a panic into a java exception.  The class of that exception is `java.lang.RuntimeException`
+//! unless changed with `set_panic_exception_class()`.
+
+use crate::{jni, unwrap_jobject, wrap_jobject, JValueNonScalar};
+use jni::errors::Error;
+use jni::objects::{JObject, JValue};
+use jni::sys::{jboolean, jbyte, jchar, jdouble, jfloat, jint, jlong, jobject, jshort};
+use jni::JNIEnv;
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Once, RwLock};
+
+/// Maps a rust type onto the raw JNI type that appears in the C signature of a native method,
+/// so the generated `extern "system" fn` can hand its arguments to `ConvertJValueToRust`
+/// and its result from `ConvertRustToJValue` back to the JVM.
+pub trait JniNativeType {
+    type Raw;
+    fn raw_into_jvalue<'a>(raw: Self::Raw) -> JValue<'a>;
+    /// For object types this creates a new local reference, because the temporary that `val`
+    /// was borrowed from will usually delete its own reference when it is dropped.
+    fn jvalue_into_raw<'a>(je: &JNIEnv<'a>, val: JValue<'a>) -> Result<Self::Raw, Error>;
+    /// What to return to java when the rust side failed and an exception is pending.
+    fn default_raw() -> Self::Raw;
+}
+
+macro_rules! impl_jni_native_type {
+    ( $( $t:ty, $raw:ty, $variant:ident, $getter:ident );* ) => {
+        $(
+        impl JniNativeType for $t {
+            type Raw = $raw;
+            fn raw_into_jvalue<'a>(raw: $raw) -> JValue<'a> {
+                JValue::$variant(raw)
+            }
+            fn jvalue_into_raw<'a>(_je: &JNIEnv<'a>, val: JValue<'a>) -> Result<$raw, Error> {
+                val.$getter()
+            }
+            fn default_raw() -> $raw {
+                Default::default()
+            }
+        }
+        )*
+    };
+}
+
+impl_jni_native_type! {
+    i8, jbyte, Byte, b;
+    i16, jshort, Short, s;
+    i32, jint, Int, i;
+    i64, jlong, Long, j;
+    f32, jfloat, Float, f;
+    f64, jdouble, Double, d
+}
+
+impl JniNativeType for () {
+    type Raw = ();
+    fn raw_into_jvalue<'a>(_raw: ()) -> JValue<'a> {
+        JValue::Void
+    }
+    fn jvalue_into_raw<'a>(_je: &JNIEnv<'a>, val: JValue<'a>) -> Result<(), Error> {
+        val.v()
+    }
+    fn default_raw() {}
+}
+
+impl JniNativeType for bool {
+    type Raw = jboolean;
+    fn raw_into_jvalue<'a>(raw: jboolean) -> JValue<'a> {
+        JValue::Bool(raw)
+    }
+    fn jvalue_into_raw<'a>(_je: &JNIEnv<'a>, val: JValue<'a>) -> Result<jboolean, Error> {
+        Ok(val.z()? as jboolean)
+    }
+    fn default_raw() -> jboolean {
+        0
+    }
+}
+
+impl JniNativeType for char {
+    type Raw = jchar;
+    fn raw_into_jvalue<'a>(raw: jchar) -> JValue<'a> {
+        JValue::Char(raw)
+    }
+    fn jvalue_into_raw<'a>(_je: &JNIEnv<'a>, val: JValue<'a>) -> Result<jchar, Error> {
+        val.c()
+    }
+    fn default_raw() -> jchar {
+        0
+    }
+}
+
+impl<T: JValueNonScalar> JniNativeType for T {
+    type Raw = jobject;
+    fn raw_into_jvalue<'a>(raw: jobject) -> JValue<'a> {
+        JValue::Object(wrap_jobject(raw))
+    }
+    fn jvalue_into_raw<'a>(je: &JNIEnv<'a>, val: JValue<'a>) -> Result<jobject, Error> {
+        let obj = val.l()?;
+        if obj.is_null() {
+            Ok(unwrap_jobject(obj))
+        } else {
+            Ok(unwrap_jobject(new_local_ref(je, obj)?))
+        }
+    }
+    fn default_raw() -> jobject {
+        std::ptr::null_mut()
+    }
+}
+
+#[cfg(not(feature = "jni_0_20"))]
+pub(crate) fn new_local_ref<'a>(je: &JNIEnv<'a>, obj: JObject<'a>) -> Result<JObject<'a>, Error> {
+    je.new_local_ref::<JObject>(obj)
+}
+
+#[cfg(feature = "jni_0_20")]
+pub(crate) fn new_local_ref<'a>(je: &JNIEnv<'a>, obj: JObject<'a>) -> Result<JObject<'a>, Error> {
+    je.new_local_ref(obj)
+}
+
+/// Called by the generated native entry point when converting an argument or the result failed,
+/// or when the rust function returned an `Err`.
+/// If java does not already have a pending exception, this throws a `java.lang.RuntimeException` describing `err`.
+pub fn throw_for_native_error(je: &JNIEnv, err: &Error) {
+    if let Ok(true) = je.exception_check() {
+        return;
+    }
+    if let Err(e) = je.throw_new("java/lang/RuntimeException", format!("{}", err)) {
+        log::debug!("failed to throw RuntimeException for {:?}: {:?}", err, e);
+    }
+}
+
+static PANIC_EXCEPTION_CLASS: RwLock<Option<String>> = RwLock::new(None);
+
+/// Replace the process-wide class of the exception thrown when rust code called from java panics.
+/// `class_name` uses slashes (`com/example/RustPanic`), and the class needs a constructor taking a `String`.
+pub fn set_panic_exception_class(class_name: &str) {
+    let class_name = Some(String::from(class_name));
+    match PANIC_EXCEPTION_CLASS.write() {
+        Ok(mut guard) => *guard = class_name,
+        Err(poisoned) => *poisoned.into_inner() = class_name,
+    }
+}
+
+/// The class of the exception thrown when rust code called from java panics.
+pub fn panic_exception_class() -> String {
+    let guard = match PANIC_EXCEPTION_CLASS.read() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    guard
+        .clone()
+        .unwrap_or_else(|| String::from("java/lang/RuntimeException"))
+}
+
+thread_local! {
+    /// where the most recent panic on this thread happened, recorded by the hook `install_panic_hook()` adds
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// The payload `catch_unwind` hands back does not say where the panic happened,
+/// so we chain a panic hook that remembers the location for `catch_panic()`.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|location| location.to_string());
+            PANIC_LOCATION.with(|cell| *cell.borrow_mut() = location);
+            previous(info);
+        }));
+    });
+}
+
+/// The message of the exception thrown for a panic.
+pub fn panic_message(payload: &(dyn Any + Send), location: Option<&str>) -> String {
+    let text = if let Some(text) = payload.downcast_ref::<&str>() {
+        text
+    } else if let Some(text) = payload.downcast_ref::<String>() {
+        text.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    match location {
+        Some(location) => format!("rust panic at {}: {}", location, text),
+        None => format!("rust panic: {}", text),
+    }
+}
+
+/// Runs `body`, and if it panics throws an exception of `panic_exception_class()` instead of unwinding into the JVM.
+/// Returns `None` after a panic, when the caller should return a default value to java.
+///
+/// A java exception left pending by the panicking code is cleared, since the panic is the one to report.
+pub fn catch_panic<R, F>(je: &JNIEnv, body: F) -> Option<R>
+where
+    F: FnOnce() -> R,
+{
+    install_panic_hook();
+    PANIC_LOCATION.with(|cell| *cell.borrow_mut() = None);
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(rval) => Some(rval),
+        Err(payload) => {
+            let location = PANIC_LOCATION.with(|cell| cell.borrow_mut().take());
+            let message = panic_message(&*payload, location.as_deref());
+            if let Ok(true) = je.exception_check() {
+                let _ = je.exception_clear();
+            }
+            let class_name = panic_exception_class();
+            if let Err(e) = je.throw_new(class_name.as_str(), &message) {
+                log::debug!("failed to throw {} for {}: {:?}", class_name, message, e);
+            }
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{catch_panic, panic_message};
+    use crate::{jni, test_jvm, ConvertJValueToRust};
+    use jni::errors::Error;
+
+    #[test]
+    fn messages() {
+        assert_eq!(
+            "rust panic at src/lib.rs:3:5: boom",
+            panic_message(&"boom", Some("src/lib.rs:3:5"))
+        );
+        assert_eq!(
+            "rust panic: 42 is too big",
+            panic_message(&format!("{} is too big", 42), None)
+        );
+        assert_eq!("rust panic: Box<dyn Any>", panic_message(&42, None));
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn panic_becomes_exception() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        assert_eq!(Some(7), catch_panic(&jni_env, || 7));
+        assert!(!jni_env.exception_check()?);
+
+        let rval: Option<i32> = catch_panic(&jni_env, || panic!("out of {}", "cheese"));
+        assert_eq!(None, rval);
+        assert!(jni_env.exception_check()?);
+        let throwable = jni_env.exception_occurred()?;
+        jni_env.exception_clear()?;
+        assert!(jni_env.is_instance_of(*throwable, "java/lang/RuntimeException")?);
+        let message = jni_env.call_method(*throwable, "getMessage", "()Ljava/lang/String;", &[])?;
+        let message = String::to_rust(&jni_env, message)?;
+        assert!(message.starts_with("rust panic at "), "{}", message);
+        assert!(message.contains("native_method.rs"), "{}", message);
+        assert!(message.ends_with(": out of cheese"), "{}", message);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Calling an ancestor's implementation of a method, the way `super.toString()` does in java.
+//!
+//! `JNIEnv::call_method` always calls the object's own override.  `call_nonvirtual_method()` resolves the
+//! method on a named class instead, and calls that class's implementation through `CallNonvirtual<Type>MethodA`:
+//!
+//! ```no_run
+//! # use jni_old as jni;
+//! use jni::errors::Error;
+//! use jni::objects::JObject;
+//! use jni::JNIEnv;
+//! use jni_boilerplate_helper::nonvirtual::call_nonvirtual_method;
+//! use jni_boilerplate_helper::ConvertJValueToRust;
+//!
+//! /// `Object.toString()`'s answer, even for an object that overrides it
+//! fn identity_string(jni_env: &JNIEnv, object: JObject) -> Result<String, Error> {
+//!     let text = call_nonvirtual_method(
+//!         jni_env,
+//!         object,
+//!         "java/lang/Object",
+//!         "toString",
+//!         "()Ljava/lang/String;",
+//!         &[],
+//!     )?;
+//!     String::to_rust(jni_env, text)
+//! }
+//! ```
+//!
+//! `jni_nonvirtual_method!` and `#[nonvirtual = "..."]` in a `#[jni_class]` generate such calls.
+
+use crate::jni;
+use crate::{java_exception, unwrap_jobject, wrap_jobject};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JClass, JObject, JValue};
+use jni::sys::{jmethodID, jvalue};
+use jni::JNIEnv;
+
+/// Calls `class_name`'s (slashed) implementation of the method `name` with the signature `sig` on `object`,
+/// which must be an instance of that class.  A java exception is left pending, and reported as an `Err`.
+pub fn call_nonvirtual_method<'a>(
+    jni_env: &JNIEnv<'a>,
+    object: JObject,
+    class_name: &str,
+    name: &str,
+    sig: &str,
+    args: &[JValue],
+) -> Result<JValue<'a>, Error> {
+    let class = AutoLocal::new(jni_env, JObject::from(jni_env.find_class(class_name)?));
+    let class = JClass::from(class.as_obj());
+    let method_id = unwrap_method_id(jni_env.get_method_id(class, name, sig)?);
+
+    let raw = jni_env.get_native_interface();
+    let object = unwrap_jobject(object);
+    let class = unwrap_jobject(*class);
+    let args: Vec<jvalue> = args.iter().map(|arg| arg.to_jni()).collect();
+    let args = args.as_ptr();
+
+    macro_rules! call {
+        ($function:ident) => {{
+            let function = unsafe { (**raw).$function }
+                .ok_or_else(|| method_not_found(stringify!($function)))?;
+            unsafe { function(raw, object, class, method_id, args) }
+        }};
+    }
+
+    // the return type is whatever follows the parameters
+    let value = match sig.rsplit(')').next().and_then(|ret| ret.chars().next()) {
+        Some('L') | Some('[') => JValue::Object(wrap_jobject(call!(CallNonvirtualObjectMethodA))),
+        Some('Z') => JValue::Bool(call!(CallNonvirtualBooleanMethodA)),
+        Some('B') => JValue::Byte(call!(CallNonvirtualByteMethodA)),
+        Some('C') => JValue::Char(call!(CallNonvirtualCharMethodA)),
+        Some('S') => JValue::Short(call!(CallNonvirtualShortMethodA)),
+        Some('I') => JValue::Int(call!(CallNonvirtualIntMethodA)),
+        Some('J') => JValue::Long(call!(CallNonvirtualLongMethodA)),
+        Some('F') => JValue::Float(call!(CallNonvirtualFloatMethodA)),
+        Some('D') => JValue::Double(call!(CallNonvirtualDoubleMethodA)),
+        Some('V') => {
+            call!(CallNonvirtualVoidMethodA);
+            JValue::Void
+        }
+        // the JVM found the method, so the signature is well formed
+        _ => unreachable!("no return type in {}", sig),
+    };
+
+    if jni_env.exception_check()? {
+        return Err(java_exception());
+    }
+    Ok(value)
+}
+
+#[cfg(not(feature = "jni_0_20"))]
+fn unwrap_method_id(method_id: jni::objects::JMethodID) -> jmethodID {
+    method_id.into_inner()
+}
+
+#[cfg(feature = "jni_0_20")]
+fn unwrap_method_id(method_id: jni::objects::JMethodID) -> jmethodID {
+    method_id.into_raw()
+}
+
+#[cfg(any(feature = "jni_0_18", feature = "jni_0_20"))]
+fn method_not_found(name: &'static str) -> Error {
+    Error::JNIEnvMethodNotFound(name)
+}
+
+#[cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+fn method_not_found(name: &'static str) -> Error {
+    Error::from_kind(jni::errors::ErrorKind::JNIEnvMethodNotFound(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::call_nonvirtual_method;
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::ConvertJValueToRust;
+    use jni::errors::Error;
+    use jni::objects::{JObject, JValue};
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn ancestors() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+        let text = jni_env.new_string("seven")?;
+        let text = JObject::from(text);
+
+        // String overrides hashCode(), Object's is the identity hash
+        let own = jni_env.call_method(text, "hashCode", "()I", &[])?.i()?;
+        let identity = jni_env
+            .call_static_method(
+                "java/lang/System",
+                "identityHashCode",
+                "(Ljava/lang/Object;)I",
+                &[JValue::from(text)],
+            )?
+            .i()?;
+        let object_hash =
+            call_nonvirtual_method(&jni_env, text, "java/lang/Object", "hashCode", "()I", &[])?;
+        assert_eq!(identity, object_hash.i()?);
+        let string_hash =
+            call_nonvirtual_method(&jni_env, text, "java/lang/String", "hashCode", "()I", &[])?;
+        assert_eq!(own, string_hash.i()?);
+
+        let object_string = call_nonvirtual_method(
+            &jni_env,
+            text,
+            "java/lang/Object",
+            "toString",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        // whose own call to hashCode() is virtual again
+        let object_string = String::to_rust(&jni_env, object_string)?;
+        assert_eq!(format!("java.lang.String@{:x}", own), object_string);
+
+        let found = call_nonvirtual_method(
+            &jni_env,
+            text,
+            "java/lang/String",
+            "indexOf",
+            "(I)I",
+            &[JValue::Int('v' as i32)],
+        )?;
+        assert_eq!(2, found.i()?);
+
+        // an exception comes back as an Err, still pending
+        let thrown = call_nonvirtual_method(
+            &jni_env,
+            text,
+            "java/lang/String",
+            "charAt",
+            "(I)C",
+            &[JValue::Int(99)],
+        );
+        assert!(thrown.is_err());
+        assert!(jni_env.exception_check()?);
+        jni_env.exception_clear()?;
+
+        assert!(call_nonvirtual_method(
+            &jni_env,
+            text,
+            "java/lang/Object",
+            "noSuchMethod",
+            "()V",
+            &[]
+        )
+        .is_err());
+        jni_env.exception_clear()?;
+        Ok(())
+    }
+}
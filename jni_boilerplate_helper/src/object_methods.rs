@@ -0,0 +1,282 @@
+//! `java.lang.Object`'s methods for every wrapper, and `jni_object_traits!` to turn them into
+//! rust's `Clone`, `PartialEq`, `Hash`, `Debug`, `Display` and `Ord`, so wrappers can be map keys
+//! and be printed.
+//!
+//! ```
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::{jni_object_traits, jni_wrapper_cliche_impl};
+//! use std::collections::HashSet;
+//!
+//! jni_wrapper_cliche_impl! { Locale, "java/util/Locale" }
+//! jni_object_traits! { Locale: Clone, PartialEq, Hash, Display }
+//!
+//! fn languages<'a: 'b, 'b>(locales: &[Locale<'a, 'b>]) -> HashSet<Locale<'a, 'b>> {
+//!     locales.iter().cloned().collect()
+//! }
+//! ```
+
+use crate::inheritance::JavaObjectWrapper;
+use crate::jni;
+use crate::native_method::new_local_ref;
+use crate::{ClearIfErr, JavaConstructible};
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JValue};
+use std::cmp::Ordering;
+
+/// `equals`, `hashCode`, `toString` and `compareTo` for any wrapper.
+/// Exceptions they throw are cleared and reported like any binding's, and come back as `Err(JavaException)`.
+/// A null wrapper behaves as `java.util.Objects` would treat a null.
+pub trait JavaObjectMethods<'a: 'b, 'b>: JavaObjectWrapper<'a, 'b> {
+    /// another wrapper for the same object, with a new local reference
+    fn new_reference(&self) -> Result<Self, Error>
+    where
+        Self: JavaConstructible<'a, 'b>,
+    {
+        let jni_env = self.jni_env();
+        let object = self.java_object();
+        let object = if object.is_null() {
+            object
+        } else {
+            new_local_ref(jni_env, object)?
+        };
+        Ok(Self::wrap_jobject(jni_env, AutoLocal::new(jni_env, object)))
+    }
+
+    /// whether both refer to the very same java object (`IsSameObject`), as `==` would in java
+    fn is_same_object<'c, 'd, O: JavaObjectWrapper<'c, 'd>>(&self, other: &O) -> Result<bool, Error>
+    where
+        'c: 'd,
+    {
+        self.jni_env()
+            .is_same_object(self.java_object(), other.java_object())
+    }
+
+    /// `Object.equals`
+    fn java_equals<'c, 'd, O: JavaObjectWrapper<'c, 'd>>(&self, other: &O) -> Result<bool, Error>
+    where
+        'c: 'd,
+    {
+        let jni_env = self.jni_env();
+        if self.java_object().is_null() {
+            return self.is_same_object(other);
+        }
+        jni_env
+            .call_method(
+                self.java_object(),
+                "equals",
+                "(Ljava/lang/Object;)Z",
+                &[JValue::from(other.java_object())],
+            )
+            .and_then(|equal| equal.z())
+            .clear_if_err(jni_env)
+    }
+
+    /// `Object.hashCode`, and 0 for a null
+    fn java_hash_code(&self) -> Result<i32, Error> {
+        let jni_env = self.jni_env();
+        if self.java_object().is_null() {
+            return Ok(0);
+        }
+        jni_env
+            .call_method(self.java_object(), "hashCode", "()I", &[])
+            .and_then(|hash| hash.i())
+            .clear_if_err(jni_env)
+    }
+
+    /// `Object.toString`, and `"null"` for a null
+    fn java_to_string(&self) -> Result<String, Error> {
+        let jni_env = self.jni_env();
+        if self.java_object().is_null() {
+            return Ok(String::from("null"));
+        }
+        jni_env
+            .call_method(self.java_object(), "toString", "()Ljava/lang/String;", &[])
+            .and_then(|string| string.l())
+            .and_then(|string| {
+                let string = AutoLocal::new(jni_env, string);
+                let rval = jni_env.get_string(string.as_obj().into()).map(String::from);
+                rval
+            })
+            .clear_if_err(jni_env)
+    }
+
+    /// `Comparable.compareTo`, for classes that implement it.  A null sorts before everything else.
+    fn java_compare_to<'c, 'd, O: JavaObjectWrapper<'c, 'd>>(
+        &self,
+        other: &O,
+    ) -> Result<Ordering, Error>
+    where
+        'c: 'd,
+    {
+        let jni_env = self.jni_env();
+        match (self.java_object().is_null(), other.java_object().is_null()) {
+            (true, true) => return Ok(Ordering::Equal),
+            (true, false) => return Ok(Ordering::Less),
+            (false, true) => return Ok(Ordering::Greater),
+            (false, false) => {}
+        }
+        jni_env
+            .call_method(
+                self.java_object(),
+                "compareTo",
+                "(Ljava/lang/Object;)I",
+                &[JValue::from(other.java_object())],
+            )
+            .and_then(|order| order.i())
+            .map(|order| order.cmp(&0))
+            .clear_if_err(jni_env)
+    }
+}
+
+impl<'a: 'b, 'b, W: JavaObjectWrapper<'a, 'b>> JavaObjectMethods<'a, 'b> for W {}
+
+/// Implements rust traits for a wrapper from its java object's methods.
+///
+/// <pre>jni_object_traits! { Wrapper: Trait, ... }</pre>
+///
+/// * `Clone` makes a new local reference to the same object (a derived wrapper's other fields start over from `Default`)
+/// * `PartialEq` is `Object.equals`, and brings `Eq` along; `is_same_object()` is the identity check
+/// * `Hash` is `hashCode()`, which java keeps consistent with `equals`
+/// * `Debug` shows the rust type and `toString()`, `Display` is just `toString()`
+/// * `Ord` is `Comparable.compareTo`, for classes that implement it, and brings `PartialOrd` along; it needs `PartialEq` too
+///
+/// The traits can not return errors, so a java call that fails panics (after the exception has been reported),
+/// except in `Debug` and `Display`, which return `fmt::Error`.
+/// The wrapper must have the usual two lifetime parameters.
+#[macro_export]
+macro_rules! jni_object_traits {
+    ($ty:ident: $($traits:ident),+ $(,)?) => {
+        $( $crate::jni_object_traits! { @$traits $ty } )+
+    };
+    (@Clone $ty:ident) => {
+        impl<'a: 'b, 'b> Clone for $ty<'a, 'b> {
+            fn clone(&self) -> Self {
+                $crate::object_methods::JavaObjectMethods::new_reference(self)
+                    .expect("failed to make a new local reference")
+            }
+        }
+    };
+    (@PartialEq $ty:ident) => {
+        impl<'a: 'b, 'b> PartialEq for $ty<'a, 'b> {
+            fn eq(&self, other: &Self) -> bool {
+                $crate::object_methods::JavaObjectMethods::java_equals(self, other)
+                    .expect("Object.equals() failed")
+            }
+        }
+
+        impl<'a: 'b, 'b> Eq for $ty<'a, 'b> {}
+    };
+    (@Hash $ty:ident) => {
+        impl<'a: 'b, 'b> std::hash::Hash for $ty<'a, 'b> {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                let hash = $crate::object_methods::JavaObjectMethods::java_hash_code(self)
+                    .expect("Object.hashCode() failed");
+                std::hash::Hash::hash(&hash, state)
+            }
+        }
+    };
+    (@Debug $ty:ident) => {
+        impl<'a: 'b, 'b> std::fmt::Debug for $ty<'a, 'b> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                let string = $crate::object_methods::JavaObjectMethods::java_to_string(self)
+                    .map_err(|_| std::fmt::Error)?;
+                f.debug_tuple(stringify!($ty)).field(&string).finish()
+            }
+        }
+    };
+    (@Display $ty:ident) => {
+        impl<'a: 'b, 'b> std::fmt::Display for $ty<'a, 'b> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                let string = $crate::object_methods::JavaObjectMethods::java_to_string(self)
+                    .map_err(|_| std::fmt::Error)?;
+                f.write_str(&string)
+            }
+        }
+    };
+    (@Ord $ty:ident) => {
+        impl<'a: 'b, 'b> PartialOrd for $ty<'a, 'b> {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl<'a: 'b, 'b> Ord for $ty<'a, 'b> {
+            fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+                $crate::object_methods::JavaObjectMethods::java_compare_to(self, other)
+                    .expect("Comparable.compareTo() failed")
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::JavaObjectMethods;
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::{jni_wrapper_cliche_impl, JavaConstructible};
+    use jni::errors::Error;
+    use jni::objects::{AutoLocal, JValue};
+    use jni::JNIEnv;
+    use std::collections::{BTreeSet, HashMap};
+
+    jni_wrapper_cliche_impl! { JavaString, "java/lang/String" }
+    jni_object_traits! { JavaString: Clone, PartialEq, Hash, Debug, Display, Ord }
+
+    fn string<'a: 'b, 'b>(
+        jni_env: &'b JNIEnv<'a>,
+        text: &str,
+    ) -> Result<JavaString<'a, 'b>, Error> {
+        let text = jni_env.new_string(text)?;
+        let copy = jni_env.new_object(
+            "java/lang/String",
+            "(Ljava/lang/String;)V",
+            &[JValue::from(*text)],
+        )?;
+        Ok(JavaString::wrap_jobject(
+            jni_env,
+            AutoLocal::new(jni_env, copy),
+        ))
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn equality_and_identity() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let one = string(&jni_env, "one")?;
+        let another_one = string(&jni_env, "one")?;
+        assert_eq!(one, another_one);
+        assert!(!one.is_same_object(&another_one)?);
+
+        let copy = one.clone();
+        assert!(copy.is_same_object(&one)?);
+        drop(one);
+        assert_eq!("one", copy.to_string());
+        assert_eq!("JavaString(\"one\")", format!("{:?}", copy));
+
+        let null = JavaString::null(&jni_env);
+        assert_ne!(copy, null);
+        assert_eq!(null, JavaString::null(&jni_env));
+        assert_eq!("null", null.to_string());
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn keys() -> Result<(), Error> {
+        let jni_env = test_jvm::java_vm().attach_current_thread()?;
+
+        let mut counts = HashMap::new();
+        for word in ["b", "a", "b", "c", "b"] {
+            *counts.entry(string(&jni_env, word)?).or_insert(0) += 1;
+        }
+        assert_eq!(Some(&3), counts.get(&string(&jni_env, "b")?));
+        assert_eq!(3, counts.len());
+
+        let sorted: BTreeSet<JavaString> = counts.into_keys().collect();
+        let sorted: Vec<String> = sorted.iter().map(|word| word.to_string()).collect();
+        assert_eq!(vec!["a", "b", "c"], sorted);
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+//! Rust names for overloaded java methods, derived from their parameter descriptors so that
+//! every binding of the same class comes out the same way.
+//!
+//! Used by `#[jni_class]` and by `jni_boilerplate_bindgen`.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How the overloads of a java method are told apart in rust.
+/// A method that is not overloaded keeps its own name under every policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadNaming {
+    /// overloads must be named by hand; finding one is an error
+    Explicit,
+    /// a suffix per parameter type: `echo_int`, `echo_string`, `sum_int_array`, `add_int_int`.
+    /// The overload without parameters keeps the bare name.
+    ParameterTypes,
+    /// the parameter count: `echo_1`, `echo_2`.  The overload without parameters keeps the bare name.
+    Arity,
+}
+
+impl FromStr for OverloadNaming {
+    type Err = String;
+
+    /// `explicit`, `types` or `arity`
+    fn from_str(name: &str) -> Result<OverloadNaming, String> {
+        match name {
+            "explicit" => Ok(OverloadNaming::Explicit),
+            "types" => Ok(OverloadNaming::ParameterTypes),
+            "arity" => Ok(OverloadNaming::Arity),
+            _ => Err(format!(
+                "unknown overload naming {:?}; expected \"explicit\", \"types\" or \"arity\"",
+                name
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OverloadNaming {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OverloadNaming::Explicit => "explicit",
+            OverloadNaming::ParameterTypes => "types",
+            OverloadNaming::Arity => "arity",
+        })
+    }
+}
+
+/// The suffix for one parameter's field descriptor: `I` is `int`, `Ljava/lang/String;` is `string`,
+/// `Lcom/foo/Outer$InnerThing;` is `inner_thing`, `[[J` is `long_array_array`.
+pub fn type_suffix(descriptor: &str) -> String {
+    if let Some(element) = descriptor.strip_prefix('[') {
+        return type_suffix(element) + "_array";
+    }
+    let primitive = match descriptor {
+        "Z" => "boolean",
+        "B" => "byte",
+        "C" => "char",
+        "S" => "short",
+        "I" => "int",
+        "J" => "long",
+        "F" => "float",
+        "D" => "double",
+        _ => "",
+    };
+    if !primitive.is_empty() {
+        return String::from(primitive);
+    }
+    let class = descriptor
+        .strip_prefix('L')
+        .unwrap_or(descriptor)
+        .trim_end_matches(';');
+    let simple = class.rsplit(['/', '$']).next().unwrap_or(class);
+    snake_case(simple)
+}
+
+/// `HashMap` is `hash_map`, `URLConnection` is `url_connection`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut rval = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let after_lower = i > 0 && chars[i - 1].is_lowercase();
+            let ends_acronym = i > 0
+                && chars[i - 1].is_uppercase()
+                && chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if after_lower || ends_acronym {
+                rval.push('_');
+            }
+            rval.extend(c.to_lowercase());
+        } else {
+            rval.push(c);
+        }
+    }
+    rval
+}
+
+/// the field descriptors of a method descriptor's parameters: `(I[JLjava/lang/String;)V` gives `I`, `[J` and `Ljava/lang/String;`
+pub fn parameter_descriptors(method_descriptor: &str) -> Vec<&str> {
+    let arguments = method_descriptor
+        .strip_prefix('(')
+        .and_then(|rest| rest.split(')').next())
+        .unwrap_or(method_descriptor);
+    let mut rval = Vec::new();
+    let mut rest = arguments;
+    while !rest.is_empty() {
+        let dimensions = rest.len() - rest.trim_start_matches('[').len();
+        let length = match rest[dimensions..].chars().next() {
+            Some('L') => rest.find(';').map_or(rest.len(), |end| end + 1),
+            Some(_) => dimensions + 1,
+            None => rest.len(),
+        };
+        rval.push(&rest[..length]);
+        rest = &rest[length..];
+    }
+    rval
+}
+
+/// The rust name of one overload of `base` under `naming`; `None` under `OverloadNaming::Explicit`.
+pub fn overload_name<S: AsRef<str>>(
+    base: &str,
+    parameters: &[S],
+    naming: OverloadNaming,
+) -> Option<String> {
+    if parameters.is_empty() && naming != OverloadNaming::Explicit {
+        return Some(String::from(base));
+    }
+    match naming {
+        OverloadNaming::Explicit => None,
+        OverloadNaming::ParameterTypes => {
+            let mut rval = String::from(base);
+            for parameter in parameters {
+                rval.push('_');
+                rval.push_str(&type_suffix(parameter.as_ref()));
+            }
+            Some(rval)
+        }
+        OverloadNaming::Arity => Some(format!("{}_{}", base, parameters.len())),
+    }
+}
+
+/// Names every overload of `base`, given the parameter descriptors of each.
+/// A lone method keeps `base`.  Fails, with a message naming the overloads, when the policy gives
+/// two of them the same name or the policy is `Explicit`.
+pub fn overload_names<S: AsRef<str>>(
+    base: &str,
+    overloads: &[Vec<S>],
+    naming: OverloadNaming,
+) -> Result<Vec<String>, String> {
+    if overloads.len() == 1 {
+        return Ok(vec![String::from(base)]);
+    }
+    let describe = |parameters: &Vec<S>| {
+        let parameters: Vec<&str> = parameters.iter().map(|p| p.as_ref()).collect();
+        format!("{}({})", base, parameters.join(""))
+    };
+
+    let mut rval: Vec<String> = Vec::with_capacity(overloads.len());
+    for parameters in overloads {
+        let name = overload_name(base, parameters, naming).ok_or_else(|| {
+            format!(
+                "{} is overloaded; name each overload explicitly or choose an overload naming policy",
+                base
+            )
+        })?;
+        if let Some(earlier) = rval.iter().position(|other| *other == name) {
+            return Err(format!(
+                "{} and {} would both be named {} under the {} overload naming policy",
+                describe(&overloads[earlier]),
+                describe(parameters),
+                name,
+                naming
+            ));
+        }
+        rval.push(name);
+    }
+    Ok(rval)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{overload_names, parameter_descriptors, type_suffix, OverloadNaming};
+
+    #[test]
+    fn suffixes() {
+        assert_eq!("int", type_suffix("I"));
+        assert_eq!("boolean", type_suffix("Z"));
+        assert_eq!("string", type_suffix("Ljava/lang/String;"));
+        assert_eq!("hash_map", type_suffix("Ljava/util/HashMap;"));
+        assert_eq!("url_connection", type_suffix("Ljava/net/URLConnection;"));
+        assert_eq!("inner_thing", type_suffix("Lcom/foo/Outer$InnerThing;"));
+        assert_eq!("int_array", type_suffix("[I"));
+        assert_eq!("string_array_array", type_suffix("[[Ljava/lang/String;"));
+        // the macros hand over rust type names in the same shape
+        assert_eq!("widget", type_suffix("LWidget;"));
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(
+            vec!["I", "[J", "Ljava/lang/String;", "[[Lcom/foo/Bar;", "Z"],
+            parameter_descriptors("(I[JLjava/lang/String;[[Lcom/foo/Bar;Z)V")
+        );
+        assert!(parameter_descriptors("()V").is_empty());
+        assert_eq!(vec!["C", "D"], parameter_descriptors("CD"));
+    }
+
+    #[test]
+    fn by_parameter_types() {
+        let echo = vec![vec!["I"], vec!["Ljava/lang/String;"], vec!["C"], vec![]];
+        assert_eq!(
+            vec!["echo_int", "echo_string", "echo_char", "echo"],
+            overload_names("echo", &echo, OverloadNaming::ParameterTypes).unwrap()
+        );
+        let add = vec![vec!["I", "I"], vec!["[I"]];
+        assert_eq!(
+            vec!["add_int_int", "add_int_array"],
+            overload_names("add", &add, OverloadNaming::ParameterTypes).unwrap()
+        );
+        // different classes with the same simple name
+        let take = vec![vec!["Lcom/a/Node;"], vec!["Lcom/b/Node;"]];
+        let message = overload_names("take", &take, OverloadNaming::ParameterTypes).unwrap_err();
+        assert_eq!(
+            "take(Lcom/a/Node;) and take(Lcom/b/Node;) would both be named take_node under the types overload naming policy",
+            message
+        );
+    }
+
+    #[test]
+    fn by_arity() {
+        let put = vec![vec!["I"], vec!["I", "J"], vec![]];
+        assert_eq!(
+            vec!["put_1", "put_2", "put"],
+            overload_names("put", &put, OverloadNaming::Arity).unwrap()
+        );
+        let echo = vec![vec!["I"], vec!["C"]];
+        assert!(overload_names("echo", &echo, OverloadNaming::Arity).is_err());
+    }
+
+    #[test]
+    fn explicit() {
+        let lone: Vec<Vec<&str>> = vec![vec!["I"]];
+        assert_eq!(
+            vec!["echo"],
+            overload_names("echo", &lone, OverloadNaming::Explicit).unwrap()
+        );
+        let echo = vec![vec!["I"], vec!["C"]];
+        assert!(overload_names("echo", &echo, OverloadNaming::Explicit).is_err());
+        assert_eq!(Ok(OverloadNaming::Arity), "arity".parse());
+        assert!("numbers".parse::<OverloadNaming>().is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! Support code for the `jni_register_natives!` macro, which binds rust functions to java `native` methods
+//! with `RegisterNatives` from a generated `JNI_OnLoad` instead of relying on exported `Java_...` symbols.
+
+use crate::jni;
+use crate::native_method::{catch_panic, throw_for_native_error};
+use crate::thread_env;
+use jni::errors::Error;
+use jni::sys::{jint, JNI_ERR, JNI_VERSION_1_6};
+use jni::{JNIEnv, JavaVM};
+use std::panic::{self, AssertUnwindSafe};
+
+/// The `JavaVM` that loaded this library, once the generated `JNI_OnLoad` has run.
+/// Use it to get a `JNIEnv` on threads java did not call you from, or see `thread_env`.
+pub fn java_vm() -> Option<&'static JavaVM> {
+    thread_env::java_vm()
+}
+
+/// The body of the generated `JNI_OnLoad`.
+/// Stores the `JavaVM`, then hands the current thread's `JNIEnv` to `register`,
+/// which registers the native methods and runs the user's init hook.
+///
+/// If `register` fails, a pending java exception (e.g. the `NoSuchMethodError` from `RegisterNatives`)
+/// is left for `System.loadLibrary` to throw; otherwise a `java.lang.RuntimeException` is thrown.
+/// A panic becomes an exception too, see `native_method::catch_panic()`.
+///
+/// # Safety
+///
+/// `vm` must be the `JavaVM` pointer the JVM passed to `JNI_OnLoad`.
+pub unsafe fn on_load<F>(vm: *mut jni::sys::JavaVM, register: F) -> jint
+where
+    F: FnOnce(&JNIEnv) -> Result<(), Error>,
+{
+    let vm = match JavaVM::from_raw(vm) {
+        Ok(vm) => vm,
+        Err(e) => {
+            log::debug!("JNI_OnLoad got an unusable JavaVM: {:?}", e);
+            return JNI_ERR;
+        }
+    };
+    let vm = thread_env::set_java_vm(vm);
+    let jni_env = match vm.get_env() {
+        Ok(jni_env) => jni_env,
+        Err(e) => {
+            log::debug!("JNI_OnLoad could not get a JNIEnv: {:?}", e);
+            return JNI_ERR;
+        }
+    };
+    match catch_panic(&jni_env, || register(&jni_env)) {
+        Some(Ok(())) => JNI_VERSION_1_6,
+        Some(Err(e)) => {
+            throw_for_native_error(&jni_env, &e);
+            JNI_ERR
+        }
+        None => JNI_ERR,
+    }
+}
+
+/// The body of the generated `JNI_OnUnload`, which only exists when the macro was given a shutdown hook.
+///
+/// # Safety
+///
+/// `vm` must be the `JavaVM` pointer the JVM passed to `JNI_OnUnload`.
+pub unsafe fn on_unload<F>(vm: *mut jni::sys::JavaVM, shutdown: F)
+where
+    F: FnOnce(&JavaVM),
+{
+    // there may be no JNIEnv to throw on by now, so a panic is only logged
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| match java_vm() {
+        Some(vm) => shutdown(vm),
+        None => match JavaVM::from_raw(vm) {
+            Ok(vm) => shutdown(&vm),
+            Err(e) => log::debug!("JNI_OnUnload got an unusable JavaVM: {:?}", e),
+        },
+    }));
+    if outcome.is_err() {
+        log::error!("the JNI_OnUnload shutdown hook panicked");
+    }
+}
@@ -0,0 +1,51 @@
+//! The JVM shared by the tests that need one.
+//!
+//! Those tests are marked `#[ignore = "needs a JVM"]`, so a plain `cargo test` runs the rest without a JDK.
+//! To run them as well, point `JAVA_HOME` at a JDK and use `cargo test -- --include-ignored`.
+//! Without `JAVA_HOME` they fail rather than pass without testing anything.
+//! The other crates' integration tests get this module through the `test_jvm` feature.
+
+use crate::jni;
+use crate::launch::JvmConfig;
+use jni::JavaVM;
+
+/// The JVM, started with `-Xcheck:jni` by whichever test asks for it first,
+/// and registered with `thread_env::set_java_vm()`.
+/// With the `leak_check` feature it goes without `-Xcheck:jni`, which would keep local references from being counted.
+/// Panics if it can not be started, which includes `JAVA_HOME` not being set.
+pub fn java_vm() -> &'static JavaVM {
+    let config = JvmConfig::new();
+    let config = if cfg!(feature = "leak_check") {
+        config
+    } else {
+        config.check_jni()
+    };
+    config
+        .launch()
+        .unwrap_or_else(|e| panic!("unable to start the JVM for the tests: {}", e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::java_vm;
+    use crate::{jni, ConvertJValueToRust};
+    use jni::objects::JValue;
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn shared() -> Result<(), jni::errors::Error> {
+        let jni_env = java_vm().attach_current_thread()?;
+        let text = jni_env.call_static_method(
+            "java/lang/String",
+            "valueOf",
+            "(I)Ljava/lang/String;",
+            &[JValue::Int(42)],
+        )?;
+        assert_eq!("42", String::to_rust(&jni_env, text)?);
+        assert_eq!(
+            java_vm().get_java_vm_pointer(),
+            jni_env.get_java_vm()?.get_java_vm_pointer()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! The process's `JavaVM`, and the current thread's `JNIEnv` without passing one around.
+//!
+//! The generated `JNI_OnLoad` of `jni_register_natives!` registers the `JavaVM` that loaded the library,
+//! and `launch::JvmConfig` the JVM it starts; one started some other way is given to `set_java_vm()`.
+//! After that any thread can call into java:
+//!
+//! ```no_run
+//! # use jni_old as jni;
+//! use jni_boilerplate_helper::thread_env;
+//!
+//! std::thread::spawn(|| {
+//!     thread_env::with_daemon_env("rust-worker", |jni_env| {
+//!         jni_env.call_static_method("java/lang/System", "gc", "()V", &[])?;
+//!         Ok(())
+//!     })
+//! });
+//! ```
+//!
+//! Threads are attached permanently: they stay attached, and keep the same `JNIEnv`, until they exit,
+//! so `current_env()` can lend theirs out for good.  A thread that was attached already, by java or by other
+//! native code, may be detached again behind our back; `Attachment::env()` and `with_env()` only lend its env
+//! for a while, and `current_env()` refuses it.
+//! `#[implicit_env]` static methods and constructors of a `#[jni_class]` use `current_env()` instead of a
+//! `jni_env` argument.
+
+use crate::jni;
+use jni::errors::Error;
+use jni::objects::{AutoLocal, JValue};
+use jni::{JNIEnv, JavaVM};
+use std::cell::Cell;
+use std::ops::Deref;
+use std::sync::OnceLock;
+use std::thread;
+
+static JAVA_VM: OnceLock<JavaVM> = OnceLock::new();
+
+thread_local! {
+    /// this thread's env, if this module attached it, leaked so that it can be lent out until the thread exits
+    static ATTACHED_ENV: Cell<Option<&'static JNIEnv<'static>>> = const { Cell::new(None) };
+}
+
+/// Registers the process's `JavaVM`, unless one already is (there is only ever one JVM in a process),
+/// and returns the registered one.
+pub fn set_java_vm(java_vm: JavaVM) -> &'static JavaVM {
+    JAVA_VM.get_or_init(|| java_vm)
+}
+
+/// the registered `JavaVM`, if there is one yet
+pub fn java_vm() -> Option<&'static JavaVM> {
+    JAVA_VM.get()
+}
+
+/// How to attach the current thread, if it is not attached already.
+/// A thread that already is, such as one java called a native method on, is left as it is.
+#[derive(Debug, Clone, Default)]
+pub struct Attachment {
+    daemon: bool,
+    name: Option<String>,
+}
+
+impl Attachment {
+    /// a permanent attachment as an ordinary thread, which keeps the JVM from exiting until it ends
+    pub fn new() -> Attachment {
+        Attachment::default()
+    }
+
+    /// a daemon thread, which does not keep the JVM from exiting
+    pub fn daemon(mut self) -> Attachment {
+        self.daemon = true;
+        self
+    }
+
+    /// The name of the thread in java, for stack dumps and the like.
+    /// By default it is the rust thread's name, if it has one.
+    pub fn name(mut self, name: &str) -> Attachment {
+        self.name = Some(String::from(name));
+        self
+    }
+
+    /// the current thread's env, attaching the thread first if need be
+    pub fn env(&self) -> Result<ThreadEnv<'_>, Error> {
+        let java_vm = java_vm().ok_or_else(no_java_vm)?;
+        if let Ok(jni_env) = java_vm.get_env() {
+            // a thread we attached, unless somebody detached it and attached it again
+            return Ok(match ATTACHED_ENV.with(Cell::get) {
+                Some(attached)
+                    if attached.get_native_interface() == jni_env.get_native_interface() =>
+                {
+                    ThreadEnv::Permanent(attached)
+                }
+                _ => ThreadEnv::Borrowed(jni_env),
+            });
+        }
+
+        let jni_env = if self.daemon {
+            java_vm.attach_current_thread_as_daemon()?
+        } else {
+            java_vm.attach_current_thread_permanently()?
+        };
+        let name = self
+            .name
+            .clone()
+            .or_else(|| thread::current().name().map(String::from));
+        if let Some(name) = name {
+            set_thread_name(&jni_env, &name)?;
+        }
+        let jni_env: &'static JNIEnv<'static> = Box::leak(Box::new(jni_env));
+        ATTACHED_ENV.with(|attached| attached.set(Some(jni_env)));
+        Ok(ThreadEnv::Permanent(jni_env))
+    }
+
+    /// Calls `f` with the current thread's env, attaching the thread first if need be.
+    pub fn with_env<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&JNIEnv) -> Result<R, Error>,
+    {
+        f(&*self.env()?)
+    }
+}
+
+/// The current thread's env, as `Attachment::env()` finds it
+pub enum ThreadEnv<'e> {
+    /// this module attached the thread, permanently, so the env lasts until the thread exits
+    Permanent(&'static JNIEnv<'static>),
+    /// The thread was attached already, by java or by other native code that may detach it again,
+    /// so the env is only lent while the `Attachment` is borrowed.
+    Borrowed(JNIEnv<'e>),
+}
+
+impl ThreadEnv<'_> {
+    /// the env for as long as the thread lives, if this module attached the thread
+    pub fn permanent(&self) -> Option<&'static JNIEnv<'static>> {
+        match self {
+            ThreadEnv::Permanent(jni_env) => Some(jni_env),
+            ThreadEnv::Borrowed(_) => None,
+        }
+    }
+}
+
+impl<'e> Deref for ThreadEnv<'e> {
+    type Target = JNIEnv<'e>;
+
+    fn deref(&self) -> &JNIEnv<'e> {
+        match self {
+            ThreadEnv::Permanent(jni_env) => jni_env,
+            ThreadEnv::Borrowed(jni_env) => jni_env,
+        }
+    }
+}
+
+/// The current thread's env, attaching it permanently if it is not attached yet.
+/// Fails if no `JavaVM` has been registered, or if the thread was attached by something else,
+/// which could detach it while the env is still in use.
+pub fn current_env() -> Result<&'static JNIEnv<'static>, Error> {
+    Attachment::new()
+        .env()?
+        .permanent()
+        .ok_or_else(attached_elsewhere)
+}
+
+/// `f` with the current thread's env, attaching it permanently if it is not attached yet
+pub fn with_env<F, R>(f: F) -> Result<R, Error>
+where
+    F: FnOnce(&JNIEnv) -> Result<R, Error>,
+{
+    Attachment::new().with_env(f)
+}
+
+/// `f` with the current thread's env, attaching it as a daemon called `name` if it is not attached yet
+pub fn with_daemon_env<F, R>(name: &str, f: F) -> Result<R, Error>
+where
+    F: FnOnce(&JNIEnv) -> Result<R, Error>,
+{
+    Attachment::new().daemon().name(name).with_env(f)
+}
+
+fn set_thread_name(jni_env: &JNIEnv, name: &str) -> Result<(), Error> {
+    let thread = jni_env
+        .call_static_method(
+            "java/lang/Thread",
+            "currentThread",
+            "()Ljava/lang/Thread;",
+            &[],
+        )?
+        .l()?;
+    let thread = AutoLocal::new(jni_env, thread);
+    let name = AutoLocal::new(jni_env, *jni_env.new_string(name)?);
+    jni_env.call_method(
+        thread.as_obj(),
+        "setName",
+        "(Ljava/lang/String;)V",
+        &[JValue::from(name.as_obj())],
+    )?;
+    Ok(())
+}
+
+#[cfg(any(feature = "jni_0_18", feature = "jni_0_20"))]
+fn no_java_vm() -> Error {
+    Error::NullPtr("no JavaVM has been registered with thread_env::set_java_vm()")
+}
+
+#[cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+fn no_java_vm() -> Error {
+    Error::from_kind(jni::errors::ErrorKind::NullPtr(
+        "no JavaVM has been registered with thread_env::set_java_vm()",
+    ))
+}
+
+#[cfg(any(feature = "jni_0_18", feature = "jni_0_20"))]
+fn attached_elsewhere() -> Error {
+    Error::NullPtr("the thread was not attached by thread_env, so its env can only be borrowed")
+}
+
+#[cfg(not(any(feature = "jni_0_18", feature = "jni_0_20")))]
+fn attached_elsewhere() -> Error {
+    Error::from_kind(jni::errors::ErrorKind::NullPtr(
+        "the thread was not attached by thread_env, so its env can only be borrowed",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{current_env, with_daemon_env, with_env, Attachment};
+    use crate::jni;
+    use crate::test_jvm;
+    use crate::ConvertJValueToRust;
+    use jni::errors::Error;
+    use std::thread;
+
+    fn java_thread_name(jni_env: &jni::JNIEnv) -> Result<String, Error> {
+        let thread = jni_env
+            .call_static_method(
+                "java/lang/Thread",
+                "currentThread",
+                "()Ljava/lang/Thread;",
+                &[],
+            )?
+            .l()?;
+        let name = jni_env.call_method(thread, "getName", "()Ljava/lang/String;", &[])?;
+        jni_env.delete_local_ref(thread)?;
+        String::to_rust(jni_env, name)
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn worker_threads() -> Result<(), Error> {
+        test_jvm::java_vm();
+
+        let name = thread::spawn(|| with_daemon_env("rust-worker", java_thread_name))
+            .join()
+            .unwrap()?;
+        assert_eq!("rust-worker", name);
+
+        let (name, same_env) = thread::Builder::new()
+            .name(String::from("named-in-rust"))
+            .spawn(|| {
+                let name = with_env(java_thread_name)?;
+                let first = current_env()?;
+                Ok::<_, Error>((name, std::ptr::eq(first, current_env()?)))
+            })
+            .unwrap()
+            .join()
+            .unwrap()?;
+        assert_eq!("named-in-rust", name);
+        assert!(same_env);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "needs a JVM"]
+    fn attached_elsewhere() -> Result<(), Error> {
+        let java_vm = test_jvm::java_vm();
+
+        thread::spawn(move || {
+            // attached for the guard's scope only, so its env is lent rather than kept
+            let guard = java_vm.attach_current_thread()?;
+            let attachment = Attachment::new();
+            let jni_env = attachment.env()?;
+            assert!(jni_env.permanent().is_none());
+            assert_eq!(guard.get_native_interface(), jni_env.get_native_interface());
+            assert!(current_env().is_err());
+            assert_eq!(
+                "rust-attached",
+                with_env(|jni_env| {
+                    String::to_rust(jni_env, (*jni_env.new_string("rust-attached")?).into())
+                })?
+            );
+            Ok::<_, Error>(())
+        })
+        .join()
+        .unwrap()
+    }
+}
@@ -0,0 +1,23 @@
+package com.purplefrog.jni_boilerplate;
+
+/**
+ * A main class for the run_main() test in src/launch.rs, which defines it at run time
+ * from the compiled class next to this file.
+ * <p>
+ * After editing this file, recompile it with
+ * <pre>javac --release 8 -d tests/java tests/java/com/purplefrog/jni_boilerplate/RecordingMain.java</pre>
+ * from the jni_boilerplate_helper directory.
+ */
+public final class RecordingMain
+{
+    /** the arguments of the last main() that returned, joined with spaces */
+    public static String lastArgs;
+
+    public static void main(String[] args)
+    {
+        if (args.length > 0 && args[0].equals("fail")) {
+            throw new IllegalArgumentException("asked to fail");
+        }
+        lastArgs = String.join(" ", args);
+    }
+}